@@ -1,8 +1,210 @@
 fn main() {
     windows::build! {
+      Windows::Win32::Storage::FileSystem::GetLogicalDriveStringsW,
       Windows::Win32::Storage::FileSystem::GetLogicalDrives,
       Windows::Win32::Storage::FileSystem::GetVolumeInformationW,
       Windows::Win32::Storage::FileSystem::GetDriveTypeW,
-      Windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW
+      Windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW,
+      Windows::Win32::Storage::FileSystem::GetDiskFreeSpaceW,
+      Windows::Win32::Storage::FileSystem::FindFirstVolumeW,
+      Windows::Win32::Storage::FileSystem::FindNextVolumeW,
+      Windows::Win32::Storage::FileSystem::FindVolumeClose,
+      Windows::Win32::Storage::FileSystem::GetVolumePathNamesForVolumeNameW,
+      Windows::Win32::Storage::FileSystem::SetVolumeLabelW,
+      Windows::Win32::Storage::FileSystem::GetVolumeNameForVolumeMountPointW,
+      Windows::Win32::Storage::FileSystem::GetVolumePathNameW,
+      Windows::Win32::Storage::FileSystem::QueryDosDeviceW,
+      Windows::Win32::Storage::FileSystem::SetVolumeMountPointW,
+      Windows::Win32::Storage::FileSystem::DeleteVolumeMountPointW,
+      Windows::Win32::Storage::FileSystem::CreateFileW,
+      Windows::Win32::Storage::FileSystem::VOLUME_DISK_EXTENTS,
+      Windows::Win32::Storage::FileSystem::DISK_GEOMETRY_EX,
+      Windows::Win32::Storage::FileSystem::DRIVE_LAYOUT_INFORMATION_EX,
+      Windows::Win32::Storage::FileSystem::STORAGE_PROPERTY_QUERY,
+      Windows::Win32::Storage::FileSystem::STORAGE_DEVICE_DESCRIPTOR,
+      Windows::Win32::Storage::FileSystem::DEVICE_SEEK_PENALTY_DESCRIPTOR,
+      Windows::Win32::Storage::FileSystem::DEVICE_TRIM_DESCRIPTOR,
+      Windows::Win32::Storage::FileSystem::STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR,
+      Windows::Win32::Storage::FileSystem::NTFS_VOLUME_DATA_BUFFER,
+      Windows::Win32::Foundation::CloseHandle,
+      Windows::Win32::NetworkManagement::WNet::WNetGetConnectionW,
+      Windows::Win32::System::SystemServices::DeviceIoControl,
+      Windows::Win32::System::SystemServices::IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DRIVE_GEOMETRY_EX,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+      Windows::Win32::System::SystemServices::IOCTL_STORAGE_QUERY_PROPERTY,
+      Windows::Win32::System::SystemServices::FSCTL_GET_NTFS_VOLUME_DATA,
+      Windows::Win32::System::SystemServices::FSCTL_IS_VOLUME_DIRTY,
+      Windows::Win32::System::SystemServices::VOLUME_IS_DIRTY,
+      Windows::Win32::System::SystemServices::IOCTL_STORAGE_EJECT_MEDIA,
+      Windows::Win32::System::SystemServices::IOCTL_STORAGE_MEDIA_REMOVAL,
+      Windows::Win32::System::SystemServices::PREVENT_MEDIA_REMOVAL,
+      Windows::Win32::System::SystemServices::FSCTL_LOCK_VOLUME,
+      Windows::Win32::System::SystemServices::FSCTL_UNLOCK_VOLUME,
+      Windows::Win32::System::SystemServices::FSCTL_DISMOUNT_VOLUME,
+      Windows::Win32::System::SystemServices::DEV_BROADCAST_HDR,
+      Windows::Win32::System::SystemServices::DEV_BROADCAST_VOLUME,
+      Windows::Win32::System::SystemServices::DBT_DEVICEARRIVAL,
+      Windows::Win32::System::SystemServices::DBT_DEVICEREMOVECOMPLETE,
+      Windows::Win32::System::LibraryLoader::GetModuleHandleW,
+      Windows::Win32::UI::WindowsAndMessaging::RegisterClassW,
+      Windows::Win32::UI::WindowsAndMessaging::CreateWindowExW,
+      Windows::Win32::UI::WindowsAndMessaging::DefWindowProcW,
+      Windows::Win32::UI::WindowsAndMessaging::DestroyWindow,
+      Windows::Win32::UI::WindowsAndMessaging::GetMessageW,
+      Windows::Win32::UI::WindowsAndMessaging::TranslateMessage,
+      Windows::Win32::UI::WindowsAndMessaging::DispatchMessageW,
+      Windows::Win32::UI::WindowsAndMessaging::PostMessageW,
+      Windows::Win32::UI::WindowsAndMessaging::PostQuitMessage,
+      Windows::Win32::UI::WindowsAndMessaging::WM_DEVICECHANGE,
+      Windows::Win32::UI::WindowsAndMessaging::WM_CLOSE,
+      Windows::Win32::UI::WindowsAndMessaging::WM_DESTROY,
+      Windows::Win32::Storage::FileSystem::IDiskQuotaControl,
+      Windows::Win32::Storage::FileSystem::IDiskQuotaUser,
+      Windows::Win32::Storage::FileSystem::IEnumDiskQuotaUsers,
+      Windows::Win32::Storage::FileSystem::CLSID_DiskQuotaControl,
+      Windows::Win32::Storage::FileSystem::DISKQUOTA_USERNAME_RESOLVE,
+      Windows::Win32::Storage::FileSystem::DISKQUOTA_STATE_MASK,
+      Windows::Win32::Storage::FileSystem::DISKQUOTA_STATE_TRACK,
+      Windows::Win32::Storage::FileSystem::DISKQUOTA_STATE_ENFORCE,
+      Windows::Win32::System::Com::CoInitializeEx,
+      Windows::Win32::System::Com::CoCreateInstance,
+      Windows::Win32::System::Com::CoUninitialize,
+      Windows::Win32::System::Com::CLSCTX,
+      Windows::Win32::System::Com::COINIT,
+      Windows::Win32::System::Com::CoTaskMemFree,
+      Windows::Win32::Storage::Vss::IVssSnapshotMgmt,
+      Windows::Win32::Storage::Vss::IVssEnumObject,
+      Windows::Win32::Storage::Vss::VssSnapshotMgmt,
+      Windows::Win32::Storage::Vss::VSS_OBJECT_PROP,
+      Windows::Win32::Storage::Vss::VSS_SNAPSHOT_PROP,
+      Windows::Win32::Storage::Vss::VSS_OBJECT_TYPE,
+      Windows::Win32::Storage::Vhd::OpenVirtualDisk,
+      Windows::Win32::Storage::Vhd::CreateVirtualDisk,
+      Windows::Win32::Storage::Vhd::AttachVirtualDisk,
+      Windows::Win32::Storage::Vhd::DetachVirtualDisk,
+      Windows::Win32::Storage::Vhd::GetVirtualDiskPhysicalPath,
+      Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE,
+      Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_ISO,
+      Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_UNKNOWN,
+      Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_VHD,
+      Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_VHDX,
+      Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_VENDOR_MICROSOFT,
+      Windows::Win32::System::SystemInformation::GetSystemWindowsDirectoryW,
+      Windows::Win32::System::SystemInformation::GetFirmwareType,
+      Windows::Win32::System::SystemInformation::FIRMWARE_TYPE,
+      Windows::Win32::System::Registry::RegGetValueW,
+      Windows::Win32::System::Registry::HKEY_LOCAL_MACHINE,
+      Windows::Win32::System::Registry::RRF_RT,
+      Windows::Win32::System::Wmi::IWbemLocator,
+      Windows::Win32::System::Wmi::WbemLocator,
+      Windows::Win32::System::Wmi::IWbemServices,
+      Windows::Win32::System::Wmi::IEnumWbemClassObject,
+      Windows::Win32::System::Wmi::IWbemClassObject,
+      Windows::Win32::System::Wmi::WBEM_GENERIC_FLAG_TYPE,
+      Windows::Win32::System::Wmi::WBEM_TIMEOUT_TYPE,
+      Windows::Win32::System::Com::CoSetProxyBlanket,
+      Windows::Win32::System::Com::RPC_C_AUTHN_LEVEL,
+      Windows::Win32::System::Com::RPC_C_IMP_LEVEL,
+      Windows::Win32::System::Com::EOLE_AUTHENTICATION_CAPABILITIES,
+      Windows::Win32::System::Rpc::RPC_C_AUTHN_WINNT,
+      Windows::Win32::System::Rpc::RPC_C_AUTHZ_NONE,
+      Windows::Win32::System::OleAutomation::VARIANT,
+      Windows::Win32::System::OleAutomation::VariantClear,
+      Windows::Win32::System::OleAutomation::VARENUM,
+      Windows::Win32::Foundation::BSTR,
+      Windows::Win32::Storage::FileSystem::GetFileAttributesW,
+      Windows::Win32::Storage::FileSystem::GetFileInformationByHandleEx,
+      Windows::Win32::Storage::FileSystem::FILE_INFO_BY_HANDLE_CLASS,
+      Windows::Win32::Storage::FileSystem::USN_JOURNAL_DATA_V0,
+      Windows::Win32::System::SystemServices::FSCTL_QUERY_USN_JOURNAL,
+      Windows::Win32::System::SystemServices::FSCTL_CREATE_USN_JOURNAL,
+      Windows::Win32::System::SystemServices::FSCTL_DELETE_USN_JOURNAL,
+      Windows::Win32::Storage::FileSystem::CREATE_USN_JOURNAL_DATA,
+      Windows::Win32::Storage::FileSystem::DELETE_USN_JOURNAL_DATA,
+      Windows::Win32::Security::AdjustTokenPrivileges,
+      Windows::Win32::Security::LookupPrivilegeValueW,
+      Windows::Win32::Security::TOKEN_PRIVILEGES,
+      Windows::Win32::Security::TOKEN_ACCESS_MASK,
+      Windows::Win32::System::Threading::OpenProcessToken,
+      Windows::Win32::System::Threading::GetCurrentProcess,
+      Windows::Win32::System::SystemServices::FSCTL_GET_VOLUME_BITMAP,
+      Windows::Win32::Storage::FileSystem::STARTING_LCN_INPUT_BUFFER,
+      Windows::Win32::Storage::FileSystem::STARTING_VCN_INPUT_BUFFER,
+      Windows::Win32::System::SystemServices::FSCTL_GET_RETRIEVAL_POINTERS,
+      Windows::Win32::System::SystemServices::IOCTL_STORAGE_PREDICT_FAILURE,
+      Windows::Win32::Storage::IscsiDisc::ATA_PASS_THROUGH_EX,
+      Windows::Win32::Storage::IscsiDisc::IOCTL_ATA_PASS_THROUGH,
+      Windows::Win32::Storage::IscsiDisc::GetIScsiSessionListW,
+      Windows::Win32::Storage::IscsiDisc::GetDevicesForIScsiSessionW,
+      Windows::Win32::Storage::IscsiDisc::ISCSI_SESSION_INFOW,
+      Windows::Win32::Storage::IscsiDisc::ISCSI_CONNECTION_INFOW,
+      Windows::Win32::Storage::IscsiDisc::ISCSI_DEVICE_ON_SESSIONW,
+      Windows::Win32::Storage::FileSystem::STORAGE_PROTOCOL_SPECIFIC_DATA,
+      Windows::Win32::Storage::FileSystem::STORAGE_PROTOCOL_TYPE,
+      Windows::Win32::Storage::FileSystem::STORAGE_PROTOCOL_NVME_DATA_TYPE,
+      Windows::Win32::Storage::FileSystem::DISK_CACHE_INFORMATION,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_GET_CACHE_INFORMATION,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_SET_CACHE_INFORMATION,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DISK_ATTRIBUTES,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_SET_DISK_ATTRIBUTES,
+      Windows::Win32::System::SystemServices::DISK_ATTRIBUTE_OFFLINE,
+      Windows::Win32::System::SystemServices::DISK_ATTRIBUTE_READ_ONLY,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_SET_DRIVE_LAYOUT_EX,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_UPDATE_PROPERTIES,
+      Windows::Win32::System::LibraryLoader::LoadLibraryW,
+      Windows::Win32::System::LibraryLoader::GetProcAddress,
+      Windows::Win32::System::LibraryLoader::FreeLibrary,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_GROW_PARTITION,
+      Windows::Win32::System::SystemServices::FSCTL_SHRINK_VOLUME,
+      Windows::Win32::System::SystemServices::FSCTL_EXTEND_VOLUME,
+      Windows::Win32::Storage::FileSystem::SHRINK_VOLUME_INFORMATION,
+      Windows::Win32::System::Performance::PdhOpenQueryW,
+      Windows::Win32::System::Performance::PdhAddEnglishCounterW,
+      Windows::Win32::System::Performance::PdhCollectQueryData,
+      Windows::Win32::System::Performance::PdhGetFormattedCounterArrayW,
+      Windows::Win32::System::Performance::PdhCloseQuery,
+      Windows::Win32::System::Performance::PDH_FMT_COUNTERVALUE_ITEM_W,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_PERFORMANCE,
+      Windows::Win32::Storage::FileSystem::DISK_PERFORMANCE,
+      Windows::Win32::Storage::FileSystem::ReadFile,
+      Windows::Win32::Storage::FileSystem::WriteFile,
+      Windows::Win32::Storage::FileSystem::GET_LENGTH_INFORMATION,
+      Windows::Win32::System::SystemServices::IOCTL_DISK_GET_LENGTH_INFO,
+      Windows::Win32::System::SystemServices::OVERLAPPED,
+      Windows::Win32::System::SystemServices::IOCTL_STORAGE_GET_HOTPLUG_INFO,
+      Windows::Win32::System::SystemServices::STORAGE_HOTPLUG_INFO,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetClassDevsW,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiEnumDeviceInterfaces,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetDeviceInterfaceDetailW,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetDeviceInstanceIdW,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetDeviceRegistryPropertyW,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::CM_Get_Parent,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::CM_Get_Device_IDW,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SPDRP_FRIENDLYNAME,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SPDRP_DEVICEDESC,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiDestroyDeviceInfoList,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SP_DEVINFO_DATA,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SP_DEVICE_INTERFACE_DATA,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::SP_DEVICE_INTERFACE_DETAIL_DATA_W,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::DIGCF_PRESENT,
+      Windows::Win32::Devices::DeviceAndDriverInstallation::DIGCF_DEVICEINTERFACE,
+      Windows::Win32::System::SystemServices::GUID_DEVINTERFACE_DISK,
+      Windows::Win32::System::SystemServices::IOCTL_STORAGE_GET_DEVICE_NUMBER,
+      Windows::Win32::System::SystemServices::STORAGE_DEVICE_NUMBER,
+      Windows::Win32::Storage::FileSystem::IOCTL_VOLUME_LOGICAL_TO_PHYSICAL,
+      Windows::Win32::Storage::FileSystem::VOLUME_LOGICAL_OFFSET,
+      Windows::Win32::Storage::FileSystem::VOLUME_PHYSICAL_OFFSETS,
+      Windows::Win32::Storage::DistributedFileSystem::NetDfsGetClientInfo,
+      Windows::Win32::Storage::DistributedFileSystem::DFS_INFO_3,
+      Windows::Win32::Storage::DistributedFileSystem::DFS_STORAGE_INFO,
+      Windows::Win32::Storage::DistributedFileSystem::DFS_STORAGE_STATE_ACTIVE,
+      Windows::Win32::NetworkManagement::NetManagement::NetApiBufferFree,
+      Windows::Win32::Storage::OfflineFiles::OfflineFilesQueryStatus,
+      Windows::Win32::Storage::OfflineFiles::IOfflineFilesCache,
+      Windows::Win32::Storage::OfflineFiles::IOfflineFilesItem,
+      Windows::Win32::Storage::OfflineFiles::IOfflineFilesConnectionInfo,
+      Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_CONNECT_STATE,
+      Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_OFFLINE_REASON
     };
 }
\ No newline at end of file
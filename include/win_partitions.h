@@ -0,0 +1,115 @@
+#ifndef WIN_PARTITIONS_H
+#define WIN_PARTITIONS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Length in UTF-16 code units of the string buffers in [WpPartition], including the null terminator
+ */
+#define WP_STRING_LENGTH 261
+
+/**
+ * The call succeeded
+ */
+#define WP_OK 0
+
+/**
+ * A required pointer argument was null
+ */
+#define WP_ERROR_NULL_POINTER -1
+
+/**
+ * The caller-provided buffer is too small; the required number of entries was written to `count`
+ */
+#define WP_ERROR_BUFFER_TOO_SMALL -2
+
+/**
+ * The crate panicked; this is a bug in the crate
+ */
+#define WP_ERROR_PANIC -3
+
+/**
+ * C representation of [WindowsPartition]
+ */
+typedef struct WpPartition {
+  /**
+   * Drive letter as a UTF-16 code unit, e.g. `'C'`
+   */
+  uint16_t letter;
+  /**
+   * Whether the partition is ready, see [WindowsPartition::ready]
+   */
+  bool ready;
+  /**
+   * Drive type code as returned by `GetDriveTypeW`, see [DriveType](crate::win_api::DriveType)
+   */
+  uint32_t drive_type;
+  /**
+   * Total size of partition in bytes
+   */
+  uint64_t size;
+  /**
+   * Free space in bytes
+   */
+  uint64_t free_space;
+  /**
+   * Cluster size in bytes, 0 if it couldn't be read
+   */
+  uint32_t allocation_unit_size;
+  /**
+   * Partition name
+   */
+  uint16_t name[WP_STRING_LENGTH];
+  /**
+   * Partition format name, e.g. `NTFS`
+   */
+  uint16_t file_system_name[WP_STRING_LENGTH];
+} WpPartition;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Writes the system partitions to the caller-provided `buffer` of `capacity` entries and their number to `count`.
+ *
+ * If `buffer` is too small, nothing is written to it, the required number of entries is written to `count`
+ * and [WP_ERROR_BUFFER_TOO_SMALL] is returned. `buffer` may be null if `capacity` is 0.
+ *
+ * # Safety
+ *
+ * `count` must be a valid pointer. `buffer` must be null or valid for writing `capacity` entries
+ */
+int32_t wp_get_partitions(struct WpPartition *buffer, size_t capacity, size_t *count);
+
+/**
+ * Allocates an array of the system partitions, writing it to `partitions` and its length to `count`.
+ * The array must be released with [wp_free_partitions].
+ *
+ * # Safety
+ *
+ * `partitions` and `count` must be valid pointers
+ */
+int32_t wp_get_partitions_alloc(struct WpPartition **partitions, size_t *count);
+
+/**
+ * Releases an array returned by [wp_get_partitions_alloc]. Does nothing if `partitions` is null.
+ *
+ * # Safety
+ *
+ * `partitions` and `count` must be the values written by [wp_get_partitions_alloc], and the array must not
+ * be released twice
+ */
+void wp_free_partitions(struct WpPartition *partitions, size_t count);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* WIN_PARTITIONS_H */
@@ -0,0 +1,69 @@
+//! Async versions of the partition queries, enabled by the `async` feature.
+//!
+//! Windows API calls against a dead network drive can block for many seconds. These functions run the
+//! blocking calls on the tokio blocking thread pool, so they don't stall the async runtime.
+//! They must be called from within a tokio runtime.
+
+use crate::backend::SystemBackend;
+use crate::error::WinPartitionError;
+use crate::win_api::{
+    get_disk_free_space, get_logical_drive, get_volume_information, FileSystemFlags, VolumeSerial,
+};
+use crate::windows_partitions::{get_network_share, query_partition, NetworkShare, WindowsPartition};
+
+/// Runs a blocking function on the tokio blocking thread pool, forwarding its panics
+async fn blocking<T, F>(function: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(function).await {
+        Ok(value) => value,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Async version of [get_partitions](crate::windows_partitions::get_partitions).
+/// All drives are queried concurrently
+pub async fn get_partitions_async() -> Result<Vec<WindowsPartition>, WinPartitionError> {
+    let drives = blocking(get_logical_drive).await?;
+    let handles: Vec<_> = drives
+        .into_iter()
+        .map(|letter| tokio::task::spawn_blocking(move || query_partition(&SystemBackend, letter, &mut vec![])))
+        .collect();
+
+    let mut result: Vec<WindowsPartition> = vec![];
+    for handle in handles {
+        match handle.await {
+            Ok(partition) => result.push(partition),
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Async version of [WindowsPartition::from_letter]
+pub async fn get_partition_async(letter: char) -> Result<WindowsPartition, WinPartitionError> {
+    blocking(move || WindowsPartition::from_letter(letter)).await
+}
+
+/// Async version of [get_network_share](crate::windows_partitions::get_network_share), which can block for
+/// a long time when the server doesn't answer
+pub async fn get_network_share_async(path: String) -> Result<NetworkShare, WinPartitionError> {
+    blocking(move || get_network_share(path)).await
+}
+
+/// Async version of [get_volume_information](crate::win_api::get_volume_information)
+pub async fn get_volume_information_async(
+    lprootpathname: String
+) -> Result<(String, String, VolumeSerial, u32, FileSystemFlags), WinPartitionError> {
+    blocking(move || get_volume_information(lprootpathname)).await
+}
+
+/// Async version of [get_disk_free_space](crate::win_api::get_disk_free_space)
+pub async fn get_disk_free_space_async(
+    lpdirectoryname: String
+) -> Result<(u64, u64, u64), WinPartitionError> {
+    blocking(move || get_disk_free_space(lpdirectoryname)).await
+}
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::error::WinPartitionError;
+use crate::storage::{get_storage_bus_type, StorageBusType};
+use crate::win_api::{
+    get_disk_free_space, get_disk_free_space_basic, get_drive_type, get_file_attributes, get_logical_drive,
+    get_network_connection, get_volume_disk_extents, get_volume_information, get_volume_name_for_mount_point,
+    get_volume_path_names, query_dos_device, DiskExtent, DriveType, FileSystemFlags, VolumeSerial,
+};
+use crate::wmi::{WmiConnection, WmiObject, CIMV2_NAMESPACE};
+
+/// Win32 error code for "The system cannot find the path specified"
+const ERROR_PATH_NOT_FOUND: i32 = 3;
+/// Win32 error code for "The device is not ready"
+const ERROR_NOT_READY: i32 = 21;
+/// Win32 error code for "The request is not supported"
+const ERROR_NOT_SUPPORTED: i32 = 50;
+/// `FILE_ATTRIBUTE_DIRECTORY` flag of [get_file_attributes]
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+/// `FILE_ATTRIBUTE_COMPRESSED` flag of [get_file_attributes]
+const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+
+/// Windows API calls used to query partitions, so they can be replaced by a fake in tests.
+///
+/// [SystemBackend] makes the real calls, [WmiBackend] reads the same data from WMI. Functions such as
+/// [get_partitions_with](crate::windows_partitions::get_partitions_with) accept any backend, so code built
+/// on them can be tested with synthetic drives off a real Windows box. Root paths are in `C:\` form.
+///
+/// The optional calls default to failing with `ERROR_NOT_SUPPORTED`, which partition queries tolerate
+pub trait WinApiBackend {
+    /// See [get_logical_drive]
+    fn logical_drives(&self) -> Result<Vec<char>, WinPartitionError>;
+
+    /// See [get_drive_type]
+    fn drive_type(&self, root_path: &str) -> DriveType;
+
+    /// See [get_disk_free_space]
+    fn disk_free_space(&self, root_path: &str) -> Result<(u64, u64, u64), WinPartitionError>;
+
+    /// See [get_volume_information]. The serial is `None` if the backend can't report it
+    fn volume_information(
+        &self,
+        root_path: &str,
+    ) -> Result<(String, String, Option<VolumeSerial>, u32, FileSystemFlags), WinPartitionError>;
+
+    /// See [get_disk_free_space_basic]
+    fn disk_free_space_basic(&self, root_path: &str) -> Result<(u32, u32, u32, u32), WinPartitionError> {
+        Err(not_supported("GetDiskFreeSpaceW", root_path))
+    }
+
+    /// See [get_volume_disk_extents]
+    fn volume_disk_extents(&self, root_path: &str) -> Result<Vec<DiskExtent>, WinPartitionError> {
+        Err(not_supported("IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS", root_path))
+    }
+
+    /// See [get_storage_bus_type]
+    fn storage_bus_type(&self, root_path: &str) -> Result<StorageBusType, WinPartitionError> {
+        Err(not_supported("IOCTL_STORAGE_QUERY_PROPERTY", root_path))
+    }
+
+    /// See [get_network_connection]
+    fn network_connection(&self, letter: char) -> Result<String, WinPartitionError> {
+        Err(not_supported("WNetGetConnectionW", &format!("{}:", letter)))
+    }
+
+    /// See [query_dos_device]
+    fn dos_device(&self, device_name: &str) -> Result<Vec<String>, WinPartitionError> {
+        Err(not_supported("QueryDosDeviceW", device_name))
+    }
+
+    /// See [get_file_attributes]
+    fn file_attributes(&self, path: &str) -> Result<u32, WinPartitionError> {
+        Err(not_supported("GetFileAttributesW", path))
+    }
+
+    /// See [get_volume_path_names] of the volume [get_volume_name_for_mount_point] returns for `root_path`
+    fn volume_path_names(&self, root_path: &str) -> Result<Vec<String>, WinPartitionError> {
+        Err(not_supported("GetVolumePathNamesForVolumeNameW", root_path))
+    }
+}
+
+fn not_supported(api: &'static str, path: &str) -> WinPartitionError {
+    WinPartitionError::from_code(api, Some(path), ERROR_NOT_SUPPORTED)
+}
+
+/// Backend calling the real Windows API
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemBackend;
+
+impl WinApiBackend for SystemBackend {
+    fn logical_drives(&self) -> Result<Vec<char>, WinPartitionError> {
+        get_logical_drive()
+    }
+
+    fn drive_type(&self, root_path: &str) -> DriveType {
+        get_drive_type(root_path)
+    }
+
+    fn disk_free_space(&self, root_path: &str) -> Result<(u64, u64, u64), WinPartitionError> {
+        get_disk_free_space(root_path)
+    }
+
+    fn volume_information(
+        &self,
+        root_path: &str,
+    ) -> Result<(String, String, Option<VolumeSerial>, u32, FileSystemFlags), WinPartitionError> {
+        get_volume_information(root_path)
+            .map(|(name, file_system, serial, max_component_length, flags)| {
+                (name, file_system, Some(serial), max_component_length, flags)
+            })
+    }
+
+    fn disk_free_space_basic(&self, root_path: &str) -> Result<(u32, u32, u32, u32), WinPartitionError> {
+        get_disk_free_space_basic(root_path.to_string())
+    }
+
+    fn volume_disk_extents(&self, root_path: &str) -> Result<Vec<DiskExtent>, WinPartitionError> {
+        get_volume_disk_extents(root_path.to_string())
+    }
+
+    fn storage_bus_type(&self, root_path: &str) -> Result<StorageBusType, WinPartitionError> {
+        get_storage_bus_type(root_path.to_string())
+    }
+
+    fn network_connection(&self, letter: char) -> Result<String, WinPartitionError> {
+        get_network_connection(letter)
+    }
+
+    fn dos_device(&self, device_name: &str) -> Result<Vec<String>, WinPartitionError> {
+        query_dos_device(device_name.to_string())
+    }
+
+    fn file_attributes(&self, path: &str) -> Result<u32, WinPartitionError> {
+        get_file_attributes(path.to_string())
+    }
+
+    fn volume_path_names(&self, root_path: &str) -> Result<Vec<String>, WinPartitionError> {
+        get_volume_path_names(get_volume_name_for_mount_point(root_path.to_string())?)
+    }
+}
+
+/// Backend reading drives from the WMI classes
+/// [Win32_LogicalDisk](https://docs.microsoft.com/en-us/windows/win32/cimwin32prov/win32-logicaldisk) and
+/// [Win32_Volume](https://docs.microsoft.com/en-us/previous-versions/windows/desktop/legacy/aa394515(v=vs.85)),
+/// for environments where opening volume handles is blocked but WMI is allowed.
+///
+/// The drives are read once by [WmiBackend::connect], connect again to see later changes. Disk extents,
+/// bus types and DOS devices aren't available over WMI, so partitions queried through this backend have no
+/// `disk_extents`, `bus_type` and `device_path`. Volume flags are limited to compression and quota support.
+/// Pass it to [get_partitions_with](crate::windows_partitions::get_partitions_with):
+///
+/// ```no_run
+/// use win_partitions::backend::WmiBackend;
+/// use win_partitions::windows_partitions::get_partitions_with;
+///
+/// let partitions = get_partitions_with(&WmiBackend::connect().unwrap()).unwrap();
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WmiBackend {
+    drives: BTreeMap<char, WmiDrive>,
+}
+
+/// Properties of a `Win32_LogicalDisk` and its `Win32_Volume`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WmiDrive {
+    drive_type: DriveType,
+    /// `None` for drives without media
+    size: Option<u64>,
+    free_space: u64,
+    volume_name: String,
+    file_system: String,
+    serial: Option<VolumeSerial>,
+    max_component_length: u32,
+    flags: FileSystemFlags,
+    compressed: bool,
+    /// Cluster size, `Win32_Volume` only lists fixed and removable volumes
+    block_size: Option<u64>,
+    /// UNC path of a network drive
+    provider_name: Option<String>,
+}
+
+impl WmiBackend {
+    /// Reads the drives from WMI
+    pub fn connect() -> Result<WmiBackend, WinPartitionError> {
+        let wmi = WmiConnection::connect(CIMV2_NAMESPACE)?;
+        let mut block_sizes: BTreeMap<char, u64> = BTreeMap::new();
+        for volume in wmi.query("SELECT DriveLetter, BlockSize FROM Win32_Volume WHERE DriveLetter IS NOT NULL")? {
+            let letter = volume.get_string("DriveLetter")?.as_deref().and_then(parse_device_id);
+            if let (Some(letter), Some(block_size)) = (letter, volume.get_u64("BlockSize")?) {
+                block_sizes.insert(letter, block_size);
+            }
+        }
+
+        let mut drives = BTreeMap::new();
+        for disk in wmi.query("SELECT * FROM Win32_LogicalDisk")? {
+            if let Some(letter) = disk.get_string("DeviceID")?.as_deref().and_then(parse_device_id) {
+                let mut drive = query_drive(&disk)?;
+                drive.block_size = block_sizes.get(&letter).copied();
+                drives.insert(letter, drive);
+            }
+        }
+
+        Ok(WmiBackend { drives })
+    }
+
+    /// Drive at a root path such as `C:\`, failing with `ERROR_PATH_NOT_FOUND` for unknown drives and
+    /// `ERROR_NOT_READY` for drives without media
+    fn ready_drive(&self, api: &'static str, root_path: &str) -> Result<&WmiDrive, WinPartitionError> {
+        let drive = parse_device_id(root_path)
+            .and_then(|letter| self.drives.get(&letter))
+            .ok_or_else(|| WinPartitionError::from_code(api, Some(root_path), ERROR_PATH_NOT_FOUND))?;
+        match drive.size {
+            Some(_) => Ok(drive),
+            None => Err(WinPartitionError::from_code(api, Some(root_path), ERROR_NOT_READY)),
+        }
+    }
+}
+
+impl WinApiBackend for WmiBackend {
+    fn logical_drives(&self) -> Result<Vec<char>, WinPartitionError> {
+        Ok(self.drives.keys().copied().collect())
+    }
+
+    fn drive_type(&self, root_path: &str) -> DriveType {
+        parse_device_id(root_path)
+            .and_then(|letter| self.drives.get(&letter))
+            .map_or(DriveType::DriveNoRootDir, |drive| drive.drive_type)
+    }
+
+    fn disk_free_space(&self, root_path: &str) -> Result<(u64, u64, u64), WinPartitionError> {
+        let drive = self.ready_drive("Win32_LogicalDisk", root_path)?;
+        // WMI doesn't apply the quota of the caller
+        Ok((drive.free_space, drive.size.unwrap_or(0), drive.free_space))
+    }
+
+    fn volume_information(
+        &self,
+        root_path: &str,
+    ) -> Result<(String, String, Option<VolumeSerial>, u32, FileSystemFlags), WinPartitionError> {
+        let drive = self.ready_drive("Win32_LogicalDisk", root_path)?;
+        Ok((
+            drive.volume_name.clone(),
+            drive.file_system.clone(),
+            drive.serial,
+            drive.max_component_length,
+            drive.flags))
+    }
+
+    /// Reports the cluster size of `Win32_Volume` as clusters of a single sector
+    fn disk_free_space_basic(&self, root_path: &str) -> Result<(u32, u32, u32, u32), WinPartitionError> {
+        let drive = self.ready_drive("Win32_Volume", root_path)?;
+        match drive.block_size.filter(|block_size| *block_size > 0 && *block_size <= u32::MAX as u64) {
+            Some(block_size) => {
+                let clusters = |bytes: u64| (bytes / block_size).min(u32::MAX as u64) as u32;
+                Ok((1, block_size as u32, clusters(drive.free_space), clusters(drive.size.unwrap_or(0))))
+            }
+            None => Err(not_supported("Win32_Volume", root_path)),
+        }
+    }
+
+    fn network_connection(&self, letter: char) -> Result<String, WinPartitionError> {
+        self.drives
+            .get(&letter.to_ascii_uppercase())
+            .and_then(|drive| drive.provider_name.clone())
+            .ok_or_else(|| not_supported("Win32_LogicalDisk", &format!("{}:", letter)))
+    }
+
+    /// Reports the attributes of root directories only, from the `Compressed` property
+    fn file_attributes(&self, path: &str) -> Result<u32, WinPartitionError> {
+        let drive = self.ready_drive("Win32_LogicalDisk", path)?;
+        if path.len() > 3 {
+            return Err(not_supported("Win32_LogicalDisk", path));
+        }
+        Ok(FILE_ATTRIBUTE_DIRECTORY | if drive.compressed { FILE_ATTRIBUTE_COMPRESSED } else { 0 })
+    }
+}
+
+fn query_drive(disk: &WmiObject) -> Result<WmiDrive, WinPartitionError> {
+    let drive_type = disk.get_u64("DriveType")?
+        .and_then(|drive_type| DriveType::try_from(drive_type as u32).ok())
+        .unwrap_or_default();
+    let mut flags = FileSystemFlags::empty();
+    flags.set(FileSystemFlags::FILE_FILE_COMPRESSION, disk.get_bool("SupportsFileBasedCompression")? == Some(true));
+    flags.set(FileSystemFlags::FILE_VOLUME_QUOTAS, disk.get_bool("SupportsDiskQuotas")? == Some(true));
+
+    Ok(WmiDrive {
+        drive_type,
+        size: disk.get_u64("Size")?,
+        free_space: disk.get_u64("FreeSpace")?.unwrap_or(0),
+        volume_name: disk.get_string("VolumeName")?.unwrap_or_default(),
+        file_system: disk.get_string("FileSystem")?.unwrap_or_default(),
+        serial: disk.get_string("VolumeSerialNumber")?.as_deref().and_then(parse_serial_number),
+        max_component_length: disk.get_u64("MaximumComponentLength")?.unwrap_or(0) as u32,
+        flags,
+        compressed: disk.get_bool("Compressed")? == Some(true),
+        block_size: None,
+        provider_name: disk.get_string("ProviderName")?.filter(|name| !name.is_empty()),
+    })
+}
+
+/// Drive letter of a WMI device ID such as `C:` or a root path such as `C:\`
+fn parse_device_id(device_id: &str) -> Option<char> {
+    let mut chars = device_id.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Parses a `VolumeSerialNumber` of `Win32_LogicalDisk`, 8 hex digits without the dash, e.g. `1A2B03C4`
+fn parse_serial_number(serial: &str) -> Option<VolumeSerial> {
+    u32::from_str_radix(serial, 16).ok().map(VolumeSerial)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_wmi_values_test() {
+        assert_eq!(parse_device_id("c:"), Some('C'));
+        assert_eq!(parse_device_id("D:\\"), Some('D'));
+        assert_eq!(parse_device_id("\\\\?\\Volume{1}\\"), None);
+        assert_eq!(parse_serial_number("1A2B03C4"), Some(VolumeSerial(0x1a2b_03c4)));
+        assert_eq!(parse_serial_number(""), None);
+    }
+
+    #[test]
+    fn wmi_backend_test() {
+        let drive = |drive_type| WmiDrive {
+            drive_type,
+            size: None,
+            free_space: 0,
+            volume_name: String::new(),
+            file_system: String::new(),
+            serial: None,
+            max_component_length: 0,
+            flags: FileSystemFlags::empty(),
+            compressed: false,
+            block_size: None,
+            provider_name: None,
+        };
+        let mut backend = WmiBackend::default();
+        backend.drives.insert('C', WmiDrive {
+            size: Some(512 * 1024 * 1024 * 1024),
+            free_space: 123 * 1024 * 1024 * 1024,
+            file_system: "NTFS".to_string(),
+            block_size: Some(4096),
+            compressed: true,
+            ..drive(DriveType::DriveFixed)
+        });
+        backend.drives.insert('E', drive(DriveType::DriveCDRom));
+
+        assert_eq!(backend.logical_drives().unwrap(), vec!['C', 'E']);
+        assert_eq!(backend.drive_type("F:\\"), DriveType::DriveNoRootDir);
+        assert_eq!(backend.disk_free_space("C:\\").unwrap().1, 512 * 1024 * 1024 * 1024);
+        assert_eq!(backend.disk_free_space_basic("C:\\").unwrap().1, 4096);
+        assert_eq!(backend.file_attributes("C:\\").unwrap(), FILE_ATTRIBUTE_DIRECTORY | FILE_ATTRIBUTE_COMPRESSED);
+        assert_eq!(backend.volume_information("C:\\").unwrap().2, None);
+        assert!(matches!(backend.volume_information("E:\\"), Err(WinPartitionError::NotReady { .. })));
+    }
+}
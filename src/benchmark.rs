@@ -0,0 +1,187 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::GET_LENGTH_INFORMATION,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_LENGTH_INFO,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::{open_unbuffered, volume_device_path, AlignedBuffer, DeviceHandle};
+
+/// Win32 error code for "The parameter is incorrect"
+const ERROR_INVALID_PARAMETER: i32 = 87;
+/// Alignment of the read buffer and of `block_size`, a multiple of the 512 and 4096 byte sector sizes
+/// required by unbuffered I/O
+const BLOCK_ALIGNMENT: usize = 4096;
+
+/// Throughput and latency of a read benchmark
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkResult {
+    /// Number of bytes read
+    pub bytes_read: u64,
+    /// Number of reads
+    pub operations: u64,
+    /// Time spent reading
+    pub elapsed: Duration,
+    /// Latency of every read, sorted from the fastest
+    latencies: Vec<Duration>,
+}
+
+impl BenchmarkResult {
+    fn new(bytes_read: u64, elapsed: Duration, mut latencies: Vec<Duration>) -> Self {
+        latencies.sort();
+        BenchmarkResult { bytes_read, operations: latencies.len() as u64, elapsed, latencies }
+    }
+
+    /// Throughput in megabytes (10^6 bytes) per second, as reported by disk vendors
+    pub fn megabytes_per_second(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => self.bytes_read as f64 / 1_000_000.0 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    /// Reads per second
+    pub fn iops(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            seconds if seconds > 0.0 => self.operations as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    /// Latency under which `percentile` percent of the reads completed, such as `50.0` for the median or
+    /// `99.0`. Uses the nearest-rank method, `0.0` returns the fastest read and `100.0` the slowest
+    pub fn latency_percentile(&self, percentile: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies[rank.saturating_sub(1).min(self.latencies.len() - 1)]
+    }
+}
+
+/// Measures the sequential read throughput of the volume or disk at `path`, such as `C:\`, a volume GUID path
+/// or `\\.\PhysicalDrive0`, by reading `total_bytes` from its start in blocks of `block_size` bytes.
+/// Reading stops early at the end of the device.
+///
+/// Reads bypass the cache with `FILE_FLAG_NO_BUFFERING` so the device itself is measured, which requires
+/// `block_size` to be a non-zero multiple of 4096 bytes, else fails with `ERROR_INVALID_PARAMETER`.
+/// Nothing is written. Requires administrator rights.
+///
+/// ```no_run
+/// use win_partitions::benchmark::benchmark_sequential_read;
+///
+/// let result = benchmark_sequential_read("C:\\".to_string(), 1024 * 1024, 1024 * 1024 * 1024).unwrap();
+/// println!("{:.1} MB/s, p99 {:?}", result.megabytes_per_second(), result.latency_percentile(99.0));
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn benchmark_sequential_read(
+    path: String,
+    block_size: u32,
+    total_bytes: u64,
+) -> Result<BenchmarkResult, WinPartitionError> {
+    let device = BenchmarkDevice::open(&path, block_size)?;
+    let total_bytes = total_bytes.min(device.length);
+
+    let mut latencies = vec![];
+    let mut offset: u64 = 0;
+    let start = Instant::now();
+    while offset + block_size as u64 <= total_bytes {
+        latencies.push(device.read_at(offset)?);
+        offset += block_size as u64;
+    }
+
+    Ok(BenchmarkResult::new(offset, start.elapsed(), latencies))
+}
+
+/// Measures the random read performance of the volume or disk at `path` like [benchmark_sequential_read],
+/// with `operations` reads of `block_size` bytes at random block-aligned offsets across the whole device.
+/// Use a small `block_size` such as 4096 to measure IOPS and latency.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn benchmark_random_read(
+    path: String,
+    block_size: u32,
+    operations: u32,
+) -> Result<BenchmarkResult, WinPartitionError> {
+    let device = BenchmarkDevice::open(&path, block_size)?;
+    let blocks = device.length / block_size as u64;
+    if blocks == 0 {
+        return Err(WinPartitionError::from_code("ReadFile", Some(&path), ERROR_INVALID_PARAMETER));
+    }
+
+    let mut random = XorShift::from_time();
+    let mut latencies = Vec::with_capacity(operations as usize);
+    let start = Instant::now();
+    for _ in 0..operations {
+        latencies.push(device.read_at(random.next() % blocks * block_size as u64)?);
+    }
+
+    Ok(BenchmarkResult::new(operations as u64 * block_size as u64, start.elapsed(), latencies))
+}
+
+/// Device opened for unbuffered reads, with a sector-aligned buffer of one block
+struct BenchmarkDevice {
+    device: DeviceHandle,
+    length: u64,
+    buffer: RefCell<AlignedBuffer>,
+}
+
+impl BenchmarkDevice {
+    fn open(path: &str, block_size: u32) -> Result<BenchmarkDevice, WinPartitionError> {
+        if block_size == 0 || !(block_size as usize).is_multiple_of(BLOCK_ALIGNMENT) {
+            return Err(WinPartitionError::from_code("ReadFile", Some(path), ERROR_INVALID_PARAMETER));
+        }
+        let device = open_unbuffered(&volume_device_path(path), FILE_GENERIC_READ)?;
+        let length: GET_LENGTH_INFORMATION = device.query("IOCTL_DISK_GET_LENGTH_INFO", IOCTL_DISK_GET_LENGTH_INFO)?;
+        let buffer = RefCell::new(AlignedBuffer::new(block_size as usize, BLOCK_ALIGNMENT));
+
+        Ok(BenchmarkDevice { device, length: length.Length.max(0) as u64, buffer })
+    }
+
+    /// Reads one block at `offset` and returns how long the read took
+    fn read_at(&self, offset: u64) -> Result<Duration, WinPartitionError> {
+        let mut buffer = self.buffer.borrow_mut();
+        let start = Instant::now();
+        self.device.read_at(offset, buffer.as_mut_slice())?;
+        Ok(start.elapsed())
+    }
+}
+
+/// Xorshift64 generator, random enough to defeat read-ahead and caches of the device
+struct XorShift(u64);
+
+impl XorShift {
+    fn from_time() -> XorShift {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0);
+        // The state must not be zero
+        XorShift(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn latency_percentile_test() {
+        let latencies = (1..=100).rev().map(Duration::from_millis).collect();
+        let result = BenchmarkResult::new(100 * 4096, Duration::from_secs(2), latencies);
+        assert_eq!(result.latency_percentile(50.0), Duration::from_millis(50));
+        assert_eq!(result.latency_percentile(99.0), Duration::from_millis(99));
+        assert_eq!(result.latency_percentile(0.0), Duration::from_millis(1));
+        assert_eq!(result.latency_percentile(100.0), Duration::from_millis(100));
+        assert_eq!(result.iops(), 50.0);
+        assert_eq!(BenchmarkResult::default().latency_percentile(99.0), Duration::ZERO);
+    }
+}
@@ -0,0 +1,88 @@
+//! Command line tool printing the system partitions, built with the `cli` feature.
+//!
+//! Run `win-partitions --help` for the options.
+
+use std::env;
+use std::fmt::Write;
+use std::process::exit;
+
+use win_partitions::byte_size::ByteSize;
+use win_partitions::windows_partitions::{PartitionQuery, WindowsPartition};
+
+const USAGE: &str = "Usage: win-partitions [options]
+
+Prints the system partitions as a table.
+
+Options:
+    --json          Print the partitions as a JSON array
+    --only-ready    Only list drives that are ready
+    --only-fixed    Only list fixed drives
+    --no-network    Don't list network drives
+    -h, --help      Print this help";
+
+fn main() {
+    let mut query = PartitionQuery::new();
+    let mut json = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--only-ready" => query = query.only_ready(),
+            "--only-fixed" => query = query.only_fixed(),
+            "--no-network" => query = query.include_network(false),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("win-partitions: unknown option {}\n\n{}", arg, USAGE);
+                exit(2);
+            }
+        }
+    }
+
+    let (partitions, errors) = match query.run_lossy() {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("win-partitions: {}", err);
+            exit(1);
+        }
+    };
+    for err in &errors {
+        eprintln!("win-partitions: {}", err);
+    }
+
+    if json {
+        match serde_json::to_string(&partitions) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("win-partitions: {}", err);
+                exit(1);
+            }
+        }
+    } else {
+        print!("{}", to_table(&partitions));
+    }
+}
+
+fn to_table(partitions: &[WindowsPartition]) -> String {
+    let mut table = format!(
+        "{:<6} {:<10} {:<20} {:<8} {:>10} {:>10}\n",
+        "Drive", "Type", "Name", "FS", "Size", "Free");
+    for partition in partitions {
+        let (size, free) = if partition.ready {
+            (ByteSize(partition.size).to_string(), ByteSize(partition.free_space).to_string())
+        } else {
+            ("-".to_string(), "-".to_string())
+        };
+        let _ = writeln!(
+            table,
+            "{:<6} {:<10} {:<20} {:<8} {:>10} {:>10}",
+            format!("{}:", partition.letter),
+            partition.drive_type,
+            partition.name,
+            partition.file_system_name,
+            size,
+            free);
+    }
+    table
+}
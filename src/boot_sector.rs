@@ -0,0 +1,258 @@
+use std::convert::TryInto;
+
+use crate::error::WinPartitionError;
+use crate::file_system::FileSystem;
+use crate::raw_device::RawDevice;
+
+/// Win32 error code for "The volume does not contain a recognized file system"
+const ERROR_UNRECOGNIZED_VOLUME: i32 = 1005;
+/// Size of the boot sector fields, the rest of a larger sector is boot code
+const BOOT_SECTOR_SIZE: usize = 512;
+/// Extended boot signature of FAT boot sectors whose serial number, label and type fields are valid
+const FAT_EXTENDED_BOOT_SIGNATURE: u8 = 0x29;
+
+/// Fields of the BIOS Parameter Block in the boot sector of a FAT, FAT32, exFAT or NTFS volume
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BootSector {
+    /// File system identified from the boot sector markers
+    pub file_system: FileSystem,
+    /// OEM name at offset 3, `NTFS` and `EXFAT` for those file systems, the formatting tool such as
+    /// `MSDOS5.0` for FAT
+    pub oem_id: String,
+    /// Number of bytes per sector
+    pub bytes_per_sector: u32,
+    /// Number of sectors per cluster
+    pub sectors_per_cluster: u32,
+    /// Number of sectors of the volume
+    pub total_sectors: u64,
+    /// Serial number, 64-bit for NTFS, 32-bit for FAT and exFAT. Windows shows the low 32 bits as `XXXX-XXXX`
+    pub serial_number: u64,
+    /// Label written by the formatting tool in FAT boot sectors, the current label is stored in the root
+    /// directory. `None` for exFAT and NTFS
+    pub label: Option<String>,
+}
+
+impl BootSector {
+    /// Cluster size in bytes
+    pub fn cluster_size(&self) -> u64 {
+        self.bytes_per_sector as u64 * self.sectors_per_cluster as u64
+    }
+
+    /// Size of the volume in bytes
+    pub fn size(&self) -> u64 {
+        self.total_sectors * self.bytes_per_sector as u64
+    }
+}
+
+/// Reads the first sector of the volume at `lprootpathname`, a root path such as `E:\` or a volume GUID path,
+/// through a [RawDevice] and decodes it with [parse_boot_sector]. Works while the file system is dismounted,
+/// when [get_volume_information](crate::win_api::get_volume_information) fails. Fails with
+/// `ERROR_UNRECOGNIZED_VOLUME` if the sector isn't a FAT, FAT32, exFAT or NTFS boot sector.
+/// Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn read_boot_sector(lprootpathname: String) -> Result<BootSector, WinPartitionError> {
+    let device = RawDevice::open_volume(lprootpathname)?;
+    let sector = device.read_sectors(0, 1)?;
+
+    parse_boot_sector(&sector)
+        .ok_or_else(|| WinPartitionError::from_code("ReadFile", Some(device.path()), ERROR_UNRECOGNIZED_VOLUME))
+}
+
+/// Decodes a FAT, FAT32, exFAT or NTFS boot sector, `None` if `sector` is shorter than 512 bytes or isn't
+/// a boot sector of one of these file systems
+pub fn parse_boot_sector(sector: &[u8]) -> Option<BootSector> {
+    if sector.len() < BOOT_SECTOR_SIZE || sector[510..512] != [0x55, 0xAA] {
+        return None;
+    }
+    match &sector[3..11] {
+        b"NTFS    " => parse_ntfs(sector),
+        b"EXFAT   " => parse_exfat(sector),
+        _ => parse_fat(sector),
+    }
+}
+
+fn parse_ntfs(sector: &[u8]) -> Option<BootSector> {
+    let bytes_per_sector = valid_sector_size(u16_at(sector, 11))?;
+    // Values above 0x80 encode clusters of 2^(256 - value) sectors, used for clusters of 128 KB and more
+    let sectors_per_cluster = match sector[13] {
+        0 => return None,
+        value @ 0x01..=0x80 => value as u32,
+        value => 1u32.checked_shl(256 - value as u32)?,
+    };
+
+    Some(BootSector {
+        file_system: FileSystem::Ntfs,
+        oem_id: ascii(&sector[3..11]),
+        bytes_per_sector,
+        sectors_per_cluster,
+        total_sectors: u64_at(sector, 40),
+        serial_number: u64_at(sector, 72),
+        label: None,
+    })
+}
+
+fn parse_exfat(sector: &[u8]) -> Option<BootSector> {
+    // Sizes are stored as powers of two: 512 to 4096 byte sectors and clusters up to 32 MB
+    let bytes_per_sector_shift = sector[108];
+    let sectors_per_cluster_shift = sector[109];
+    if !(9..=12).contains(&bytes_per_sector_shift) || bytes_per_sector_shift + sectors_per_cluster_shift > 25 {
+        return None;
+    }
+
+    Some(BootSector {
+        file_system: FileSystem::ExFat,
+        oem_id: ascii(&sector[3..11]),
+        bytes_per_sector: 1 << bytes_per_sector_shift,
+        sectors_per_cluster: 1 << sectors_per_cluster_shift,
+        total_sectors: u64_at(sector, 72),
+        serial_number: u32_at(sector, 100) as u64,
+        label: None,
+    })
+}
+
+fn parse_fat(sector: &[u8]) -> Option<BootSector> {
+    let bytes_per_sector = valid_sector_size(u16_at(sector, 11))?;
+    let sectors_per_cluster = sector[13] as u32;
+    if !sectors_per_cluster.is_power_of_two() || sector[16] == 0 {
+        return None;
+    }
+    let total_sectors = match u16_at(sector, 19) {
+        0 => u32_at(sector, 32),
+        total_sectors => total_sectors as u32,
+    };
+
+    // FAT32 has no 16-bit FAT size, and its extended fields start after the 32-bit one
+    let (file_system, extended) = match u16_at(sector, 22) {
+        0 => (FileSystem::Fat32, 64),
+        _ => (FileSystem::Fat, 36),
+    };
+    let (serial_number, label) = match sector[extended + 2] {
+        FAT_EXTENDED_BOOT_SIGNATURE => {
+            let label = ascii(&sector[extended + 7..extended + 18]);
+            (u32_at(sector, extended + 3) as u64, Some(label).filter(|label| label != "NO NAME"))
+        }
+        _ => (0, None),
+    };
+
+    Some(BootSector {
+        file_system,
+        oem_id: ascii(&sector[3..11]),
+        bytes_per_sector,
+        sectors_per_cluster,
+        total_sectors: total_sectors as u64,
+        serial_number,
+        label,
+    })
+}
+
+/// Sector size of a BPB if it is a power of two between 512 and 4096 bytes
+fn valid_sector_size(bytes_per_sector: u16) -> Option<u32> {
+    Some(bytes_per_sector as u32).filter(|size| size.is_power_of_two() && (512..=4096).contains(size))
+}
+
+/// Space padded ASCII field
+fn ascii(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches([' ', '\0']).to_string()
+}
+
+fn u16_at(sector: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(sector[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(sector: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(sector[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(sector: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(sector[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Empty boot sector with the jump instruction, OEM name and signature
+    fn sector(oem_id: &[u8; 8]) -> Vec<u8> {
+        let mut sector = vec![0; 512];
+        sector[0..3].copy_from_slice(&[0xEB, 0x52, 0x90]);
+        sector[3..11].copy_from_slice(oem_id);
+        sector[510..512].copy_from_slice(&[0x55, 0xAA]);
+        sector
+    }
+
+    #[test]
+    fn parse_ntfs_test() {
+        let mut ntfs = sector(b"NTFS    ");
+        ntfs[11..13].copy_from_slice(&512u16.to_le_bytes());
+        ntfs[13] = 8;
+        ntfs[40..48].copy_from_slice(&2_097_151u64.to_le_bytes());
+        ntfs[72..80].copy_from_slice(&0x1234_5678_9ABC_DEF0u64.to_le_bytes());
+
+        let boot_sector = parse_boot_sector(&ntfs).unwrap();
+        assert_eq!(boot_sector.file_system, FileSystem::Ntfs);
+        assert_eq!(boot_sector.oem_id, "NTFS");
+        assert_eq!(boot_sector.cluster_size(), 4096);
+        assert_eq!(boot_sector.total_sectors, 2_097_151);
+        assert_eq!(boot_sector.serial_number, 0x1234_5678_9ABC_DEF0);
+
+        // 2 MB clusters of 4096 sectors
+        ntfs[13] = 0xF4;
+        assert_eq!(parse_boot_sector(&ntfs).unwrap().sectors_per_cluster, 4096);
+    }
+
+    #[test]
+    fn parse_exfat_test() {
+        let mut exfat = sector(b"EXFAT   ");
+        exfat[72..80].copy_from_slice(&62_521_344u64.to_le_bytes());
+        exfat[100..104].copy_from_slice(&0xA1B2_C3D4u32.to_le_bytes());
+        exfat[108] = 9;
+        exfat[109] = 8;
+
+        let boot_sector = parse_boot_sector(&exfat).unwrap();
+        assert_eq!(boot_sector.file_system, FileSystem::ExFat);
+        assert_eq!(boot_sector.cluster_size(), 128 * 1024);
+        assert_eq!(boot_sector.size(), 62_521_344 * 512);
+        assert_eq!(boot_sector.serial_number, 0xA1B2_C3D4);
+    }
+
+    #[test]
+    fn parse_fat_test() {
+        let mut fat32 = sector(b"MSDOS5.0");
+        fat32[11..13].copy_from_slice(&512u16.to_le_bytes());
+        fat32[13] = 16;
+        fat32[16] = 2;
+        fat32[32..36].copy_from_slice(&31_250_000u32.to_le_bytes());
+        fat32[66] = FAT_EXTENDED_BOOT_SIGNATURE;
+        fat32[67..71].copy_from_slice(&0x0102_0304u32.to_le_bytes());
+        fat32[71..82].copy_from_slice(b"USB STICK  ");
+        fat32[82..90].copy_from_slice(b"FAT32   ");
+
+        let boot_sector = parse_boot_sector(&fat32).unwrap();
+        assert_eq!(boot_sector.file_system, FileSystem::Fat32);
+        assert_eq!(boot_sector.oem_id, "MSDOS5.0");
+        assert_eq!(boot_sector.cluster_size(), 8192);
+        assert_eq!(boot_sector.total_sectors, 31_250_000);
+        assert_eq!(boot_sector.serial_number, 0x0102_0304);
+        assert_eq!(boot_sector.label.as_deref(), Some("USB STICK"));
+
+        let mut fat16 = sector(b"MSDOS5.0");
+        fat16[11..13].copy_from_slice(&512u16.to_le_bytes());
+        fat16[13] = 4;
+        fat16[16] = 2;
+        fat16[19..21].copy_from_slice(&40_000u16.to_le_bytes());
+        fat16[22..24].copy_from_slice(&40u16.to_le_bytes());
+        fat16[38] = FAT_EXTENDED_BOOT_SIGNATURE;
+        fat16[43..54].copy_from_slice(b"NO NAME    ");
+
+        let boot_sector = parse_boot_sector(&fat16).unwrap();
+        assert_eq!(boot_sector.file_system, FileSystem::Fat);
+        assert_eq!(boot_sector.total_sectors, 40_000);
+        assert_eq!(boot_sector.label, None);
+
+        // Partition tables and blank sectors aren't boot sectors
+        assert_eq!(parse_boot_sector(&sector(b"\0\0\0\0\0\0\0\0")), None);
+        assert_eq!(parse_boot_sector(&[0; 512]), None);
+    }
+}
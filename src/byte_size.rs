@@ -0,0 +1,47 @@
+use std::fmt;
+
+const UNITS: [&str; 7] = ["B", "KB", "MB", "GB", "TB", "PB", "EB"];
+
+/// Number of bytes, displayed in human readable form with 1024 based units and three significant digits,
+/// the way Windows Explorer does, e.g. `512 GB` or `1.82 TB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        let decimals = match value {
+            _ if unit == 0 || value >= 100.0 => 0,
+            _ if value >= 10.0 => 1,
+            _ => 2,
+        };
+        let formatted = format!("{:.*}", decimals, value);
+        let formatted = if formatted.contains('.') {
+            formatted.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            &formatted
+        };
+        write!(f, "{} {}", formatted, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_test() {
+        assert_eq!(ByteSize(0).to_string(), "0 B");
+        assert_eq!(ByteSize(1000).to_string(), "1000 B");
+        assert_eq!(ByteSize(1536).to_string(), "1.5 KB");
+        assert_eq!(ByteSize(512 * 1024 * 1024 * 1024).to_string(), "512 GB");
+        assert_eq!(ByteSize(2_000_398_934_016).to_string(), "1.82 TB");
+        assert_eq!(ByteSize(u64::MAX).to_string(), "16 EB");
+    }
+}
@@ -0,0 +1,90 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::GetFileInformationByHandleEx,
+    Windows::Win32::Storage::FileSystem::FileCaseSensitiveInfo,
+    Windows::Win32::Storage::FileSystem::FILE_READ_ATTRIBUTES,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::{get_volume_information, open_file};
+
+/// Win32 error code for "Incorrect function", returned by file systems without per-directory case sensitivity
+const ERROR_INVALID_FUNCTION: i32 = 1;
+/// Win32 error code for "The request is not supported"
+const ERROR_NOT_SUPPORTED: i32 = 50;
+/// Win32 error code for "The parameter is incorrect", returned by Windows versions before 10 1803
+const ERROR_INVALID_PARAMETER: i32 = 87;
+/// `FILE_CS_FLAG_CASE_SENSITIVE_DIR` flag of `FILE_CASE_SENSITIVE_INFO`
+const FILE_CS_FLAG_CASE_SENSITIVE_DIR: u32 = 0x1;
+
+/// Case sensitivity support of a volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaseSensitivity {
+    /// Whether the file system can preserve and search names case-sensitively (`FILE_CASE_SENSITIVE_SEARCH`)
+    pub case_sensitive_search: bool,
+    /// Whether directories can be flagged case-sensitive, as done by WSL or
+    /// `fsutil file setCaseSensitiveInfo`
+    pub per_directory: bool,
+    /// Whether the root directory of the volume is flagged case-sensitive
+    pub root_case_sensitive: bool,
+}
+
+/// Probes the case sensitivity support of the volume at `lprootpathname`, such as `C:\`. Per-directory
+/// case sensitivity is read like [is_directory_case_sensitive] from the root directory
+///
+/// Minimum OS: Windows XP/Windows Server 2003, Windows 10 1803 for per-directory case sensitivity
+pub fn get_case_sensitivity(lprootpathname: String) -> Result<CaseSensitivity, WinPartitionError> {
+    let flags = get_volume_information(&lprootpathname)?.4;
+    let root = is_directory_case_sensitive(lprootpathname)?;
+
+    Ok(CaseSensitivity {
+        case_sensitive_search: flags.is_case_sensitive(),
+        per_directory: root.is_some(),
+        root_case_sensitive: root == Some(true),
+    })
+}
+
+/// Calls [GetFileInformationByHandleEx](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getfileinformationbyhandleex)
+/// with `FileCaseSensitiveInfo` and returns whether the directory at `path` is flagged case-sensitive.
+/// Returns `None` if the file system or Windows version doesn't support per-directory case sensitivity
+///
+/// Minimum OS: Windows 10 1803
+pub fn is_directory_case_sensitive(path: String) -> Result<Option<bool>, WinPartitionError> {
+    let directory = open_file(&path, FILE_READ_ATTRIBUTES)?;
+    let mut flags: u32 = 0;
+    let result = unsafe {
+        GetFileInformationByHandleEx(
+            directory.handle(),
+            FileCaseSensitiveInfo,
+            &mut flags as *mut u32 as *mut c_void,
+            size_of::<u32>() as u32).as_bool()
+    };
+
+    if result {
+        return Ok(Some(flags & FILE_CS_FLAG_CASE_SENSITIVE_DIR != 0));
+    }
+    let err = WinPartitionError::last_os_error("GetFileInformationByHandleEx", Some(&path));
+    if is_unsupported(&err) {
+        Ok(None)
+    } else {
+        Err(err)
+    }
+}
+
+/// Whether a failed `FileCaseSensitiveInfo` query means the feature isn't available
+fn is_unsupported(err: &WinPartitionError) -> bool {
+    matches!(err.raw_os_error(), ERROR_INVALID_FUNCTION | ERROR_NOT_SUPPORTED | ERROR_INVALID_PARAMETER)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_unsupported_test() {
+        assert!(is_unsupported(&WinPartitionError::from_code("GetFileInformationByHandleEx", None, 87)));
+        assert!(!is_unsupported(&WinPartitionError::from_code("GetFileInformationByHandleEx", None, 5)));
+    }
+}
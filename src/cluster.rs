@@ -0,0 +1,97 @@
+use crate::error::WinPartitionError;
+use crate::file_system::FileSystem;
+use crate::win_api::{get_volume_information, get_volume_path_name};
+use crate::wmi::{associators_query, WmiConnection, CLUSTER_NAMESPACE};
+
+/// Cluster Shared Volume of a failover cluster, mounted below `C:\ClusterStorage` on every node. Its file system
+/// is [FileSystem::Csvfs], which redirects I/O to the node owning the disk when needed, so it must not be
+/// treated as a local NTFS or ReFS volume
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClusterSharedVolume {
+    /// Mount point of the volume, e.g. `C:\ClusterStorage\Volume1`
+    pub path: String,
+    /// Volume GUID path, e.g. `\\?\Volume{1b7d5b9c-...}\`
+    pub volume_name: Option<String>,
+    /// Name of the cluster disk resource holding the volume, e.g. `Cluster Disk 1`, as shown by
+    /// `Get-ClusterSharedVolume`
+    pub resource_name: Option<String>,
+}
+
+impl ClusterSharedVolume {
+    /// Whether `path` is stored on this volume, i.e. it is the mount point or below it
+    pub fn contains(&self, path: &str) -> bool {
+        is_below(&self.path, path)
+    }
+}
+
+/// Lists the Cluster Shared Volumes of the cluster this computer is a node of with the
+/// [MSCluster_ClusterSharedVolume](https://docs.microsoft.com/en-us/previous-versions/windows/desktop/cluswmi/mscluster-clustersharedvolume)
+/// WMI class. Fails if the Failover Clustering feature isn't installed. Requires administrator rights.
+///
+/// Minimum OS: Windows Server 2008 R2
+pub fn get_cluster_shared_volumes() -> Result<Vec<ClusterSharedVolume>, WinPartitionError> {
+    let wmi = WmiConnection::connect(CLUSTER_NAMESPACE)?;
+    let mut volumes = vec![];
+    for volume in wmi.query("SELECT * FROM MSCluster_ClusterSharedVolume")? {
+        let resource = wmi.query(&associators_query(&volume, "MSCluster_Resource")?)?.into_iter().next();
+        let resource_name = match resource {
+            Some(resource) => resource.get_string("Name")?,
+            None => None,
+        };
+        volumes.push(ClusterSharedVolume {
+            path: volume.get_string("Name")?.unwrap_or_default(),
+            volume_name: volume.get_string("VolumeName")?,
+            resource_name,
+        });
+    }
+
+    Ok(volumes)
+}
+
+/// Returns the Cluster Shared Volume `path` is stored on, see [get_cluster_shared_volumes], or `None` if it
+/// isn't on a Cluster Shared Volume. Paths not on a CSVFS volume are recognized without querying the cluster
+///
+/// Minimum OS: Windows Server 2012
+pub fn find_cluster_shared_volume(path: &str) -> Result<Option<ClusterSharedVolume>, WinPartitionError> {
+    if !is_cluster_shared_volume(get_volume_path_name(path.to_string())?)? {
+        return Ok(None);
+    }
+    Ok(get_cluster_shared_volumes()?
+        .into_iter()
+        .filter(|volume| volume.contains(path))
+        .max_by_key(|volume| volume.path.len()))
+}
+
+/// Checks whether the volume at `lprootpathname`, such as `C:\ClusterStorage\Volume1\`, is a Cluster Shared
+/// Volume, i.e. its file system is [FileSystem::Csvfs]. Unlike [get_cluster_shared_volumes] this doesn't need
+/// administrator rights
+///
+/// Minimum OS: Windows Server 2012
+pub fn is_cluster_shared_volume(lprootpathname: String) -> Result<bool, WinPartitionError> {
+    let file_system_name = get_volume_information(lprootpathname)?.1;
+    Ok(FileSystem::from(file_system_name.as_str()) == FileSystem::Csvfs)
+}
+
+/// Whether `path` is `mount_point` or a path below it, ignoring case and trailing backslashes
+fn is_below(mount_point: &str, path: &str) -> bool {
+    let mount_point = mount_point.trim_end_matches('\\').to_lowercase();
+    let path = path.to_lowercase();
+    match path.strip_prefix(&mount_point) {
+        Some(rest) => rest.is_empty() || rest.starts_with('\\'),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_below_test() {
+        assert!(is_below(r"C:\ClusterStorage\Volume1", r"C:\ClusterStorage\Volume1"));
+        assert!(is_below(r"C:\ClusterStorage\Volume1\", r"c:\clusterstorage\volume1\VMs\vm1.vhdx"));
+        assert!(!is_below(r"C:\ClusterStorage\Volume1", r"C:\ClusterStorage\Volume10\VMs"));
+        assert!(!is_below(r"C:\ClusterStorage\Volume1", r"C:\Windows"));
+    }
+}
@@ -0,0 +1,39 @@
+use std::ptr::null_mut;
+
+use crate::bindings::Windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED};
+use crate::error::WinPartitionError;
+
+/// HRESULT returned by `CoInitializeEx` when the thread already uses another COM apartment model
+const RPC_E_CHANGED_MODE: u32 = 0x80010106;
+
+/// Initializes COM on the calling thread for the lifetime of the guard
+pub(crate) struct ComGuard {
+    initialized: bool,
+}
+
+impl ComGuard {
+    pub(crate) fn new() -> Result<ComGuard, WinPartitionError> {
+        match unsafe { CoInitializeEx(null_mut(), COINIT_MULTITHREADED) } {
+            Ok(()) => Ok(ComGuard { initialized: true }),
+            // COM is already initialized as single threaded, which works as well
+            Err(err) if err.code().0 == RPC_E_CHANGED_MODE => Ok(ComGuard { initialized: false }),
+            Err(err) => Err(com_error("CoInitializeEx", None)(err)),
+        }
+    }
+}
+
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        if self.initialized {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
+
+/// Maps the error of a failed COM call to [WinPartitionError]
+pub(crate) fn com_error<'a>(
+    api: &'static str,
+    path: Option<&'a str>,
+) -> impl Fn(windows::Error) -> WinPartitionError + 'a {
+    move |err| WinPartitionError::from_hresult(api, path, err.code().0)
+}
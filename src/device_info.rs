@@ -0,0 +1,321 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Devices::DeviceAndDriverInstallation::CM_Get_Device_IDW,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::CM_Get_Parent,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::DIGCF_DEVICEINTERFACE,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::DIGCF_PRESENT,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SPDRP_DEVICEDESC,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SPDRP_FRIENDLYNAME,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SP_DEVICE_INTERFACE_DATA,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SP_DEVICE_INTERFACE_DETAIL_DATA_W,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SP_DEVINFO_DATA,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiDestroyDeviceInfoList,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiEnumDeviceInterfaces,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetClassDevsW,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetDeviceInstanceIdW,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetDeviceInterfaceDetailW,
+    Windows::Win32::Devices::DeviceAndDriverInstallation::SetupDiGetDeviceRegistryPropertyW,
+    Windows::Win32::Foundation::HWND,
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::Storage::FileSystem::FILE_ACCESS_FLAGS,
+    Windows::Win32::System::SystemServices::GUID_DEVINTERFACE_DISK,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_GET_DEVICE_NUMBER,
+    Windows::Win32::System::SystemServices::STORAGE_DEVICE_NUMBER,
+};
+use crate::error::WinPartitionError;
+use crate::physical_disk::physical_disk_path;
+use crate::win_api::open_device;
+use crate::wstr::vec_u16_to_string;
+
+/// Win32 error code returned when an enumeration has no more items
+const ERROR_NO_MORE_ITEMS: i32 = 259;
+/// Win32 error code for "Element not found"
+const ERROR_NOT_FOUND: i32 = 1168;
+/// `MAX_DEVICE_ID_LEN` of `cfgmgr32.h`, the longest device instance ID
+const MAX_DEVICE_ID_LEN: usize = 200;
+/// `CR_SUCCESS` of `cfgmgr32.h`, the `CONFIGRET` of configuration manager functions that succeeded
+const CR_SUCCESS: u32 = 0;
+
+/// Plug and Play device of a physical disk, as listed by Device Manager under "Disk drives"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskDevice {
+    /// Disk number, as in `\\.\PhysicalDriveN`
+    pub number: u32,
+    /// Device instance ID, e.g. `USBSTOR\DISK&VEN_SANDISK&PROD_CRUZER&REV_1.00\4C530001230405117480&0`, shown
+    /// as "Device instance path" in Device Manager
+    pub instance_id: String,
+    /// Path of the disk device interface, e.g. `\\?\usbstor#disk&ven_sandisk...#{53f56307-...}`. It can be
+    /// opened like `\\.\PhysicalDriveN` and stays the same when disk numbers change
+    pub interface_path: String,
+    /// Human-readable name shown by Device Manager and Explorer, e.g. `Samsung SSD 990 PRO 2TB`. The friendly
+    /// name of the device or else its description, `None` if the device has neither
+    pub friendly_name: Option<String>,
+    /// USB device the disk belongs to, `None` for disks not attached over USB
+    pub usb: Option<UsbDevice>,
+}
+
+/// Identification of a USB device, as in its device instance ID `USB\VID_0781&PID_5567\4C530001230405117480`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsbDevice {
+    /// Vendor ID assigned by the USB-IF, e.g. `0x0781` for SanDisk
+    pub vendor_id: u16,
+    /// Product ID assigned by the vendor
+    pub product_id: u16,
+    /// Serial number reported by the device, `None` if it has none and Windows generated an instance ID instead
+    pub serial_number: Option<String>,
+}
+
+/// Lists the Plug and Play devices of the present physical disks with
+/// [SetupDiGetClassDevsW](https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetclassdevsw)
+/// and the disk device interface class, ordered by disk number
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_disk_devices() -> Result<Vec<DiskDevice>, WinPartitionError> {
+    let devices = DeviceInfoSet::disks()?;
+    let mut disks = vec![];
+    let mut index = 0;
+    while let Some(mut interface) = devices.interface(index)? {
+        index += 1;
+        let mut device = SP_DEVINFO_DATA { cbSize: size_of::<SP_DEVINFO_DATA>() as u32, ..Default::default() };
+        let interface_path = devices.interface_path(&mut interface, &mut device)?;
+        // Disks being removed can't be opened anymore
+        if let Some(number) = query_device_number(&interface_path) {
+            let friendly_name = devices
+                .registry_string(&mut device, SPDRP_FRIENDLYNAME)
+                .or_else(|| devices.registry_string(&mut device, SPDRP_DEVICEDESC));
+            disks.push(DiskDevice {
+                number,
+                instance_id: devices.instance_id(&mut device)?,
+                interface_path,
+                friendly_name,
+                usb: find_usb_device(device.DevInst),
+            });
+        }
+    }
+    disks.sort_by_key(|disk| disk.number);
+
+    Ok(disks)
+}
+
+/// Returns the Plug and Play device of the physical disk `number`, see [get_disk_devices].
+/// Fails with `ERROR_NOT_FOUND` if no present disk has this number
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_disk_device(number: u32) -> Result<DiskDevice, WinPartitionError> {
+    get_disk_devices()?
+        .into_iter()
+        .find(|disk| disk.number == number)
+        .ok_or_else(|| {
+            let path = physical_disk_path(number);
+            WinPartitionError::from_code("SetupDiEnumDeviceInterfaces", Some(&path), ERROR_NOT_FOUND)
+        })
+}
+
+/// Device information set of the present disk device interfaces, destroyed on drop
+struct DeviceInfoSet(*mut c_void);
+
+impl DeviceInfoSet {
+    fn disks() -> Result<DeviceInfoSet, WinPartitionError> {
+        let devices = unsafe {
+            SetupDiGetClassDevsW(&GUID_DEVINTERFACE_DISK, PWSTR::NULL, HWND(0), DIGCF_PRESENT | DIGCF_DEVICEINTERFACE)
+        };
+        // INVALID_HANDLE_VALUE on failure
+        if devices as isize == -1 {
+            Err(WinPartitionError::last_os_error("SetupDiGetClassDevsW", None))
+        } else {
+            Ok(DeviceInfoSet(devices))
+        }
+    }
+
+    /// Device interface at `index`, `None` once all interfaces are enumerated
+    fn interface(&self, index: u32) -> Result<Option<SP_DEVICE_INTERFACE_DATA>, WinPartitionError> {
+        let mut interface = SP_DEVICE_INTERFACE_DATA {
+            cbSize: size_of::<SP_DEVICE_INTERFACE_DATA>() as u32,
+            ..Default::default()
+        };
+        let found = unsafe {
+            SetupDiEnumDeviceInterfaces(self.0, null_mut(), &GUID_DEVINTERFACE_DISK, index, &mut interface).as_bool()
+        };
+        if found {
+            return Ok(Some(interface));
+        }
+        let err = WinPartitionError::last_os_error("SetupDiEnumDeviceInterfaces", None);
+        if err.raw_os_error() == ERROR_NO_MORE_ITEMS {
+            Ok(None)
+        } else {
+            Err(err)
+        }
+    }
+
+    /// Path of a device interface, filling in the device it belongs to
+    fn interface_path(
+        &self,
+        interface: &mut SP_DEVICE_INTERFACE_DATA,
+        device: &mut SP_DEVINFO_DATA,
+    ) -> Result<String, WinPartitionError> {
+        let mut required_size: u32 = 0;
+        // Fails with ERROR_INSUFFICIENT_BUFFER, returning the required size
+        unsafe {
+            SetupDiGetDeviceInterfaceDetailW(self.0, interface, null_mut(), 0, &mut required_size, null_mut())
+        };
+
+        let mut buffer: Vec<u64> = vec![0; (required_size as usize).div_ceil(size_of::<u64>()).max(1)];
+        let detail = buffer.as_mut_ptr() as *mut SP_DEVICE_INTERFACE_DETAIL_DATA_W;
+        // The size of the fixed part, which is packed on 32-bit Windows
+        unsafe { (*detail).cbSize = if cfg!(target_pointer_width = "64") { 8 } else { 6 } };
+        let result = unsafe {
+            SetupDiGetDeviceInterfaceDetailW(
+                self.0,
+                interface,
+                detail,
+                (buffer.len() * size_of::<u64>()) as u32,
+                &mut required_size,
+                device).as_bool()
+        };
+        if !result {
+            return Err(WinPartitionError::last_os_error("SetupDiGetDeviceInterfaceDetailW", None));
+        }
+
+        let path = unsafe {
+            let start = (*detail).DevicePath.as_ptr();
+            let len = (buffer.len() * size_of::<u64>() - (start as usize - detail as usize)) / size_of::<u16>();
+            std::slice::from_raw_parts(start, len)
+        };
+        Ok(vec_u16_to_string(path))
+    }
+
+    /// Calls [SetupDiGetDeviceInstanceIdW](https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinstanceidw)
+    fn instance_id(&self, device: &mut SP_DEVINFO_DATA) -> Result<String, WinPartitionError> {
+        let mut instance_id: Vec<u16> = vec![0; MAX_DEVICE_ID_LEN + 1];
+        let result = unsafe {
+            SetupDiGetDeviceInstanceIdW(
+                self.0,
+                device,
+                PWSTR(instance_id.as_mut_ptr()),
+                instance_id.len() as u32,
+                null_mut()).as_bool()
+        };
+        if result {
+            Ok(vec_u16_to_string(&instance_id))
+        } else {
+            Err(WinPartitionError::last_os_error("SetupDiGetDeviceInstanceIdW", None))
+        }
+    }
+
+    /// String `property` of a device read with
+    /// [SetupDiGetDeviceRegistryPropertyW](https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceregistrypropertyw),
+    /// `None` if the device doesn't have it or it is empty
+    fn registry_string(&self, device: &mut SP_DEVINFO_DATA, property: u32) -> Option<String> {
+        let mut required_size: u32 = 0;
+        // Fails with ERROR_INSUFFICIENT_BUFFER, returning the required size
+        unsafe {
+            SetupDiGetDeviceRegistryPropertyW(
+                self.0,
+                device,
+                property,
+                null_mut(),
+                null_mut(),
+                0,
+                &mut required_size)
+        };
+        if required_size == 0 {
+            return None;
+        }
+
+        let mut buffer: Vec<u16> = vec![0; (required_size as usize).div_ceil(size_of::<u16>())];
+        let result = unsafe {
+            SetupDiGetDeviceRegistryPropertyW(
+                self.0,
+                device,
+                property,
+                null_mut(),
+                buffer.as_mut_ptr() as *mut u8,
+                (buffer.len() * size_of::<u16>()) as u32,
+                null_mut()).as_bool()
+        };
+        Some(vec_u16_to_string(&buffer)).filter(|value| result && !value.is_empty())
+    }
+}
+
+impl Drop for DeviceInfoSet {
+    fn drop(&mut self) {
+        unsafe { SetupDiDestroyDeviceInfoList(self.0) };
+    }
+}
+
+/// Walks up the device tree from the devnode of a disk to the USB device it belongs to. Disks of USB mass
+/// storage devices are children of the device, or of one of its interfaces for composite devices
+fn find_usb_device(mut devnode: u32) -> Option<UsbDevice> {
+    loop {
+        let mut parent: u32 = 0;
+        if unsafe { CM_Get_Parent(&mut parent, devnode, 0) }.0 != CR_SUCCESS {
+            return None;
+        }
+        devnode = parent;
+
+        let mut instance_id: Vec<u16> = vec![0; MAX_DEVICE_ID_LEN + 1];
+        let result = unsafe {
+            CM_Get_Device_IDW(devnode, PWSTR(instance_id.as_mut_ptr()), instance_id.len() as u32, 0)
+        };
+        if result.0 != CR_SUCCESS {
+            return None;
+        }
+        let instance_id = vec_u16_to_string(&instance_id);
+        // Interfaces of composite devices have an `&MI_xx` suffix and their own instance ID
+        if let Some(usb) = parse_usb_instance_id(&instance_id).filter(|_| !instance_id.contains("&MI_")) {
+            return Some(usb);
+        }
+    }
+}
+
+/// Decodes the device instance ID of a USB device, e.g. `USB\VID_0781&PID_5567\4C530001230405117480`. `None`
+/// if it isn't a USB device
+fn parse_usb_instance_id(instance_id: &str) -> Option<UsbDevice> {
+    let mut parts = instance_id.split('\\');
+    if !parts.next()?.eq_ignore_ascii_case("USB") {
+        return None;
+    }
+    let hardware_id = parts.next()?.to_ascii_uppercase();
+    let id = |prefix: &str| {
+        let start = hardware_id.find(prefix)? + prefix.len();
+        u16::from_str_radix(hardware_id.get(start..start + 4)?, 16).ok()
+    };
+    let (vendor_id, product_id) = (id("VID_")?, id("PID_")?);
+    // Instance IDs generated for devices without serial number contain `&`, e.g. `5&2C3A8F1E&0&2`
+    let serial_number = parts.next().filter(|serial| !serial.is_empty() && !serial.contains('&'));
+
+    Some(UsbDevice { vendor_id, product_id, serial_number: serial_number.map(str::to_string) })
+}
+
+/// Disk number of a disk device interface, `None` if it can't be opened or has no number
+fn query_device_number(interface_path: &str) -> Option<u32> {
+    let device = open_device(interface_path, FILE_ACCESS_FLAGS(0)).ok()?;
+    let number: STORAGE_DEVICE_NUMBER =
+        device.query("IOCTL_STORAGE_GET_DEVICE_NUMBER", IOCTL_STORAGE_GET_DEVICE_NUMBER).ok()?;
+    Some(number.DeviceNumber)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_usb_instance_id_test() {
+        let usb = parse_usb_instance_id(r"USB\VID_0781&PID_5567\4C530001230405117480").unwrap();
+        assert_eq!(usb.vendor_id, 0x0781);
+        assert_eq!(usb.product_id, 0x5567);
+        assert_eq!(usb.serial_number.as_deref(), Some("4C530001230405117480"));
+
+        let usb = parse_usb_instance_id(r"USB\VID_0BDA&PID_9210&MI_00\6&1A2B3C4D&0&0000").unwrap();
+        assert_eq!((usb.vendor_id, usb.product_id, usb.serial_number), (0x0BDA, 0x9210, None));
+
+        let usb_storage = r"USBSTOR\DISK&VEN_SANDISK&PROD_CRUZER&REV_1.00\4C530001230405117480&0";
+        assert_eq!(parse_usb_instance_id(usb_storage), None);
+        assert_eq!(parse_usb_instance_id(r"PCI\VEN_144D&DEV_A80C&SUBSYS_A801144D&REV_00\4&1F2E3D4C&0&0008"), None);
+    }
+}
@@ -0,0 +1,112 @@
+use std::ffi::c_void;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::NetworkManagement::NetManagement::NetApiBufferFree,
+    Windows::Win32::Storage::DistributedFileSystem::DFS_INFO_3,
+    Windows::Win32::Storage::DistributedFileSystem::DFS_STORAGE_STATE_ACTIVE,
+    Windows::Win32::Storage::DistributedFileSystem::NetDfsGetClientInfo,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::pwstr_to_string;
+
+/// Win32 error code for "Element not found", returned for paths outside a DFS namespace
+const ERROR_NOT_FOUND: u32 = 1168;
+/// `NERR_DfsNoSuchVolume` of `lmerr.h`, returned for paths the DFS client has no referral for
+const NERR_DFS_NO_SUCH_VOLUME: u32 = 2662;
+
+/// Folder target of a DFS namespace entry, the share of a real file server
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DfsTarget {
+    /// Name of the file server, e.g. `fs01`
+    pub server: String,
+    /// Name of the share on the server, e.g. `projects`
+    pub share: String,
+    /// Whether the DFS client currently sends requests for the entry to this target
+    pub active: bool,
+}
+
+impl DfsTarget {
+    /// UNC path of the target share, e.g. `\\fs01\projects`
+    pub fn unc_path(&self) -> String {
+        format!("\\\\{}\\{}", self.server, self.share)
+    }
+}
+
+/// Referral of the DFS namespace root or link a UNC path belongs to, as cached by the DFS client
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DfsReferral {
+    /// UNC path of the namespace root or link, e.g. `\\corp.example.com\files\projects`
+    pub entry_path: String,
+    /// Targets of the entry in referral order
+    pub targets: Vec<DfsTarget>,
+}
+
+impl DfsReferral {
+    /// Target the DFS client is currently connected to, `None` before the entry is first accessed
+    pub fn active_target(&self) -> Option<&DfsTarget> {
+        self.targets.iter().find(|target| target.active)
+    }
+}
+
+/// Returns the DFS referral of the UNC path `path`, such as the
+/// [remote_path](crate::windows_partitions::WindowsPartition::remote_path) of a network drive, or `None` if it
+/// isn't in a DFS namespace. Calls
+/// [NetDfsGetClientInfo](https://docs.microsoft.com/en-us/windows/win32/api/lmdfs/nf-lmdfs-netdfsgetclientinfo),
+/// which answers from the referral cache of the DFS client
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_dfs_referral(path: String) -> Result<Option<DfsReferral>, WinPartitionError> {
+    let mut buffer: *mut u8 = null_mut();
+    let result = unsafe { NetDfsGetClientInfo(path.as_str(), PWSTR::NULL, PWSTR::NULL, 3, &mut buffer) };
+    match result {
+        0 => {}
+        ERROR_NOT_FOUND | NERR_DFS_NO_SUCH_VOLUME => return Ok(None),
+        code => return Err(WinPartitionError::from_code("NetDfsGetClientInfo", Some(&path), code as i32)),
+    }
+
+    let referral = unsafe {
+        let info = &*(buffer as *const DFS_INFO_3);
+        let storages = if info.Storage.is_null() {
+            &[]
+        } else {
+            std::slice::from_raw_parts(info.Storage, info.NumberOfStorages as usize)
+        };
+        DfsReferral {
+            entry_path: pwstr_to_string(info.EntryPath),
+            targets: storages
+                .iter()
+                .map(|storage| DfsTarget {
+                    server: pwstr_to_string(storage.ServerName),
+                    share: pwstr_to_string(storage.ShareName),
+                    active: storage.State & DFS_STORAGE_STATE_ACTIVE != 0,
+                })
+                .collect(),
+        }
+    };
+    unsafe { NetApiBufferFree(buffer as *mut c_void) };
+
+    Ok(Some(referral))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn active_target_test() {
+        let target = |server: &str, active| DfsTarget {
+            server: server.to_string(),
+            share: "projects".to_string(),
+            active,
+        };
+        let referral = DfsReferral {
+            entry_path: "\\\\corp.example.com\\files\\projects".to_string(),
+            targets: vec![target("fs01", false), target("fs02", true)],
+        };
+        assert_eq!(referral.active_target().map(DfsTarget::unc_path).as_deref(), Some("\\\\fs02\\projects"));
+    }
+}
@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::ptr::null_mut;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::bindings::{
+    Windows::Win32::Foundation::HWND,
+    Windows::Win32::Foundation::LPARAM,
+    Windows::Win32::Foundation::LRESULT,
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::Foundation::WPARAM,
+    Windows::Win32::System::LibraryLoader::GetModuleHandleW,
+    Windows::Win32::System::SystemServices::DBTF_MEDIA,
+    Windows::Win32::System::SystemServices::DBTF_NET,
+    Windows::Win32::System::SystemServices::DBT_DEVICEARRIVAL,
+    Windows::Win32::System::SystemServices::DBT_DEVICEREMOVECOMPLETE,
+    Windows::Win32::System::SystemServices::DBT_DEVTYP_VOLUME,
+    Windows::Win32::System::SystemServices::DEV_BROADCAST_HDR,
+    Windows::Win32::System::SystemServices::DEV_BROADCAST_VOLUME,
+    Windows::Win32::UI::WindowsAndMessaging::CreateWindowExW,
+    Windows::Win32::UI::WindowsAndMessaging::DefWindowProcW,
+    Windows::Win32::UI::WindowsAndMessaging::DestroyWindow,
+    Windows::Win32::UI::WindowsAndMessaging::DispatchMessageW,
+    Windows::Win32::UI::WindowsAndMessaging::GetMessageW,
+    Windows::Win32::UI::WindowsAndMessaging::HMENU,
+    Windows::Win32::UI::WindowsAndMessaging::MSG,
+    Windows::Win32::UI::WindowsAndMessaging::PostMessageW,
+    Windows::Win32::UI::WindowsAndMessaging::PostQuitMessage,
+    Windows::Win32::UI::WindowsAndMessaging::RegisterClassW,
+    Windows::Win32::UI::WindowsAndMessaging::TranslateMessage,
+    Windows::Win32::UI::WindowsAndMessaging::WINDOW_EX_STYLE,
+    Windows::Win32::UI::WindowsAndMessaging::WINDOW_STYLE,
+    Windows::Win32::UI::WindowsAndMessaging::WM_CLOSE,
+    Windows::Win32::UI::WindowsAndMessaging::WM_DESTROY,
+    Windows::Win32::UI::WindowsAndMessaging::WM_DEVICECHANGE,
+    Windows::Win32::UI::WindowsAndMessaging::WNDCLASSW,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::drive_mask_to_letters;
+
+/// Win32 error code returned when a window class is registered twice
+const ERROR_CLASS_ALREADY_EXISTS: i32 = 1410;
+/// Name of the hidden window class receiving device broadcasts
+const WINDOW_CLASS_NAME: &str = "win_partitions_drive_watcher";
+
+/// Channel of a watcher and the events it delivers
+struct EventSender {
+    sender: Sender<DriveEvent>,
+    /// Only delivers media insertions and removals, see [DriveWatcher::media]
+    media_only: bool,
+}
+
+thread_local! {
+    /// Channel of the watcher owning the window of the current thread
+    static EVENT_SENDER: RefCell<Option<EventSender>> = const { RefCell::new(None) };
+}
+
+/// Whether a drive appeared or disappeared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DriveEventKind {
+    /// A drive was plugged in, mapped, or media was inserted
+    Arrival,
+    /// A drive was unplugged, unmapped, or media was removed
+    Removal,
+}
+
+/// Drive arrival or removal reported by a [DriveWatcher]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DriveEvent {
+    /// Whether the drive appeared or disappeared
+    pub kind: DriveEventKind,
+    /// Drive letter of the drive
+    pub letter: char,
+    /// Indicates a network drive being mapped or unmapped
+    pub network: bool,
+    /// Indicates media being inserted into or ejected from a drive that stays, such as a CD/DVD drive or a card
+    /// reader. The drive letter is still assigned after a media removal, but the drive is not ready
+    pub media: bool,
+}
+
+/// Subscription to drive arrival and removal notifications.
+///
+/// Listens for [WM_DEVICECHANGE](https://docs.microsoft.com/en-us/windows/win32/devio/wm-devicechange) volume
+/// broadcasts on a hidden window owned by a background thread, and delivers them as [DriveEvent]s over a channel.
+/// The window and its thread are destroyed when the watcher is dropped.
+///
+/// ```no_run
+/// use win_partitions::drive_events::{DriveEventKind, DriveWatcher};
+///
+/// let watcher = DriveWatcher::media().unwrap();
+/// for event in watcher.events() {
+///     match event.kind {
+///         DriveEventKind::Arrival => println!("Disc inserted in {}:", event.letter),
+///         DriveEventKind::Removal => println!("Disc ejected from {}:", event.letter),
+///     }
+/// }
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub struct DriveWatcher {
+    window: HWND,
+    events: Receiver<DriveEvent>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DriveWatcher {
+    /// Starts watching for drive arrivals and removals
+    pub fn new() -> Result<DriveWatcher, WinPartitionError> {
+        DriveWatcher::start(false)
+    }
+
+    /// Starts watching for media insertions and removals only, such as CDs and DVDs in optical drives, instead
+    /// of polling the drives until they stop failing with `ERROR_NOT_READY`
+    pub fn media() -> Result<DriveWatcher, WinPartitionError> {
+        DriveWatcher::start(true)
+    }
+
+    fn start(media_only: bool) -> Result<DriveWatcher, WinPartitionError> {
+        let (sender, events) = channel();
+        let (window_sender, window_receiver) = sync_channel(1);
+        let thread = thread::spawn(move || {
+            let window = create_window();
+            let created = window.is_ok();
+            let _ = window_sender.send(window);
+            if created {
+                EVENT_SENDER.with(|event_sender| *event_sender.borrow_mut() = Some(EventSender { sender, media_only }));
+                run_message_loop();
+            }
+        });
+
+        // The thread only stops before sending when it panics
+        match window_receiver.recv() {
+            Ok(Ok(window)) => Ok(DriveWatcher { window, events, thread: Some(thread) }),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(WinPartitionError::from_code("CreateWindowExW", None, 0)),
+        }
+    }
+
+    /// Channel receiving drive events. It is disconnected once the watcher is dropped
+    pub fn events(&self) -> &Receiver<DriveEvent> {
+        &self.events
+    }
+}
+
+impl Drop for DriveWatcher {
+    fn drop(&mut self) {
+        unsafe { PostMessageW(self.window, WM_CLOSE, WPARAM(0), LPARAM(0)) };
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Creates a hidden top-level window. Message-only windows don't receive device broadcasts
+fn create_window() -> Result<HWND, WinPartitionError> {
+    let mut class_name: Vec<u16> = WINDOW_CLASS_NAME.encode_utf16().chain(Some(0)).collect();
+    let instance = unsafe { GetModuleHandleW(PWSTR(null_mut())) };
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance,
+        lpszClassName: PWSTR(class_name.as_mut_ptr()),
+        ..Default::default()
+    };
+    if unsafe { RegisterClassW(&class) } == 0 {
+        let err = WinPartitionError::last_os_error("RegisterClassW", None);
+        if err.raw_os_error() != ERROR_CLASS_ALREADY_EXISTS {
+            return Err(err);
+        }
+    }
+
+    let window = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE(0),
+            WINDOW_CLASS_NAME,
+            WINDOW_CLASS_NAME,
+            WINDOW_STYLE(0),
+            0,
+            0,
+            0,
+            0,
+            HWND(0),
+            HMENU(0),
+            instance,
+            null_mut())
+    };
+    if window.0 == 0 {
+        Err(WinPartitionError::last_os_error("CreateWindowExW", None))
+    } else {
+        Ok(window)
+    }
+}
+
+fn run_message_loop() {
+    let mut message = MSG::default();
+    // GetMessageW returns 0 on WM_QUIT and -1 on failure
+    while unsafe { GetMessageW(&mut message, HWND(0), 0, 0) }.0 > 0 {
+        unsafe {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
+
+unsafe extern "system" fn window_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match message {
+        WM_DEVICECHANGE => {
+            if let Some(events) = device_change_events(wparam.0 as u32, lparam.0 as *const DEV_BROADCAST_HDR) {
+                EVENT_SENDER.with(|sender| {
+                    if let Some(sender) = sender.borrow().as_ref() {
+                        for event in events.into_iter().filter(|event| event.media || !sender.media_only) {
+                            let _ = sender.sender.send(event);
+                        }
+                    }
+                });
+            }
+            LRESULT(1)
+        }
+        WM_CLOSE => {
+            DestroyWindow(window);
+            LRESULT(0)
+        }
+        WM_DESTROY => {
+            PostQuitMessage(0);
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(window, message, wparam, lparam),
+    }
+}
+
+/// Converts a volume arrival or removal broadcast to drive events
+unsafe fn device_change_events(event: u32, header: *const DEV_BROADCAST_HDR) -> Option<Vec<DriveEvent>> {
+    let kind = match event {
+        DBT_DEVICEARRIVAL => DriveEventKind::Arrival,
+        DBT_DEVICEREMOVECOMPLETE => DriveEventKind::Removal,
+        _ => return None,
+    };
+    if header.is_null() || (*header).dbch_devicetype != DBT_DEVTYP_VOLUME {
+        return None;
+    }
+
+    let volume = &*(header as *const DEV_BROADCAST_VOLUME);
+    let network = volume.dbcv_flags.0 & DBTF_NET.0 != 0;
+    let media = volume.dbcv_flags.0 & DBTF_MEDIA.0 != 0;
+    Some(drive_mask_to_letters(volume.dbcv_unitmask)
+        .into_iter()
+        .map(|letter| DriveEvent { kind, letter, network, media })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn device_change_events_test() {
+        let volume = DEV_BROADCAST_VOLUME {
+            dbcv_size: std::mem::size_of::<DEV_BROADCAST_VOLUME>() as u32,
+            dbcv_devicetype: DBT_DEVTYP_VOLUME.0,
+            dbcv_reserved: 0,
+            // D: and F:
+            dbcv_unitmask: 0b101000,
+            dbcv_flags: DBTF_MEDIA,
+        };
+        let header = &volume as *const DEV_BROADCAST_VOLUME as *const DEV_BROADCAST_HDR;
+
+        let events = unsafe { device_change_events(DBT_DEVICEREMOVECOMPLETE, header) }.unwrap();
+        assert_eq!(events, vec![
+            DriveEvent { kind: DriveEventKind::Removal, letter: 'D', network: false, media: true },
+            DriveEvent { kind: DriveEventKind::Removal, letter: 'F', network: false, media: true },
+        ]);
+        assert_eq!(unsafe { device_change_events(0x0007, header) }, None);
+    }
+}
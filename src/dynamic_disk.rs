@@ -0,0 +1,95 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::GET_LENGTH_INFORMATION,
+    Windows::Win32::Storage::FileSystem::IOCTL_VOLUME_LOGICAL_TO_PHYSICAL,
+    Windows::Win32::Storage::FileSystem::VOLUME_LOGICAL_OFFSET,
+    Windows::Win32::Storage::FileSystem::VOLUME_PHYSICAL_OFFSETS,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_LENGTH_INFO,
+};
+use crate::error::WinPartitionError;
+use crate::physical_disk::get_drive_layout;
+use crate::win_api::{get_volume_disk_extents, open_device, volume_device_path, DeviceHandle};
+
+/// Stripe size of striped and RAID-5 dynamic volumes, which can't be changed
+const STRIPE_SIZE: i64 = 64 * 1024;
+
+/// How a volume is laid out on its disks. Volumes on basic disks are always simple, the other layouts are
+/// only possible with dynamic disks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VolumeLayout {
+    /// A single extent, e.g. a partition of a basic disk
+    Simple,
+    /// Extents concatenated one after the other, possibly on different disks
+    Spanned,
+    /// Data interleaved in stripes across disks (RAID-0)
+    Striped,
+    /// The same data written to two disks (RAID-1)
+    Mirrored,
+    /// Data and parity interleaved in stripes across three or more disks
+    Raid5,
+}
+
+/// Checks whether the physical disk `number`, as in `\\.\PhysicalDriveN`, is a dynamic disk managed by the
+/// Logical Disk Manager. Its partition table holds an LDM partition, of type `0x42` on MBR disks or
+/// [GptPartitionType::LdmMetadata](crate::physical_disk::GptPartitionType::LdmMetadata) on GPT disks, and
+/// its volumes are described by the LDM database instead of the partition table
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn is_dynamic_disk(number: u32) -> Result<bool, WinPartitionError> {
+    Ok(get_drive_layout(number)?.is_dynamic())
+}
+
+/// Determines the layout of the volume at `lprootpathname`, a root path such as `E:\` or a volume GUID path,
+/// from its disk extents, see [get_volume_disk_extents]. Volumes with more than one extent are told apart by
+/// mapping logical offsets to the disks with
+/// [IOCTL_VOLUME_LOGICAL_TO_PHYSICAL](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_volume_logical_to_physical),
+/// which requires administrator rights
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_volume_layout(lprootpathname: String) -> Result<VolumeLayout, WinPartitionError> {
+    let extents = get_volume_disk_extents(lprootpathname.clone())?;
+    if extents.len() <= 1 {
+        return Ok(VolumeLayout::Simple);
+    }
+
+    let volume = open_device(&volume_device_path(&lprootpathname), FILE_GENERIC_READ)?;
+    let first = query_physical_disks(&volume, 0)?;
+    if first.len() > 1 {
+        return Ok(VolumeLayout::Mirrored);
+    }
+    // One extent of a RAID-5 volume worth of capacity holds parity
+    let length: GET_LENGTH_INFORMATION = volume.query("IOCTL_DISK_GET_LENGTH_INFO", IOCTL_DISK_GET_LENGTH_INFO)?;
+    let extents_length: u64 = extents.iter().map(|extent| extent.extent_length).sum();
+    let count = extents.len() as u64;
+    if length.Length.max(0) as u64 * count <= extents_length * (count - 1) {
+        return Ok(VolumeLayout::Raid5);
+    }
+    // The second stripe of a striped volume is on the next disk, a spanned volume continues on the first one
+    if query_physical_disks(&volume, STRIPE_SIZE)? != first {
+        Ok(VolumeLayout::Striped)
+    } else {
+        Ok(VolumeLayout::Spanned)
+    }
+}
+
+/// Disk numbers holding the byte at `offset` of a volume, several for a mirrored volume
+fn query_physical_disks(volume: &DeviceHandle, offset: i64) -> Result<Vec<u32>, WinPartitionError> {
+    let mut input = VOLUME_LOGICAL_OFFSET { LogicalOffset: offset };
+    let buffer = unsafe {
+        volume.query_variable(
+            "IOCTL_VOLUME_LOGICAL_TO_PHYSICAL",
+            IOCTL_VOLUME_LOGICAL_TO_PHYSICAL,
+            &mut input as *mut VOLUME_LOGICAL_OFFSET as *mut c_void,
+            size_of::<VOLUME_LOGICAL_OFFSET>() as u32)?
+    };
+
+    let offsets = unsafe {
+        let header = &*(buffer.as_ptr() as *const VOLUME_PHYSICAL_OFFSETS);
+        std::slice::from_raw_parts(header.PhysicalOffset.as_ptr(), header.NumberOfPhysicalOffsets as usize)
+    };
+    Ok(offsets.iter().map(|offset| offset.DiskNumber).collect())
+}
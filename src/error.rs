@@ -0,0 +1,161 @@
+use std::fmt;
+use std::io;
+
+/// Win32 error code for "Access is denied"
+const ERROR_ACCESS_DENIED: i32 = 5;
+/// Win32 error code for "The device is not ready"
+const ERROR_NOT_READY: i32 = 21;
+/// Win32 error code for "This operation returned because the timeout period expired"
+const ERROR_TIMEOUT: i32 = 1460;
+
+/// Error returned by functions of this crate.
+///
+/// Every variant remembers which Windows API function failed and, when the call was made
+/// against a drive or volume, the path that was passed to it.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WinPartitionError {
+    /// The device is not ready; for example, there is no CD in a CD-Rom drive
+    /// or no card in a card reader (`ERROR_NOT_READY`)
+    NotReady {
+        /// Name of the failed Windows API function
+        api: &'static str,
+        /// Path passed to the failed function, if any
+        path: Option<String>,
+    },
+    /// Access to the drive or volume was denied (`ERROR_ACCESS_DENIED`)
+    AccessDenied {
+        /// Name of the failed Windows API function
+        api: &'static str,
+        /// Path passed to the failed function, if any
+        path: Option<String>,
+    },
+    /// The call didn't finish in time; for example, a disconnected network drive (`ERROR_TIMEOUT`)
+    TimedOut {
+        /// Name of the Windows API function that didn't finish
+        api: &'static str,
+        /// Path passed to the function, if any
+        path: Option<String>,
+    },
+    /// Any other error reported by a Windows API function
+    Os {
+        /// Name of the failed Windows API function
+        api: &'static str,
+        /// Path passed to the failed function, if any
+        path: Option<String>,
+        /// Raw Win32 error code, or the HRESULT of a failed COM call that doesn't wrap one
+        code: i32,
+    },
+}
+
+impl WinPartitionError {
+    /// Creates an error from a raw Win32 error code
+    pub fn from_code(api: &'static str, path: Option<&str>, code: i32) -> Self {
+        let path = path.map(|path| path.to_string());
+        match code {
+            ERROR_NOT_READY => WinPartitionError::NotReady { api, path },
+            ERROR_ACCESS_DENIED => WinPartitionError::AccessDenied { api, path },
+            ERROR_TIMEOUT => WinPartitionError::TimedOut { api, path },
+            _ => WinPartitionError::Os { api, path, code },
+        }
+    }
+
+    /// Creates an error from the HRESULT of a failed COM call.
+    /// HRESULTs wrapping a Win32 error code (`0x8007xxxx`) are unwrapped to it
+    pub fn from_hresult(api: &'static str, path: Option<&str>, hresult: u32) -> Self {
+        if hresult & 0xffff_0000 == 0x8007_0000 {
+            WinPartitionError::from_code(api, path, (hresult & 0xffff) as i32)
+        } else {
+            WinPartitionError::from_code(api, path, hresult as i32)
+        }
+    }
+
+    /// Creates an error from the calling thread's last Win32 error code (`GetLastError`)
+    pub fn last_os_error(api: &'static str, path: Option<&str>) -> Self {
+        let code = io::Error::last_os_error().raw_os_error().unwrap_or(0);
+        WinPartitionError::from_code(api, path, code)
+    }
+
+    /// Name of the Windows API function that failed
+    pub fn api(&self) -> &'static str {
+        match self {
+            WinPartitionError::NotReady { api, .. } => api,
+            WinPartitionError::AccessDenied { api, .. } => api,
+            WinPartitionError::TimedOut { api, .. } => api,
+            WinPartitionError::Os { api, .. } => api,
+        }
+    }
+
+    /// Path passed to the failed function, if any
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            WinPartitionError::NotReady { path, .. } => path.as_deref(),
+            WinPartitionError::AccessDenied { path, .. } => path.as_deref(),
+            WinPartitionError::TimedOut { path, .. } => path.as_deref(),
+            WinPartitionError::Os { path, .. } => path.as_deref(),
+        }
+    }
+
+    /// Drive letter the failed call was made against, if the path starts with one
+    pub fn letter(&self) -> Option<char> {
+        let mut chars = self.path()?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+
+    /// Raw Win32 error code
+    pub fn raw_os_error(&self) -> i32 {
+        match self {
+            WinPartitionError::NotReady { .. } => ERROR_NOT_READY,
+            WinPartitionError::AccessDenied { .. } => ERROR_ACCESS_DENIED,
+            WinPartitionError::TimedOut { .. } => ERROR_TIMEOUT,
+            WinPartitionError::Os { code, .. } => *code,
+        }
+    }
+}
+
+impl fmt::Display for WinPartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed", self.api())?;
+        if let Some(path) = self.path() {
+            write!(f, " for {}", path)?;
+        }
+        write!(f, ": {}", io::Error::from_raw_os_error(self.raw_os_error()))
+    }
+}
+
+impl std::error::Error for WinPartitionError {}
+
+impl From<WinPartitionError> for io::Error {
+    fn from(err: WinPartitionError) -> Self {
+        let kind = io::Error::from_raw_os_error(err.raw_os_error()).kind();
+        io::Error::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_code_test() {
+        let err = WinPartitionError::from_code("GetDiskFreeSpaceExW", Some("e:\\"), 21);
+        assert!(matches!(err, WinPartitionError::NotReady { .. }));
+        assert_eq!(err.letter(), Some('E'));
+        assert_eq!(err.raw_os_error(), 21);
+
+        let err = WinPartitionError::from_code("GetLogicalDrives", None, 87);
+        assert!(matches!(err, WinPartitionError::Os { code: 87, .. }));
+        assert_eq!(err.letter(), None);
+
+        let err = WinPartitionError::from_code("GetVolumeInformationW", Some("Z:\\"), 1460);
+        assert!(matches!(err, WinPartitionError::TimedOut { .. }));
+
+        let err = WinPartitionError::from_hresult("IDiskQuotaControl::Initialize", Some("C:\\"), 0x8007_0015);
+        assert!(matches!(err, WinPartitionError::NotReady { .. }));
+        let err = WinPartitionError::from_hresult("CoCreateInstance", None, 0x8004_0154);
+        assert_eq!(err.raw_os_error() as u32, 0x8004_0154);
+    }
+}
@@ -0,0 +1,178 @@
+//! C ABI for the partition queries, enabled by the `ffi` feature.
+//!
+//! C, C++ and C# applications link against a DLL of the crate, built with
+//! `cargo rustc --release --features ffi --crate-type cdylib`. The matching header is `include/win_partitions.h`,
+//! generated with `cbindgen --config cbindgen.toml --output include/win_partitions.h`.
+//!
+//! Functions return [WP_OK] on success, a negative `WP_ERROR_*` code on misuse, or the positive Win32 error
+//! code of a failed Windows API call. Strings are null terminated UTF-16, truncated to fit their buffer.
+
+use std::panic::catch_unwind;
+use std::ptr::null_mut;
+
+use crate::windows_partitions::{get_partitions, WindowsPartition};
+
+/// Length in UTF-16 code units of the string buffers in [WpPartition], including the null terminator
+pub const WP_STRING_LENGTH: usize = 261;
+
+/// The call succeeded
+pub const WP_OK: i32 = 0;
+/// A required pointer argument was null
+pub const WP_ERROR_NULL_POINTER: i32 = -1;
+/// The caller-provided buffer is too small; the required number of entries was written to `count`
+pub const WP_ERROR_BUFFER_TOO_SMALL: i32 = -2;
+/// The crate panicked; this is a bug in the crate
+pub const WP_ERROR_PANIC: i32 = -3;
+
+/// C representation of [WindowsPartition]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WpPartition {
+    /// Drive letter as a UTF-16 code unit, e.g. `'C'`
+    pub letter: u16,
+    /// Whether the partition is ready, see [WindowsPartition::ready]
+    pub ready: bool,
+    /// Drive type code as returned by `GetDriveTypeW`, see [DriveType](crate::win_api::DriveType)
+    pub drive_type: u32,
+    /// Total size of partition in bytes
+    pub size: u64,
+    /// Free space in bytes
+    pub free_space: u64,
+    /// Cluster size in bytes, 0 if it couldn't be read
+    pub allocation_unit_size: u32,
+    /// Partition name
+    pub name: [u16; WP_STRING_LENGTH],
+    /// Partition format name, e.g. `NTFS`
+    pub file_system_name: [u16; WP_STRING_LENGTH],
+}
+
+impl From<&WindowsPartition> for WpPartition {
+    fn from(partition: &WindowsPartition) -> Self {
+        WpPartition {
+            letter: partition.letter as u16,
+            ready: partition.ready,
+            drive_type: partition.drive_type as u32,
+            size: partition.size,
+            free_space: partition.free_space,
+            allocation_unit_size: partition.allocation_unit_size,
+            name: to_wide_array(&partition.name),
+            file_system_name: to_wide_array(&partition.file_system_name),
+        }
+    }
+}
+
+/// Encodes `value` as null terminated UTF-16, truncated to fit the buffer
+fn to_wide_array(value: &str) -> [u16; WP_STRING_LENGTH] {
+    let mut buffer = [0u16; WP_STRING_LENGTH];
+    for (slot, unit) in buffer[..WP_STRING_LENGTH - 1].iter_mut().zip(value.encode_utf16()) {
+        *slot = unit;
+    }
+    buffer
+}
+
+/// Lists the partitions like [get_partitions], converted to [WpPartition]
+fn query_partitions() -> Result<Vec<WpPartition>, i32> {
+    match catch_unwind(get_partitions) {
+        Ok(Ok(partitions)) => Ok(partitions.iter().map(WpPartition::from).collect()),
+        Ok(Err(err)) => Err(err.raw_os_error()),
+        Err(_) => Err(WP_ERROR_PANIC),
+    }
+}
+
+/// Writes the system partitions to the caller-provided `buffer` of `capacity` entries and their number to `count`.
+///
+/// If `buffer` is too small, nothing is written to it, the required number of entries is written to `count`
+/// and [WP_ERROR_BUFFER_TOO_SMALL] is returned. `buffer` may be null if `capacity` is 0.
+///
+/// # Safety
+///
+/// `count` must be a valid pointer. `buffer` must be null or valid for writing `capacity` entries
+#[no_mangle]
+pub unsafe extern "C" fn wp_get_partitions(buffer: *mut WpPartition, capacity: usize, count: *mut usize) -> i32 {
+    if count.is_null() || buffer.is_null() && capacity > 0 {
+        return WP_ERROR_NULL_POINTER;
+    }
+    let partitions = match query_partitions() {
+        Ok(partitions) => partitions,
+        Err(code) => return code,
+    };
+
+    *count = partitions.len();
+    if partitions.len() > capacity {
+        return WP_ERROR_BUFFER_TOO_SMALL;
+    }
+    if !partitions.is_empty() {
+        std::ptr::copy_nonoverlapping(partitions.as_ptr(), buffer, partitions.len());
+    }
+    WP_OK
+}
+
+/// Allocates an array of the system partitions, writing it to `partitions` and its length to `count`.
+/// The array must be released with [wp_free_partitions].
+///
+/// # Safety
+///
+/// `partitions` and `count` must be valid pointers
+#[no_mangle]
+pub unsafe extern "C" fn wp_get_partitions_alloc(partitions: *mut *mut WpPartition, count: *mut usize) -> i32 {
+    if partitions.is_null() || count.is_null() {
+        return WP_ERROR_NULL_POINTER;
+    }
+    *partitions = null_mut();
+    *count = 0;
+    let result = match query_partitions() {
+        Ok(result) => result,
+        Err(code) => return code,
+    };
+
+    *count = result.len();
+    *partitions = Box::into_raw(result.into_boxed_slice()) as *mut WpPartition;
+    WP_OK
+}
+
+/// Releases an array returned by [wp_get_partitions_alloc]. Does nothing if `partitions` is null.
+///
+/// # Safety
+///
+/// `partitions` and `count` must be the values written by [wp_get_partitions_alloc], and the array must not
+/// be released twice
+#[no_mangle]
+pub unsafe extern "C" fn wp_free_partitions(partitions: *mut WpPartition, count: usize) {
+    if !partitions.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(partitions, count)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::win_api::DriveType;
+
+    #[test]
+    fn from_partition_test() {
+        let partition = WindowsPartition {
+            letter: 'C',
+            ready: true,
+            name: "System".to_string(),
+            file_system_name: "NTFS".to_string(),
+            drive_type: DriveType::DriveFixed,
+            ..Default::default()
+        };
+        let converted = WpPartition::from(&partition);
+        assert_eq!(converted.letter, 'C' as u16);
+        assert_eq!(converted.drive_type, 3);
+        assert_eq!(&converted.name[..7], &[83, 121, 115, 116, 101, 109, 0]);
+
+        let long = to_wide_array(&"x".repeat(300));
+        assert_eq!(long[WP_STRING_LENGTH - 2], 'x' as u16);
+        assert_eq!(long[WP_STRING_LENGTH - 1], 0);
+    }
+
+    #[test]
+    fn null_pointer_test() {
+        unsafe {
+            assert_eq!(wp_get_partitions(null_mut(), 4, &mut 0), WP_ERROR_NULL_POINTER);
+            assert_eq!(wp_get_partitions_alloc(null_mut(), &mut 0), WP_ERROR_NULL_POINTER);
+        }
+    }
+}
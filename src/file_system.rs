@@ -0,0 +1,109 @@
+use std::fmt;
+
+const KB: u64 = 1024;
+const GB: u64 = 1024 * 1024 * KB;
+const PB: u64 = 1024 * 1024 * GB;
+
+/// File system of a volume, parsed from the name reported by
+/// [GetVolumeInformationW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumeinformationw)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileSystem {
+    /// NTFS
+    Ntfs,
+    /// FAT12 or FAT16, reported as `FAT`
+    Fat,
+    /// FAT32
+    Fat32,
+    /// exFAT
+    ExFat,
+    /// Resilient File System
+    ReFs,
+    /// ISO 9660 file system of CDs
+    Cdfs,
+    /// Universal Disk Format of DVDs and Blu-ray discs
+    Udf,
+    /// Cluster Shared Volume File System of failover clusters, layered over the NTFS or ReFS volume shared by
+    /// the nodes, see [cluster](crate::cluster)
+    Csvfs,
+    /// File system unknown to this crate, with its name as reported by Windows
+    Other(String),
+}
+
+impl FileSystem {
+    /// Name of the file system as reported by Windows, e.g. `NTFS`
+    pub fn name(&self) -> &str {
+        match self {
+            FileSystem::Ntfs => "NTFS",
+            FileSystem::Fat => "FAT",
+            FileSystem::Fat32 => "FAT32",
+            FileSystem::ExFat => "exFAT",
+            FileSystem::ReFs => "ReFS",
+            FileSystem::Cdfs => "CDFS",
+            FileSystem::Udf => "UDF",
+            FileSystem::Csvfs => "CSVFS",
+            FileSystem::Other(name) => name,
+        }
+    }
+
+    /// Largest file in bytes the file system can store with default formatting options, `None` if unknown
+    pub fn max_file_size(&self) -> Option<u64> {
+        match self {
+            // 2^32 - 1 clusters of the default 4 KB cluster size
+            FileSystem::Ntfs => Some((u32::MAX as u64) * 4 * KB),
+            FileSystem::Fat | FileSystem::Fat32 | FileSystem::Cdfs => Some(4 * GB - 1),
+            FileSystem::ExFat | FileSystem::Udf => Some(u64::MAX),
+            FileSystem::ReFs => Some(35 * PB),
+            FileSystem::Csvfs | FileSystem::Other(_) => None,
+        }
+    }
+
+    /// Whether the file system stores file permissions (ACLs)
+    pub fn supports_permissions(&self) -> bool {
+        matches!(self, FileSystem::Ntfs | FileSystem::ReFs | FileSystem::Csvfs)
+    }
+
+    /// Whether the file system is used on optical media
+    pub fn is_optical(&self) -> bool {
+        matches!(self, FileSystem::Cdfs | FileSystem::Udf)
+    }
+}
+
+/// Parses a file system name ignoring case. Unknown names become [FileSystem::Other]
+impl From<&str> for FileSystem {
+    fn from(name: &str) -> Self {
+        match name.to_ascii_uppercase().as_str() {
+            "NTFS" => FileSystem::Ntfs,
+            "FAT" | "FAT12" | "FAT16" => FileSystem::Fat,
+            "FAT32" => FileSystem::Fat32,
+            "EXFAT" => FileSystem::ExFat,
+            "REFS" => FileSystem::ReFs,
+            "CDFS" => FileSystem::Cdfs,
+            "UDF" => FileSystem::Udf,
+            "CSVFS" => FileSystem::Csvfs,
+            _ => FileSystem::Other(name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FileSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_test() {
+        assert_eq!(FileSystem::from("NTFS"), FileSystem::Ntfs);
+        assert_eq!(FileSystem::from("exfat"), FileSystem::ExFat);
+        assert_eq!(FileSystem::from("ReFS"), FileSystem::ReFs);
+        assert_eq!(FileSystem::from("CSVFS"), FileSystem::Csvfs);
+        assert_eq!(FileSystem::from("ext4"), FileSystem::Other("ext4".to_string()));
+        assert_eq!(FileSystem::from("ext4").to_string(), "ext4");
+        assert_eq!(FileSystem::Fat32.max_file_size(), Some(4_294_967_295));
+    }
+}
@@ -0,0 +1,247 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+use crate::bindings::{
+    Windows::Win32::Foundation::FARPROC,
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::System::LibraryLoader::FreeLibrary,
+    Windows::Win32::System::LibraryLoader::GetProcAddress,
+    Windows::Win32::System::LibraryLoader::LoadLibraryW,
+};
+use crate::error::WinPartitionError;
+use crate::file_system::FileSystem;
+
+/// Win32 error code for "Access is denied"
+const ERROR_ACCESS_DENIED: i32 = 5;
+/// Win32 error code for "The media is write protected"
+const ERROR_WRITE_PROTECT: i32 = 19;
+/// Win32 error code for "The device is not ready"
+const ERROR_NOT_READY: i32 = 21;
+/// Win32 error code for "A device attached to the system is not functioning"
+const ERROR_GEN_FAILURE: i32 = 31;
+/// Win32 error code for "The request is not supported"
+const ERROR_NOT_SUPPORTED: i32 = 50;
+/// Win32 error code for "The parameter is incorrect"
+const ERROR_INVALID_PARAMETER: i32 = 87;
+/// Win32 error code for "The disk is in use or locked by another process"
+const ERROR_DRIVE_LOCKED: i32 = 108;
+/// Win32 error code for "The volume label you entered exceeds the label character limit"
+const ERROR_LABEL_TOO_LONG: i32 = 154;
+
+/// `FMIFS_HARDDISK` media type of `FormatEx`, used for every non-floppy volume
+const FMIFS_HARDDISK: u32 = 0xC;
+
+// Callback commands of `FormatEx`, see `CALLBACKCOMMAND` in the Sysinternals `fmifs.h`
+const FMIFS_PROGRESS: u32 = 0x00;
+const FMIFS_INCOMPATIBLE_FILE_SYSTEM: u32 = 0x03;
+const FMIFS_ACCESS_DENIED: u32 = 0x06;
+const FMIFS_MEDIA_WRITE_PROTECTED: u32 = 0x07;
+const FMIFS_VOLUME_IN_USE: u32 = 0x08;
+const FMIFS_CANT_QUICK_FORMAT: u32 = 0x09;
+const FMIFS_DONE: u32 = 0x0B;
+const FMIFS_BAD_LABEL: u32 = 0x0C;
+const FMIFS_CLUSTER_SIZE_TOO_SMALL: u32 = 0x10;
+const FMIFS_CLUSTER_SIZE_TOO_BIG: u32 = 0x11;
+const FMIFS_VOLUME_TOO_SMALL: u32 = 0x12;
+const FMIFS_VOLUME_TOO_BIG: u32 = 0x13;
+const FMIFS_NO_MEDIA_IN_DRIVE: u32 = 0x14;
+
+/// Callback of `FormatEx`, returning `0` cancels the format
+type FmifsCallback = unsafe extern "system" fn(command: u32, sub_action: u32, action_info: *mut c_void) -> u8;
+/// `FormatEx` exported by `fmifs.dll`
+type FormatExFn = unsafe extern "system" fn(
+    drive_root: PWSTR,
+    media_type: u32,
+    file_system: PWSTR,
+    label: PWSTR,
+    quick_format: i32,
+    cluster_size: u32,
+    callback: FmifsCallback,
+);
+
+/// State of the format running on the current thread, `FormatEx` calls back on the calling thread
+struct FormatState {
+    /// Points to the `&mut dyn FnMut(u32)` progress callback passed to [format_volume]
+    progress: *mut c_void,
+    error: Option<i32>,
+    succeeded: bool,
+    /// Payload of a panic of the progress callback, caught so it doesn't unwind through `FormatEx`
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+thread_local! {
+    static FORMAT_STATE: RefCell<Option<FormatState>> = const { RefCell::new(None) };
+}
+
+/// Formats the volume at `lprootpathname`, a root path such as `E:\` or a volume GUID path, like `format.com`
+/// by calling `FormatEx` of `fmifs.dll`. `cluster_size` is the allocation unit size in bytes, `0` for the
+/// default of the file system, and `quick` skips scanning the volume for bad sectors like `format /Q`.
+///
+/// `progress` is called on the current thread with the percentage done while the volume is formatted. If it
+/// panics, the format is cancelled and the panic resumes once `FormatEx` returns.
+/// Everything on the volume is lost. Fails with `ERROR_DRIVE_LOCKED` if files on the volume are open, and
+/// with `ERROR_INVALID_PARAMETER` if the cluster size doesn't suit the volume. Requires administrator rights.
+///
+/// ```no_run
+/// use win_partitions::file_system::FileSystem;
+/// use win_partitions::format::format_volume;
+///
+/// format_volume("E:\\".to_string(), &FileSystem::Ntfs, "Data", 0, true, &mut |percent| {
+///     println!("{}%", percent);
+/// }).unwrap();
+/// ```
+///
+/// `FormatEx` isn't documented by Microsoft, it's the function behind `format.com` and Explorer's format dialog.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn format_volume(
+    lprootpathname: String,
+    file_system: &FileSystem,
+    label: &str,
+    cluster_size: u32,
+    quick: bool,
+    mut progress: &mut dyn FnMut(u32),
+) -> Result<(), WinPartitionError> {
+    let format_ex = load_format_ex()?;
+    let mut root: Vec<u16> = lprootpathname.encode_utf16().chain(Some(0)).collect();
+    let mut file_system_name: Vec<u16> = file_system.name().encode_utf16().chain(Some(0)).collect();
+    let mut label: Vec<u16> = label.encode_utf16().chain(Some(0)).collect();
+
+    FORMAT_STATE.with(|state| {
+        *state.borrow_mut() = Some(FormatState {
+            progress: &mut progress as *mut &mut dyn FnMut(u32) as *mut c_void,
+            error: None,
+            succeeded: false,
+            panic: None,
+        });
+    });
+    unsafe {
+        format_ex(
+            PWSTR(root.as_mut_ptr()),
+            FMIFS_HARDDISK,
+            PWSTR(file_system_name.as_mut_ptr()),
+            PWSTR(label.as_mut_ptr()),
+            quick as i32,
+            cluster_size,
+            format_callback);
+    }
+    let state = FORMAT_STATE.with(|state| state.borrow_mut().take());
+    if let Some(FormatState { panic: Some(payload), .. }) = state {
+        resume_unwind(payload);
+    }
+
+    match state {
+        Some(FormatState { succeeded: true, .. }) => Ok(()),
+        Some(FormatState { error: Some(code), .. }) => {
+            Err(WinPartitionError::from_code("FormatEx", Some(&lprootpathname), code))
+        }
+        _ => Err(WinPartitionError::from_code("FormatEx", Some(&lprootpathname), ERROR_GEN_FAILURE)),
+    }
+}
+
+/// Loads `FormatEx` from `fmifs.dll`. The library stays loaded for the rest of the process, like Explorer keeps it
+fn load_format_ex() -> Result<FormatExFn, WinPartitionError> {
+    let library = unsafe { LoadLibraryW("fmifs.dll") };
+    if library.0 == 0 {
+        return Err(WinPartitionError::last_os_error("LoadLibraryW", Some("fmifs.dll")));
+    }
+    match unsafe { GetProcAddress(library, "FormatEx") } {
+        Some(function) => Ok(unsafe { std::mem::transmute::<FARPROC, FormatExFn>(function) }),
+        None => {
+            let err = WinPartitionError::last_os_error("GetProcAddress", Some("FormatEx"));
+            unsafe { FreeLibrary(library) };
+            Err(err)
+        }
+    }
+}
+
+unsafe extern "system" fn format_callback(command: u32, _sub_action: u32, action_info: *mut c_void) -> u8 {
+    FORMAT_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = match state.as_mut() {
+            Some(state) => state,
+            None => return 1,
+        };
+        if state.panic.is_some() {
+            return 0;
+        }
+        match command {
+            FMIFS_PROGRESS => {
+                let progress = &mut *(state.progress as *mut &mut dyn FnMut(u32));
+                let percent = *(action_info as *const u32);
+                if let Err(payload) = catch_unwind(AssertUnwindSafe(|| progress(percent))) {
+                    state.panic = Some(payload);
+                    return 0;
+                }
+            }
+            FMIFS_DONE => state.succeeded = *(action_info as *const u8) != 0,
+            command => {
+                if state.error.is_none() {
+                    state.error = callback_error(command);
+                }
+            }
+        }
+        1
+    })
+}
+
+/// Win32 error code matching a failure reported to the `FormatEx` callback, `None` for informational commands
+fn callback_error(command: u32) -> Option<i32> {
+    match command {
+        FMIFS_ACCESS_DENIED => Some(ERROR_ACCESS_DENIED),
+        FMIFS_MEDIA_WRITE_PROTECTED => Some(ERROR_WRITE_PROTECT),
+        FMIFS_NO_MEDIA_IN_DRIVE => Some(ERROR_NOT_READY),
+        FMIFS_INCOMPATIBLE_FILE_SYSTEM | FMIFS_CANT_QUICK_FORMAT => Some(ERROR_NOT_SUPPORTED),
+        FMIFS_VOLUME_IN_USE => Some(ERROR_DRIVE_LOCKED),
+        FMIFS_BAD_LABEL => Some(ERROR_LABEL_TOO_LONG),
+        FMIFS_CLUSTER_SIZE_TOO_SMALL | FMIFS_CLUSTER_SIZE_TOO_BIG | FMIFS_VOLUME_TOO_SMALL | FMIFS_VOLUME_TOO_BIG => {
+            Some(ERROR_INVALID_PARAMETER)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn callback_error_test() {
+        assert_eq!(callback_error(FMIFS_VOLUME_IN_USE), Some(ERROR_DRIVE_LOCKED));
+        assert_eq!(callback_error(FMIFS_CLUSTER_SIZE_TOO_BIG), Some(ERROR_INVALID_PARAMETER));
+        // Structure progress and output messages aren't failures
+        assert_eq!(callback_error(0x0F), None);
+        assert_eq!(callback_error(0x0E), None);
+    }
+
+    #[test]
+    fn format_callback_panic_test() {
+        let mut progress = |percent: u32| {
+            if percent == 50 {
+                panic!("progress failed");
+            }
+        };
+        let mut progress: &mut dyn FnMut(u32) = &mut progress;
+        FORMAT_STATE.with(|state| {
+            *state.borrow_mut() = Some(FormatState {
+                progress: &mut progress as *mut &mut dyn FnMut(u32) as *mut c_void,
+                error: None,
+                succeeded: false,
+                panic: None,
+            });
+        });
+        let report = |mut percent: u32| unsafe {
+            format_callback(FMIFS_PROGRESS, 0, &mut percent as *mut u32 as *mut c_void)
+        };
+        assert_eq!(report(10), 1);
+        assert_eq!(report(50), 0);
+        // Calls after the panic keep cancelling the format
+        assert_eq!(report(60), 0);
+
+        let state = FORMAT_STATE.with(|state| state.borrow_mut().take()).unwrap();
+        let payload = state.panic.unwrap();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"progress failed"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::convert::{TryFrom, TryInto};
+
+use crate::error::WinPartitionError;
+use crate::guid::Guid;
+use crate::raw_device::RawDevice;
+
+/// Win32 error code for "The data is invalid"
+const ERROR_INVALID_DATA: i32 = 13;
+/// Win32 error code for "The request is not supported"
+const ERROR_NOT_SUPPORTED: i32 = 50;
+/// Signature at the start of a GPT header
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+/// Size of the header fields defined by the UEFI specification
+const MIN_HEADER_SIZE: usize = 92;
+/// Largest partition entry array read, the usual array is 128 entries of 128 bytes
+const MAX_ENTRY_ARRAY_SIZE: usize = 1024 * 1024;
+
+/// GPT header of a physical disk read from its sectors, as defined in chapter 5 of the
+/// [UEFI specification](https://uefi.org/specifications)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GptHeader {
+    /// Revision, `0x00010000` for version 1.0
+    pub revision: u32,
+    /// Size of the header in bytes
+    pub header_size: u32,
+    /// CRC32 of the header stored on disk
+    pub header_crc32: u32,
+    /// Whether `header_crc32` matches the header
+    pub header_crc32_valid: bool,
+    /// LBA of this header, 1 for the primary header
+    pub current_lba: u64,
+    /// LBA of the other header, the last sector of the disk for the primary header
+    pub backup_lba: u64,
+    /// First LBA partitions may use
+    pub first_usable_lba: u64,
+    /// Last LBA partitions may use
+    pub last_usable_lba: u64,
+    /// Disk GUID, as reported by [PartitionStyle::Gpt](crate::physical_disk::PartitionStyle::Gpt)
+    pub disk_guid: Guid,
+    /// First LBA of the partition entry array
+    pub partition_entry_lba: u64,
+    /// Number of entries in the partition entry array
+    pub partition_entry_count: u32,
+    /// Size of each partition entry in bytes
+    pub partition_entry_size: u32,
+    /// CRC32 of the partition entry array stored in the header
+    pub partition_entries_crc32: u32,
+    /// Whether `partition_entries_crc32` matches the partition entry array on disk,
+    /// `false` if [parse_gpt_header] wasn't given the array
+    pub partition_entries_crc32_valid: bool,
+}
+
+impl GptHeader {
+    /// Whether both checksums match, as firmware checks before using a header
+    pub fn is_valid(&self) -> bool {
+        self.header_crc32_valid && self.partition_entries_crc32_valid
+    }
+
+    /// Size of the partition entry array in bytes
+    pub fn partition_entry_array_size(&self) -> u64 {
+        self.partition_entry_count as u64 * self.partition_entry_size as u64
+    }
+}
+
+/// Reads the primary GPT header from LBA 1 of the physical disk `number`, as in `\\.\PhysicalDriveN`, and checks
+/// it and its partition entry array against their CRC32. The sectors are read directly, independent of the
+/// partition table Windows reports. Fails with `ERROR_INVALID_DATA` if the disk has no GPT header, and with
+/// `ERROR_NOT_SUPPORTED` if its partition entry array is larger than 1 MiB. Requires administrator rights.
+///
+/// Comparing the headers of a disk and its clone verifies the partition table was copied intact:
+///
+/// ```no_run
+/// use win_partitions::gpt::read_gpt_header;
+///
+/// let (source, clone) = (read_gpt_header(1).unwrap(), read_gpt_header(2).unwrap());
+/// assert!(clone.is_valid());
+/// assert_eq!(source.partition_entries_crc32, clone.partition_entries_crc32);
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn read_gpt_header(number: u32) -> Result<GptHeader, WinPartitionError> {
+    let device = RawDevice::open_physical_disk(number)?;
+    read_header_at(&device, 1)
+}
+
+/// Reads the backup GPT header from the last sector of the physical disk `number` like [read_gpt_header]
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn read_backup_gpt_header(number: u32) -> Result<GptHeader, WinPartitionError> {
+    let device = RawDevice::open_physical_disk(number)?;
+    read_header_at(&device, device.sector_count().saturating_sub(1))
+}
+
+fn read_header_at(device: &RawDevice, lba: u64) -> Result<GptHeader, WinPartitionError> {
+    let invalid = || WinPartitionError::from_code("ReadFile", Some(device.path()), ERROR_INVALID_DATA);
+    let sector = device.read_sectors(lba, 1)?;
+    let header = parse_gpt_header(&sector, None).ok_or_else(invalid)?;
+
+    let array_size = header.partition_entry_array_size();
+    if array_size == 0 {
+        return Ok(header);
+    }
+    // An array too large to read can't be checked, reporting the header as corrupt would be wrong
+    if array_size > MAX_ENTRY_ARRAY_SIZE as u64 {
+        return Err(WinPartitionError::from_code("ReadFile", Some(device.path()), ERROR_NOT_SUPPORTED));
+    }
+    let array_size = usize::try_from(array_size).map_err(|_| invalid())?;
+    let offset = header.partition_entry_lba.checked_mul(device.sector_size() as u64).ok_or_else(invalid)?;
+    let mut entries = vec![0; array_size];
+    device.read_at(offset, &mut entries)?;
+
+    parse_gpt_header(&sector, Some(&entries)).ok_or_else(invalid)
+}
+
+/// Decodes a GPT header from the sector holding it, `None` without the `EFI PART` signature. The partition
+/// entry array is checked against its CRC32 if `entries` is given
+pub fn parse_gpt_header(sector: &[u8], entries: Option<&[u8]>) -> Option<GptHeader> {
+    if sector.len() < MIN_HEADER_SIZE || &sector[0..8] != GPT_SIGNATURE {
+        return None;
+    }
+    let header_size = u32_at(sector, 12);
+    let header_crc32 = u32_at(sector, 16);
+    // The header CRC32 is computed with its own field zeroed
+    let header_crc32_valid = (MIN_HEADER_SIZE..=sector.len()).contains(&(header_size as usize)) && {
+        let mut header = sector[..header_size as usize].to_vec();
+        header[16..20].fill(0);
+        crc32(&header) == header_crc32
+    };
+
+    let partition_entry_count = u32_at(sector, 80);
+    let partition_entry_size = u32_at(sector, 84);
+    let partition_entries_crc32 = u32_at(sector, 88);
+    // The fields are untrusted and their product may not fit a 32-bit usize
+    let array_size = usize::try_from(partition_entry_count as u64 * partition_entry_size as u64).ok();
+    let partition_entries_crc32_valid = match (entries, array_size) {
+        (Some(entries), Some(array_size)) if entries.len() >= array_size => {
+            crc32(&entries[..array_size]) == partition_entries_crc32
+        }
+        _ => false,
+    };
+
+    Some(GptHeader {
+        revision: u32_at(sector, 8),
+        header_size,
+        header_crc32,
+        header_crc32_valid,
+        current_lba: u64_at(sector, 24),
+        backup_lba: u64_at(sector, 32),
+        first_usable_lba: u64_at(sector, 40),
+        last_usable_lba: u64_at(sector, 48),
+        disk_guid: Guid::from_le_bytes(sector[56..72].try_into().unwrap()),
+        partition_entry_lba: u64_at(sector, 72),
+        partition_entry_count,
+        partition_entry_size,
+        partition_entries_crc32,
+        partition_entries_crc32_valid,
+    })
+}
+
+/// CRC32 with the IEEE 802.3 polynomial, as used by GPT and zip
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn u32_at(sector: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(sector[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(sector: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(sector[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc32_test() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn parse_gpt_header_test() {
+        let entries = vec![0xAB; 128 * 128];
+        let mut sector = vec![0; 512];
+        sector[0..8].copy_from_slice(GPT_SIGNATURE);
+        sector[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        sector[12..16].copy_from_slice(&92u32.to_le_bytes());
+        sector[24..32].copy_from_slice(&1u64.to_le_bytes());
+        sector[32..40].copy_from_slice(&1_953_525_167u64.to_le_bytes());
+        sector[40..48].copy_from_slice(&34u64.to_le_bytes());
+        sector[48..56].copy_from_slice(&1_953_525_134u64.to_le_bytes());
+        sector[56..72].copy_from_slice(&[
+            0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B,
+        ]);
+        sector[72..80].copy_from_slice(&2u64.to_le_bytes());
+        sector[80..84].copy_from_slice(&128u32.to_le_bytes());
+        sector[84..88].copy_from_slice(&128u32.to_le_bytes());
+        sector[88..92].copy_from_slice(&crc32(&entries).to_le_bytes());
+        let header_crc32 = crc32(&sector[..92]);
+        sector[16..20].copy_from_slice(&header_crc32.to_le_bytes());
+
+        let header = parse_gpt_header(&sector, Some(&entries)).unwrap();
+        assert!(header.is_valid());
+        assert_eq!(header.disk_guid.to_string(), "C12A7328-F81F-11D2-BA4B-00A0C93EC93B");
+        assert_eq!(header.first_usable_lba, 34);
+        assert_eq!(header.backup_lba, 1_953_525_167);
+        assert_eq!(header.partition_entry_array_size(), 16384);
+
+        // Without the array, with a truncated or with a corrupted one only the header checksum holds
+        assert!(!parse_gpt_header(&sector, None).unwrap().is_valid());
+        assert!(!parse_gpt_header(&sector, Some(&entries[1..])).unwrap().partition_entries_crc32_valid);
+        let mut corrupted = entries.clone();
+        corrupted[0] ^= 0xFF;
+        assert!(!parse_gpt_header(&sector, Some(&corrupted)).unwrap().partition_entries_crc32_valid);
+        sector[40] = 35;
+        assert!(!parse_gpt_header(&sector, Some(&entries)).unwrap().header_crc32_valid);
+        // A bogus entry count and size don't overflow
+        sector[80..88].fill(0xFF);
+        assert!(!parse_gpt_header(&sector, Some(&entries)).unwrap().partition_entries_crc32_valid);
+
+        sector[0] = 0;
+        assert_eq!(parse_gpt_header(&sector, None), None);
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+/// A globally unique identifier, as used for GPT disks and partitions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Guid {
+    /// First 8 hexadecimal digits
+    pub data1: u32,
+    /// First group of 4 hexadecimal digits
+    pub data2: u16,
+    /// Second group of 4 hexadecimal digits
+    pub data3: u16,
+    /// 2 bytes of the 4th group followed by the 6 bytes of the last group
+    pub data4: [u8; 8],
+}
+
+impl Guid {
+    /// Creates a `Guid` with the given values
+    pub const fn from_values(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> Guid {
+        Guid {
+            data1,
+            data2,
+            data3,
+            data4,
+        }
+    }
+
+    /// Decodes the 16-byte on-disk form used by GPT, with the first three fields little-endian
+    pub(crate) fn from_le_bytes(bytes: [u8; 16]) -> Guid {
+        Guid {
+            data1: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            data2: u16::from_le_bytes([bytes[4], bytes[5]]),
+            data3: u16::from_le_bytes([bytes[6], bytes[7]]),
+            data4: [bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]],
+        }
+    }
+}
+
+impl From<windows::Guid> for Guid {
+    fn from(guid: windows::Guid) -> Self {
+        // Both are the `#[repr(C)]` layout of the Win32 GUID structure
+        unsafe { std::mem::transmute(guid) }
+    }
+}
+
+impl From<Guid> for windows::Guid {
+    fn from(guid: Guid) -> Self {
+        unsafe { std::mem::transmute(guid) }
+    }
+}
+
+/// Formats as `26A21BDA-A627-11D7-9931-806E6F6E6963`
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}-{:04X}-{:04X}-", self.data1, self.data2, self.data3)?;
+        for (index, byte) in self.data4.iter().enumerate() {
+            if index == 2 {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_test() {
+        let guid = Guid::from_values(0xc12a7328, 0xf81f, 0x11d2, [0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b]);
+        assert_eq!(guid.to_string(), "C12A7328-F81F-11D2-BA4B-00A0C93EC93B");
+    }
+}
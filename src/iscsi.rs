@@ -0,0 +1,129 @@
+use std::mem::size_of;
+
+use crate::bindings::{
+    Windows::Win32::Storage::IscsiDisc::GetDevicesForIScsiSessionW,
+    Windows::Win32::Storage::IscsiDisc::GetIScsiSessionListW,
+    Windows::Win32::Storage::IscsiDisc::ISCSI_DEVICE_ON_SESSIONW,
+    Windows::Win32::Storage::IscsiDisc::ISCSI_SESSION_INFOW,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::pwstr_to_string;
+use crate::wstr::vec_u16_to_string;
+
+/// Win32 error code returned when a buffer is too small for the result
+const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+/// `FILE_DEVICE_DISK` device type of a disk in a `STORAGE_DEVICE_NUMBER`
+const FILE_DEVICE_DISK: u32 = 7;
+
+/// Network address of an iSCSI target portal a session is connected to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IscsiPortal {
+    /// IP address or host name of the portal
+    pub address: String,
+    /// TCP port of the portal, usually 3260
+    pub port: u16,
+}
+
+/// iSCSI target a physical disk is a LUN of
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IscsiTarget {
+    /// iSCSI qualified name of the target, e.g. `iqn.1991-05.com.microsoft:san01-lun1-target`
+    pub target_name: String,
+    /// iSCSI qualified name of the initiator, i.e. this computer, e.g. `iqn.1991-05.com.microsoft:host01`
+    pub initiator_name: String,
+    /// Portals the session is connected to, several with multiple connections per session
+    pub portals: Vec<IscsiPortal>,
+    /// Logical unit number of the disk on the target
+    pub lun: u8,
+}
+
+/// Returns the iSCSI target of the physical disk `number`, as in `\\.\PhysicalDriveN`, or `None` for a disk
+/// which isn't an iSCSI LUN. Looks up the disk among the devices of the sessions of the Microsoft iSCSI
+/// Initiator with [GetIScsiSessionListW](https://docs.microsoft.com/en-us/windows/win32/api/iscsidsc/nf-iscsidsc-getiscsisessionlistw)
+/// and [GetDevicesForIScsiSessionW](https://docs.microsoft.com/en-us/windows/win32/api/iscsidsc/nf-iscsidsc-getdevicesforiscsisessionw)
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn get_iscsi_target(number: u32) -> Result<Option<IscsiTarget>, WinPartitionError> {
+    let mut buffer_size: u32 = 0;
+    let mut session_count: u32 = 0;
+    let mut buffer: Vec<u64> = vec![];
+    // The session list may grow between the calls
+    loop {
+        let result = unsafe {
+            GetIScsiSessionListW(
+                &mut buffer_size,
+                &mut session_count,
+                buffer.as_mut_ptr() as *mut ISCSI_SESSION_INFOW)
+        };
+        match result {
+            0 => break,
+            ERROR_INSUFFICIENT_BUFFER => {
+                buffer = vec![0; (buffer_size as usize).div_ceil(size_of::<u64>())];
+            }
+            code => return Err(WinPartitionError::from_code("GetIScsiSessionListW", None, code as i32)),
+        }
+    }
+    if session_count == 0 {
+        return Ok(None);
+    }
+
+    let sessions = unsafe {
+        std::slice::from_raw_parts(buffer.as_ptr() as *const ISCSI_SESSION_INFOW, session_count as usize)
+    };
+    for session in sessions {
+        let device = query_session_devices(session)?.into_iter().find(|device| {
+            device.StorageDeviceNumber.DeviceType == FILE_DEVICE_DISK
+                && device.StorageDeviceNumber.DeviceNumber == number
+        });
+        if let Some(device) = device {
+            return Ok(Some(target_of(session, &device)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Devices of a session, the disks of all its LUNs
+fn query_session_devices(session: &ISCSI_SESSION_INFOW) -> Result<Vec<ISCSI_DEVICE_ON_SESSIONW>, WinPartitionError> {
+    let mut session_id = session.SessionId;
+    let mut device_count: u32 = 0;
+    let mut devices: Vec<ISCSI_DEVICE_ON_SESSIONW> = vec![];
+    loop {
+        let result = unsafe { GetDevicesForIScsiSessionW(&mut session_id, &mut device_count, devices.as_mut_ptr()) };
+        match result {
+            0 => {
+                devices.truncate(device_count as usize);
+                return Ok(devices);
+            }
+            ERROR_INSUFFICIENT_BUFFER => {
+                devices = (0..device_count).map(|_| ISCSI_DEVICE_ON_SESSIONW::default()).collect();
+            }
+            code => return Err(WinPartitionError::from_code("GetDevicesForIScsiSessionW", None, code as i32)),
+        }
+    }
+}
+
+fn target_of(session: &ISCSI_SESSION_INFOW, device: &ISCSI_DEVICE_ON_SESSIONW) -> IscsiTarget {
+    let connections = if session.Connections.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(session.Connections, session.ConnectionCount as usize) }
+    };
+    let portals = connections
+        .iter()
+        .filter(|connection| !connection.TargetAddress.0.is_null())
+        .map(|connection| IscsiPortal {
+            address: unsafe { pwstr_to_string(connection.TargetAddress) },
+            port: connection.TargetSocket,
+        })
+        .collect();
+
+    IscsiTarget {
+        target_name: vec_u16_to_string(&device.TargetName),
+        initiator_name: vec_u16_to_string(&device.InitiatorName),
+        portals,
+        lun: device.ScsiAddress.Lun,
+    }
+}
@@ -1,5 +1,47 @@
+#[cfg(feature = "async")]
+pub mod async_api;
+pub mod backend;
+pub mod benchmark;
+pub mod boot_sector;
+pub mod byte_size;
+pub mod case_sensitivity;
+pub mod cluster;
+mod com;
+pub mod device_info;
+pub mod dfs;
+pub mod drive_events;
+pub mod dynamic_disk;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod file_system;
+pub mod format;
+pub mod gpt;
+pub mod guid;
+pub mod iscsi;
+pub mod monitor;
+pub mod ntfs;
+pub mod offline_files;
+pub mod partitioning;
+#[cfg(feature = "performance")]
+pub mod performance;
+pub mod physical_disk;
+mod privilege;
+pub mod quota;
+pub mod raw_device;
+pub mod shadow_copy;
+pub mod smart;
+pub mod storage;
+pub mod storage_spaces;
+pub mod system_volume;
+pub mod usn;
+pub mod virtual_disk;
+pub mod volume_bitmap;
+pub mod volume_lock;
 pub mod windows_partitions;
 pub mod win_api;
+mod wmi;
+pub mod wstr;
 
 mod bindings {
     windows::include_bindings!();
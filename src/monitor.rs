@@ -0,0 +1,171 @@
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::win_api::get_disk_free_space;
+
+/// Free space of a drive crossing a registered threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FreeSpaceEvent {
+    /// Free space dropped below the threshold, or was already below it when monitoring started
+    BelowThreshold {
+        /// Drive letter of the partition
+        letter: char,
+        /// Free space in bytes
+        free_space: u64,
+        /// Threshold in bytes
+        threshold: u64,
+    },
+    /// Free space rose back to or above the threshold
+    AboveThreshold {
+        /// Drive letter of the partition
+        letter: char,
+        /// Free space in bytes
+        free_space: u64,
+        /// Threshold in bytes
+        threshold: u64,
+    },
+}
+
+/// Free space threshold of a single drive
+struct Threshold {
+    letter: char,
+    threshold: u64,
+    /// Whether free space was below the threshold at the last check, `None` before the first successful check
+    below: Option<bool>,
+}
+
+impl Threshold {
+    /// Records the current free space and returns an event if the threshold was crossed
+    fn update(&mut self, free_space: u64) -> Option<FreeSpaceEvent> {
+        let below = free_space < self.threshold;
+        let crossed = match self.below {
+            Some(was_below) => was_below != below,
+            // Only report the initial state when it needs attention
+            None => below,
+        };
+        self.below = Some(below);
+        if !crossed {
+            return None;
+        }
+
+        let (letter, threshold) = (self.letter, self.threshold);
+        Some(if below {
+            FreeSpaceEvent::BelowThreshold { letter, free_space, threshold }
+        } else {
+            FreeSpaceEvent::AboveThreshold { letter, free_space, threshold }
+        })
+    }
+}
+
+/// Polls free space of partitions and reports threshold crossings over a `std::sync::mpsc` channel.
+///
+/// Drives that can't be queried, e.g. a removed USB stick, are skipped until they can be queried again
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use win_partitions::monitor::FreeSpaceMonitor;
+///
+/// let (_handle, events) = FreeSpaceMonitor::new(Duration::from_secs(60))
+///     .add_threshold('C', 10 * 1024 * 1024 * 1024)
+///     .start();
+/// for event in events {
+///     println!("{:?}", event);
+/// }
+/// ```
+pub struct FreeSpaceMonitor {
+    interval: Duration,
+    thresholds: Vec<Threshold>,
+}
+
+impl FreeSpaceMonitor {
+    /// Creates a monitor checking free space every `interval`
+    pub fn new(interval: Duration) -> FreeSpaceMonitor {
+        FreeSpaceMonitor { interval, thresholds: vec![] }
+    }
+
+    /// Registers a threshold in bytes for the drive `letter`. A drive can have several thresholds
+    pub fn add_threshold(mut self, letter: char, threshold: u64) -> FreeSpaceMonitor {
+        self.thresholds.push(Threshold { letter: letter.to_ascii_uppercase(), threshold, below: None });
+        self
+    }
+
+    /// Starts monitoring in a background thread. Monitoring stops when the returned handle is dropped.
+    /// A dropped receiver is only noticed when the next event is sent, so keep the handle to stop a monitor
+    /// whose thresholds may never be crossed
+    pub fn start(self) -> (MonitorHandle, Receiver<FreeSpaceEvent>) {
+        let (stop_sender, stop_receiver) = channel::<()>();
+        let (sender, receiver) = channel();
+        let thread = thread::spawn(move || self.run(&sender, &stop_receiver));
+
+        (MonitorHandle { stop: Some(stop_sender), thread: Some(thread) }, receiver)
+    }
+
+    fn run(mut self, sender: &Sender<FreeSpaceEvent>, stop: &Receiver<()>) {
+        loop {
+            for threshold in self.thresholds.iter_mut() {
+                let free_space = match get_disk_free_space(format!("{}:\\", threshold.letter)) {
+                    Ok(value) => value.2,
+                    Err(_) => continue,
+                };
+                if let Some(event) = threshold.update(free_space) {
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+            if let Err(RecvTimeoutError::Disconnected) = stop.recv_timeout(self.interval) {
+                return;
+            }
+        }
+    }
+}
+
+/// Running [FreeSpaceMonitor], stopped on drop
+pub struct MonitorHandle {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MonitorHandle {
+    /// Stops monitoring and waits for the background thread to finish
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Disconnecting the stop channel wakes the thread up
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MonitorHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn threshold_update_test() {
+        let mut threshold = Threshold { letter: 'C', threshold: 100, below: None };
+        assert_eq!(threshold.update(150), None);
+        assert_eq!(
+            threshold.update(50),
+            Some(FreeSpaceEvent::BelowThreshold { letter: 'C', free_space: 50, threshold: 100 }));
+        assert_eq!(threshold.update(40), None);
+        assert_eq!(
+            threshold.update(100),
+            Some(FreeSpaceEvent::AboveThreshold { letter: 'C', free_space: 100, threshold: 100 }));
+
+        let mut threshold = Threshold { letter: 'D', threshold: 100, below: None };
+        assert!(matches!(threshold.update(10), Some(FreeSpaceEvent::BelowThreshold { .. })));
+    }
+}
@@ -0,0 +1,177 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_READ_ATTRIBUTES,
+    Windows::Win32::Storage::FileSystem::NTFS_VOLUME_DATA_BUFFER,
+    Windows::Win32::Storage::FileSystem::STARTING_VCN_INPUT_BUFFER,
+    Windows::Win32::System::SystemServices::FSCTL_GET_NTFS_VOLUME_DATA,
+    Windows::Win32::System::SystemServices::FSCTL_GET_RETRIEVAL_POINTERS,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::{open_device, open_file, volume_device_path};
+
+/// Low-level layout of an NTFS volume, see [NTFS_VOLUME_DATA_BUFFER](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-ntfs_volume_data_buffer).
+/// Cluster numbers (LCN) are counted from the start of the volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NtfsVolumeData {
+    /// Serial number of the volume
+    pub volume_serial_number: u64,
+    /// Number of sectors in the volume
+    pub number_sectors: u64,
+    /// Number of clusters in the volume
+    pub total_clusters: u64,
+    /// Number of free clusters in the volume
+    pub free_clusters: u64,
+    /// Number of reserved clusters in the volume
+    pub total_reserved: u64,
+    /// Size of a sector in bytes
+    pub bytes_per_sector: u32,
+    /// Size of a cluster in bytes
+    pub bytes_per_cluster: u32,
+    /// Size of a file record in the master file table (MFT) in bytes
+    pub bytes_per_file_record_segment: u32,
+    /// Number of clusters of a file record, 0 if a file record is smaller than a cluster
+    pub clusters_per_file_record_segment: u32,
+    /// Length of the MFT in bytes
+    pub mft_valid_data_length: u64,
+    /// First cluster of the MFT
+    pub mft_start_lcn: u64,
+    /// First cluster of the MFT mirror
+    pub mft2_start_lcn: u64,
+    /// First cluster of the MFT zone, the area reserved for the MFT to grow into
+    pub mft_zone_start: u64,
+    /// Cluster after the last cluster of the MFT zone
+    pub mft_zone_end: u64,
+}
+
+impl NtfsVolumeData {
+    /// Number of file records the MFT currently has room for
+    pub fn mft_record_count(&self) -> u64 {
+        match self.bytes_per_file_record_segment {
+            0 => 0,
+            size => self.mft_valid_data_length / size as u64,
+        }
+    }
+
+    /// Size of the MFT zone in clusters
+    pub fn mft_zone_clusters(&self) -> u64 {
+        self.mft_zone_end.saturating_sub(self.mft_zone_start)
+    }
+}
+
+/// Calls [FSCTL_GET_NTFS_VOLUME_DATA](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_get_ntfs_volume_data)
+/// and returns MFT and cluster information of an NTFS volume.
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_ntfs_volume_data(lprootpathname: String) -> Result<NtfsVolumeData, WinPartitionError> {
+    let device = open_device(&volume_device_path(&lprootpathname), FILE_GENERIC_READ)?;
+    let data: NTFS_VOLUME_DATA_BUFFER = device.query(
+        "FSCTL_GET_NTFS_VOLUME_DATA",
+        FSCTL_GET_NTFS_VOLUME_DATA)?;
+
+    Ok(NtfsVolumeData {
+        volume_serial_number: data.VolumeSerialNumber as u64,
+        number_sectors: data.NumberSectors as u64,
+        total_clusters: data.TotalClusters as u64,
+        free_clusters: data.FreeClusters as u64,
+        total_reserved: data.TotalReserved as u64,
+        bytes_per_sector: data.BytesPerSector,
+        bytes_per_cluster: data.BytesPerCluster,
+        bytes_per_file_record_segment: data.BytesPerFileRecordSegment,
+        clusters_per_file_record_segment: data.ClustersPerFileRecordSegment,
+        mft_valid_data_length: data.MftValidDataLength as u64,
+        mft_start_lcn: data.MftStartLcn as u64,
+        mft2_start_lcn: data.Mft2StartLcn as u64,
+        mft_zone_start: data.MftZoneStart as u64,
+        mft_zone_end: data.MftZoneEnd as u64,
+    })
+}
+
+/// Size, zone reservation and fragmentation of the master file table of an NTFS volume, see [get_mft_statistics]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MftStatistics {
+    /// Length of the MFT in bytes
+    pub mft_size: u64,
+    /// Number of file records the MFT currently has room for
+    pub mft_record_count: u64,
+    /// Size of the MFT zone, the area reserved for the MFT to grow into, in bytes
+    pub mft_zone_size: u64,
+    /// Number of fragments the MFT is stored in, 1 if it's contiguous
+    pub fragment_count: u32,
+}
+
+/// Returns MFT size and zone reservation from [get_ntfs_volume_data], and counts the fragments of `$MFT`
+/// with [FSCTL_GET_RETRIEVAL_POINTERS](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_get_retrieval_pointers).
+/// A heavily fragmented MFT slows down file lookups and can only be defragmented offline.
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_mft_statistics(lprootpathname: String) -> Result<MftStatistics, WinPartitionError> {
+    let data = get_ntfs_volume_data(lprootpathname.clone())?;
+    let mft_path = format!("{}\\$MFT", lprootpathname.trim_end_matches('\\'));
+    let mft = open_file(&mft_path, FILE_READ_ATTRIBUTES)?;
+    let mut input = STARTING_VCN_INPUT_BUFFER { StartingVcn: 0 };
+    let buffer = unsafe {
+        mft.query_variable(
+            "FSCTL_GET_RETRIEVAL_POINTERS",
+            FSCTL_GET_RETRIEVAL_POINTERS,
+            &mut input as *mut STARTING_VCN_INPUT_BUFFER as *mut c_void,
+            size_of::<STARTING_VCN_INPUT_BUFFER>() as u32)?
+    };
+
+    Ok(MftStatistics {
+        mft_size: data.mft_valid_data_length,
+        mft_record_count: data.mft_record_count(),
+        mft_zone_size: data.mft_zone_clusters() * data.bytes_per_cluster as u64,
+        fragment_count: count_fragments(&buffer),
+    })
+}
+
+/// Counts the allocated extents of a `RETRIEVAL_POINTERS_BUFFER`: a 32-bit extent count, the starting VCN,
+/// then a `NextVcn` and `Lcn` pair per extent. Extents with an LCN of -1 aren't stored on disk
+fn count_fragments(buffer: &[u64]) -> u32 {
+    let extent_count = buffer[0] as u32 as usize;
+    buffer[2..]
+        .chunks_exact(2)
+        .take(extent_count)
+        .filter(|extent| extent[1] as i64 != -1)
+        .count() as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mft_test() {
+        let data = NtfsVolumeData {
+            volume_serial_number: 0,
+            number_sectors: 0,
+            total_clusters: 0,
+            free_clusters: 0,
+            total_reserved: 0,
+            bytes_per_sector: 512,
+            bytes_per_cluster: 4096,
+            bytes_per_file_record_segment: 1024,
+            clusters_per_file_record_segment: 0,
+            mft_valid_data_length: 1024 * 300,
+            mft_start_lcn: 786432,
+            mft2_start_lcn: 2,
+            mft_zone_start: 797000,
+            mft_zone_end: 800000,
+        };
+        assert_eq!(data.mft_record_count(), 300);
+        assert_eq!(data.mft_zone_clusters(), 3000);
+
+        // 3 extents starting at VCN 0, the second one sparse
+        let buffer = [3, 0, 64, 786432, 80, u64::MAX, 128, 1000];
+        assert_eq!(count_fragments(&buffer), 2);
+    }
+}
@@ -0,0 +1,166 @@
+use windows::Interface;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::BOOL,
+    Windows::Win32::Storage::OfflineFiles::IOfflineFilesCache,
+    Windows::Win32::Storage::OfflineFiles::IOfflineFilesConnectionInfo,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_CONNECT_STATE,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_CONNECT_STATE_OFFLINE,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_CONNECT_STATE_ONLINE,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_CONNECT_STATE_PARTLY_TRANSPARENTLY_CACHED,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_CONNECT_STATE_TRANSPARENTLY_CACHED,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_OFFLINE_REASON,
+    Windows::Win32::Storage::OfflineFiles::OFFLINEFILES_OFFLINE_REASON_CONNECTION_SLOW,
+    Windows::Win32::Storage::OfflineFiles::OfflineFilesQueryStatus,
+    Windows::Win32::System::Com::CoCreateInstance,
+    Windows::Win32::System::Com::CLSCTX_INPROC_SERVER,
+};
+use crate::com::{com_error, ComGuard};
+use crate::error::WinPartitionError;
+use crate::win_api::unc_share_root;
+
+/// HRESULT of `ERROR_FILE_NOT_FOUND`, returned by `IOfflineFilesCache::FindItem` for paths not in the cache
+const HRESULT_FILE_NOT_FOUND: u32 = 0x80070002;
+/// `OFFLINEFILES_ITEM_QUERY_CONNECTIONSTATE` flag of `IOfflineFilesCache::FindItem`
+const OFFLINEFILES_ITEM_QUERY_CONNECTIONSTATE: u32 = 0x2;
+/// `CLSID_OfflineFilesCache` of `cscobj.h`
+const CLSID_OFFLINE_FILES_CACHE: windows::Guid =
+    windows::Guid::from_values(0x48c6be7c, 0x3871, 0x43cc, [0xb4, 0x6f, 0x14, 0x49, 0xa1, 0xbb, 0x2f, 0xf3]);
+
+/// How the Offline Files (Client Side Caching) service currently serves a network share
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OfflineState {
+    /// Requests go to the server, possibly served from the cache transparently
+    Online,
+    /// The connection is slower than the slow-link threshold, requests are served from the cache
+    SlowLink,
+    /// The server is unreachable or the user chose "Work offline", requests are served from the cache
+    Offline,
+    /// The service couldn't determine the state
+    Unknown,
+}
+
+impl OfflineState {
+    fn from(state: OFFLINEFILES_CONNECT_STATE, reason: OFFLINEFILES_OFFLINE_REASON) -> OfflineState {
+        match state {
+            OFFLINEFILES_CONNECT_STATE_OFFLINE if reason == OFFLINEFILES_OFFLINE_REASON_CONNECTION_SLOW => {
+                OfflineState::SlowLink
+            }
+            OFFLINEFILES_CONNECT_STATE_OFFLINE => OfflineState::Offline,
+            OFFLINEFILES_CONNECT_STATE_ONLINE
+            | OFFLINEFILES_CONNECT_STATE_TRANSPARENTLY_CACHED
+            | OFFLINEFILES_CONNECT_STATE_PARTLY_TRANSPARENTLY_CACHED => OfflineState::Online,
+            _ => OfflineState::Unknown,
+        }
+    }
+
+    /// Whether requests are served from the local cache instead of the server
+    pub fn is_offline(&self) -> bool {
+        matches!(self, OfflineState::SlowLink | OfflineState::Offline)
+    }
+}
+
+/// Disk space of the Offline Files cache in bytes, as shown in the "Disk Usage" tab of the Offline Files settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OfflineCacheUsage {
+    /// Size of the volume holding the cache
+    pub volume_total: u64,
+    /// Largest size the cache may grow to
+    pub limit: u64,
+    /// Space used by all cached files
+    pub used: u64,
+    /// Largest size of the files cached automatically, which aren't pinned to be always available offline
+    pub unpinned_limit: u64,
+    /// Space used by files cached automatically
+    pub unpinned_used: u64,
+}
+
+/// Checks whether Offline Files is enabled and its service running with
+/// [OfflineFilesQueryStatus](https://docs.microsoft.com/en-us/windows/win32/api/cscapi/nf-cscapi-offlinefilesquerystatus)
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn is_offline_files_active() -> Result<bool, WinPartitionError> {
+    let mut active = BOOL(0);
+    let mut enabled = BOOL(0);
+    match unsafe { OfflineFilesQueryStatus(&mut active, &mut enabled) } {
+        0 => Ok(active.as_bool() && enabled.as_bool()),
+        code => Err(WinPartitionError::from_code("OfflineFilesQueryStatus", None, code as i32)),
+    }
+}
+
+/// Returns the Offline Files state of the share holding the UNC path `path`, such as the
+/// [remote_path](crate::windows_partitions::WindowsPartition::remote_path) of a network drive, or `None` if
+/// Offline Files isn't active or nothing of the share is cached. Queried from
+/// [IOfflineFilesConnectionInfo](https://docs.microsoft.com/en-us/windows/win32/api/cscobj/nn-cscobj-iofflinefilesconnectioninfo)
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn get_offline_state(path: String) -> Result<Option<OfflineState>, WinPartitionError> {
+    // Offline Files tracks the connection state per share
+    let share = match unc_share_root(&path) {
+        Some(share) => share,
+        None => return Ok(None),
+    };
+    if !is_offline_files_active()? {
+        return Ok(None);
+    }
+
+    let _com = ComGuard::new()?;
+    let cache = create_cache()?;
+    let item = match unsafe { cache.FindItem(share.as_str(), OFFLINEFILES_ITEM_QUERY_CONNECTIONSTATE) } {
+        Ok(item) => item,
+        Err(err) if err.code().0 == HRESULT_FILE_NOT_FOUND => return Ok(None),
+        Err(err) => return Err(com_error("IOfflineFilesCache::FindItem", Some(&path))(err)),
+    };
+    let connection: IOfflineFilesConnectionInfo = item
+        .cast()
+        .map_err(com_error("IOfflineFilesItem::QueryInterface", Some(&path)))?;
+
+    let mut state = OFFLINEFILES_CONNECT_STATE::default();
+    let mut reason = OFFLINEFILES_OFFLINE_REASON::default();
+    unsafe { connection.GetConnectState(&mut state, &mut reason) }
+        .map_err(com_error("IOfflineFilesConnectionInfo::GetConnectState", Some(&path)))?;
+
+    Ok(Some(OfflineState::from(state, reason)))
+}
+
+/// Returns the disk space used by the Offline Files cache with
+/// [IOfflineFilesCache::GetDiskSpaceInformation](https://docs.microsoft.com/en-us/windows/win32/api/cscobj/nf-cscobj-iofflinefilescache-getdiskspaceinformation)
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn get_offline_cache_usage() -> Result<OfflineCacheUsage, WinPartitionError> {
+    let _com = ComGuard::new()?;
+    let cache = create_cache()?;
+    let mut usage = OfflineCacheUsage { volume_total: 0, limit: 0, used: 0, unpinned_limit: 0, unpinned_used: 0 };
+    unsafe {
+        cache.GetDiskSpaceInformation(
+            &mut usage.volume_total,
+            &mut usage.limit,
+            &mut usage.used,
+            &mut usage.unpinned_limit,
+            &mut usage.unpinned_used)
+    }
+        .map_err(com_error("IOfflineFilesCache::GetDiskSpaceInformation", None))?;
+
+    Ok(usage)
+}
+
+fn create_cache() -> Result<IOfflineFilesCache, WinPartitionError> {
+    unsafe { CoCreateInstance(&CLSID_OFFLINE_FILES_CACHE, None, CLSCTX_INPROC_SERVER) }
+        .map_err(com_error("CoCreateInstance", None))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offline_state_test() {
+        let reason = OFFLINEFILES_OFFLINE_REASON::default();
+        assert_eq!(OfflineState::from(OFFLINEFILES_CONNECT_STATE_ONLINE, reason), OfflineState::Online);
+        assert_eq!(OfflineState::from(OFFLINEFILES_CONNECT_STATE_OFFLINE, reason), OfflineState::Offline);
+        let slow = OfflineState::from(OFFLINEFILES_CONNECT_STATE_OFFLINE, OFFLINEFILES_OFFLINE_REASON_CONNECTION_SLOW);
+        assert!(slow == OfflineState::SlowLink && slow.is_offline());
+    }
+}
@@ -0,0 +1,759 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::DRIVE_LAYOUT_INFORMATION_EX,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_WRITE,
+    Windows::Win32::Storage::FileSystem::GPT_ATTRIBUTES,
+    Windows::Win32::Storage::FileSystem::PARTITION_INFORMATION_EX,
+    Windows::Win32::Storage::FileSystem::PARTITION_INFORMATION_EX_0,
+    Windows::Win32::Storage::FileSystem::PARTITION_INFORMATION_GPT,
+    Windows::Win32::Storage::FileSystem::PARTITION_INFORMATION_MBR,
+    Windows::Win32::Storage::FileSystem::PARTITION_STYLE_GPT,
+    Windows::Win32::Storage::FileSystem::PARTITION_STYLE_MBR,
+    Windows::Win32::Storage::FileSystem::SHRINK_VOLUME_INFORMATION,
+    Windows::Win32::System::SystemServices::FSCTL_EXTEND_VOLUME,
+    Windows::Win32::System::SystemServices::FSCTL_SHRINK_VOLUME,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GROW_PARTITION,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_SET_DRIVE_LAYOUT_EX,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_UPDATE_PROPERTIES,
+    Windows::Win32::System::SystemServices::SHRINK_VOLUME_REQUEST_TYPES,
+    Windows::Win32::System::SystemServices::ShrinkAbort,
+    Windows::Win32::System::SystemServices::ShrinkCommit,
+    Windows::Win32::System::SystemServices::ShrinkPrepare,
+};
+use crate::com::com_error;
+use crate::error::WinPartitionError;
+use crate::guid::Guid;
+use crate::physical_disk::{
+    is_extended_partition_type, is_used_entry, open_physical_disk, physical_disk_path, query_disk_geometry,
+    query_drive_layout, DiskGeometry, DriveLayout, GptPartitionType, MbrEntryKind, PartitionDetails, PartitionInfo,
+    PartitionStyle,
+};
+use crate::privilege::{enable_privilege, SE_MANAGE_VOLUME_NAME};
+use crate::volume_bitmap::VolumeBitmap;
+use crate::win_api::{
+    get_disk_free_space_basic, get_volume_disk_extents, open_device, volume_device_path, DeviceHandle, DiskExtent,
+};
+
+/// Win32 error code for "The request is not supported", returned for disks without a partition table
+const ERROR_NOT_SUPPORTED: i32 = 50;
+/// Win32 error code for "The parameter is incorrect"
+const ERROR_INVALID_PARAMETER: i32 = 87;
+/// Win32 error code for "There is not enough space on the disk"
+const ERROR_DISK_FULL: i32 = 112;
+/// Win32 error code for "The specified offset and length aren't aligned"
+const ERROR_OFFSET_ALIGNMENT_VIOLATION: i32 = 327;
+/// Win32 error code for "Element not found"
+const ERROR_NOT_FOUND: i32 = 1168;
+
+/// Default alignment of the starting offset of new partitions, 1 MiB like Disk Management and diskpart
+pub const DEFAULT_ALIGNMENT: u64 = 1024 * 1024;
+/// GPT attribute bits 3 to 47, reserved by the UEFI specification
+const GPT_RESERVED_ATTRIBUTES: u64 = 0x0000_FFFF_FFFF_FFF8;
+/// Maximum length of a GPT partition name in UTF-16 code units
+const GPT_NAME_LENGTH: usize = 36;
+/// Number of primary partition entries in a master boot record
+const MBR_PRIMARY_ENTRIES: usize = 4;
+
+/// Input of `IOCTL_DISK_GROW_PARTITION`, missing from the Windows metadata
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct DiskGrowPartition {
+    partition_number: u32,
+    bytes_to_grow: i64,
+}
+
+/// Partitioning scheme specific settings of a new partition
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum NewPartitionKind {
+    Gpt {
+        partition_type: GptPartitionType,
+        attributes: u64,
+        name: String,
+    },
+    Mbr {
+        partition_type: u8,
+        active: bool,
+    },
+}
+
+/// Builder for a new partition, checked against the partition table of the disk before it's written.
+///
+/// The partition must start at a multiple of the alignment, fit in the usable area of the disk without
+/// overlapping another partition, and match the partitioning scheme of the disk. GPT attributes must leave
+/// the bits reserved by the UEFI specification clear. On MBR disks only primary partitions can be created.
+///
+/// ```no_run
+/// use win_partitions::partitioning::PartitionBuilder;
+/// use win_partitions::physical_disk::GptPartitionType;
+///
+/// let gib: u64 = 1024 * 1024 * 1024;
+/// let partition = PartitionBuilder::gpt(GptPartitionType::BasicData, gib, 16 * gib)
+///     .name("Data")
+///     .create(1)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PartitionBuilder {
+    starting_offset: u64,
+    length: u64,
+    alignment: u64,
+    kind: NewPartitionKind,
+}
+
+impl PartitionBuilder {
+    /// Creates a GPT partition of type `partition_type`, starting `starting_offset` bytes into the disk
+    /// and `length` bytes long
+    pub fn gpt(partition_type: GptPartitionType, starting_offset: u64, length: u64) -> PartitionBuilder {
+        PartitionBuilder {
+            starting_offset,
+            length,
+            alignment: DEFAULT_ALIGNMENT,
+            kind: NewPartitionKind::Gpt { partition_type, attributes: 0, name: String::new() },
+        }
+    }
+
+    /// Creates a primary MBR partition with partition type byte `partition_type`, e.g. `0x07` for NTFS,
+    /// starting `starting_offset` bytes into the disk and `length` bytes long
+    pub fn mbr(partition_type: u8, starting_offset: u64, length: u64) -> PartitionBuilder {
+        PartitionBuilder {
+            starting_offset,
+            length,
+            alignment: DEFAULT_ALIGNMENT,
+            kind: NewPartitionKind::Mbr { partition_type, active: false },
+        }
+    }
+
+    /// Sets the name of a GPT partition, at most 36 UTF-16 code units. Ignored for MBR partitions
+    pub fn name(mut self, name: &str) -> PartitionBuilder {
+        if let NewPartitionKind::Gpt { name: current, .. } = &mut self.kind {
+            *current = name.to_string();
+        }
+        self
+    }
+
+    /// Sets the GPT attribute bits, e.g. `0x8000_0000_0000_0000` so Windows doesn't assign a drive letter
+    /// to a basic data partition. Ignored for MBR partitions
+    pub fn attributes(mut self, attributes: u64) -> PartitionBuilder {
+        if let NewPartitionKind::Gpt { attributes: current, .. } = &mut self.kind {
+            *current = attributes;
+        }
+        self
+    }
+
+    /// Marks an MBR partition active, so the BIOS boots from it. Ignored for GPT partitions
+    pub fn active(mut self, active: bool) -> PartitionBuilder {
+        if let NewPartitionKind::Mbr { active: current, .. } = &mut self.kind {
+            *current = active;
+        }
+        self
+    }
+
+    /// Multiple of the disk's sector size the starting offset must be aligned to, defaults to [DEFAULT_ALIGNMENT]
+    pub fn alignment(mut self, alignment: u64) -> PartitionBuilder {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Adds the partition to the partition table of the physical disk `number`, as in `\\.\PhysicalDriveN`,
+    /// with [IOCTL_DISK_SET_DRIVE_LAYOUT_EX](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_set_drive_layout_ex)
+    /// and returns it as read back from the disk. The partition isn't formatted.
+    ///
+    /// Fails with `ERROR_INVALID_PARAMETER` or `ERROR_OFFSET_ALIGNMENT_VIOLATION` if the partition doesn't
+    /// fit the partition table, with `ERROR_DISK_FULL` if the last free MBR primary entry was taken while
+    /// creating, and with `ERROR_NOT_SUPPORTED` for disks without one. Requires administrator rights.
+    ///
+    /// Minimum OS: Windows XP/Windows Server 2003
+    pub fn create(&self, number: u32) -> Result<PartitionInfo, WinPartitionError> {
+        let path = physical_disk_path(number);
+        let device = open_device(&path, FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+        let geometry = query_disk_geometry(&device)?;
+        self.check(&query_drive_layout(&device)?, &geometry)
+            .map_err(|code| WinPartitionError::from_code("IOCTL_DISK_SET_DRIVE_LAYOUT_EX", Some(&path), code))?;
+
+        let (header, mut entries) = read_layout(&device)?;
+        let entry = self.entry(&geometry)?;
+        match self.kind {
+            NewPartitionKind::Gpt { .. } => entries.push(entry),
+            NewPartitionKind::Mbr { .. } => {
+                if entries.len() < MBR_PRIMARY_ENTRIES {
+                    entries.resize(MBR_PRIMARY_ENTRIES, unused_mbr_entry());
+                }
+                // The layout may have changed since it was checked
+                let slot = entries[..MBR_PRIMARY_ENTRIES]
+                    .iter()
+                    .position(|entry| !is_used_entry(entry))
+                    .ok_or_else(|| {
+                        WinPartitionError::from_code("IOCTL_DISK_SET_DRIVE_LAYOUT_EX", Some(&path), ERROR_DISK_FULL)
+                    })?;
+                entries[slot] = entry;
+            }
+        }
+        write_layout(&device, header, &entries)?;
+
+        query_drive_layout(&device)?
+            .partitions
+            .into_iter()
+            .find(|partition| partition.starting_offset == self.starting_offset && partition.number != 0)
+            .ok_or_else(|| WinPartitionError::from_code("IOCTL_DISK_GET_DRIVE_LAYOUT_EX", Some(&path), ERROR_NOT_FOUND))
+    }
+
+    /// Checks the partition against the layout and geometry of the disk, returning the Win32 error code to fail with
+    fn check(&self, layout: &DriveLayout, geometry: &DiskGeometry) -> Result<(), i32> {
+        let sector_size = geometry.bytes_per_sector.max(1) as u64;
+        if self.length == 0 || self.alignment == 0 || !self.alignment.is_multiple_of(sector_size) {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        if !self.starting_offset.is_multiple_of(self.alignment) || !self.length.is_multiple_of(sector_size) {
+            return Err(ERROR_OFFSET_ALIGNMENT_VIOLATION);
+        }
+        let end = self.starting_offset.checked_add(self.length).ok_or(ERROR_INVALID_PARAMETER)?;
+
+        let (usable_start, usable_end) = match (&layout.style, &self.kind) {
+            (
+                PartitionStyle::Gpt { starting_usable_offset, usable_length, max_partition_count, .. },
+                NewPartitionKind::Gpt { partition_type, attributes, name },
+            ) => {
+                if layout.partitions.len() >= *max_partition_count as usize
+                    || partition_type.guid() == Guid::default()
+                    || attributes & GPT_RESERVED_ATTRIBUTES != 0
+                    || name.encode_utf16().count() > GPT_NAME_LENGTH
+                {
+                    return Err(ERROR_INVALID_PARAMETER);
+                }
+                (*starting_usable_offset, starting_usable_offset + usable_length)
+            }
+            (PartitionStyle::Mbr { .. }, NewPartitionKind::Mbr { partition_type, active }) => {
+                let primary_count = layout.partitions
+                    .iter()
+                    .filter(|partition| is_master_boot_record_entry(layout, partition))
+                    .count();
+                // MBR entries store the starting sector and the number of sectors as 32-bit values
+                if primary_count >= MBR_PRIMARY_ENTRIES
+                    || *partition_type == 0
+                    || is_extended_partition_type(*partition_type)
+                    || (*active && layout.partitions.iter().any(is_active))
+                    || end / sector_size > u32::MAX as u64
+                {
+                    return Err(ERROR_INVALID_PARAMETER);
+                }
+                // The first sector holds the master boot record
+                (sector_size, geometry.disk_size)
+            }
+            (PartitionStyle::Raw, _) => return Err(ERROR_NOT_SUPPORTED),
+            _ => return Err(ERROR_INVALID_PARAMETER),
+        };
+        if self.starting_offset < usable_start || end > usable_end {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        let overlaps = layout.partitions.iter().any(|partition| {
+            self.starting_offset < partition.starting_offset + partition.length && partition.starting_offset < end
+        });
+        if overlaps {
+            return Err(ERROR_INVALID_PARAMETER);
+        }
+        Ok(())
+    }
+
+    /// Builds the partition table entry written for the partition
+    fn entry(&self, geometry: &DiskGeometry) -> Result<PARTITION_INFORMATION_EX, WinPartitionError> {
+        let (style, anonymous) = match &self.kind {
+            NewPartitionKind::Gpt { partition_type, attributes, name } => {
+                let mut gpt = PARTITION_INFORMATION_GPT {
+                    PartitionType: partition_type.guid().into(),
+                    PartitionId: windows::Guid::new().map_err(com_error("CoCreateGuid", None))?,
+                    Attributes: GPT_ATTRIBUTES(*attributes),
+                    ..Default::default()
+                };
+                for (target, unit) in gpt.Name.iter_mut().zip(name.encode_utf16()) {
+                    *target = unit;
+                }
+                (PARTITION_STYLE_GPT, PARTITION_INFORMATION_EX_0 { Gpt: gpt })
+            }
+            NewPartitionKind::Mbr { partition_type, active } => {
+                let mbr = PARTITION_INFORMATION_MBR {
+                    PartitionType: *partition_type,
+                    BootIndicator: *active as u8,
+                    RecognizedPartition: 1,
+                    HiddenSectors: (self.starting_offset / geometry.bytes_per_sector.max(1) as u64) as u32,
+                    ..Default::default()
+                };
+                (PARTITION_STYLE_MBR, PARTITION_INFORMATION_EX_0 { Mbr: mbr })
+            }
+        };
+
+        Ok(PARTITION_INFORMATION_EX {
+            PartitionStyle: style,
+            StartingOffset: self.starting_offset as i64,
+            PartitionLength: self.length as i64,
+            PartitionNumber: 0,
+            RewritePartition: 1,
+            IsServicePartition: 0,
+            Anonymous: anonymous,
+        })
+    }
+}
+
+/// Removes partition `partition_number`, as in `\Device\HarddiskN\PartitionM`, from the partition table of the
+/// physical disk `number` with [IOCTL_DISK_SET_DRIVE_LAYOUT_EX](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_set_drive_layout_ex).
+/// The data of the partition stays on the disk until it's overwritten.
+///
+/// Volumes on the partition should be locked and dismounted first, see [VolumeLock](crate::volume_lock::VolumeLock).
+/// Fails with `ERROR_NOT_FOUND` if the disk has no such partition. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn delete_partition(number: u32, partition_number: u32) -> Result<(), WinPartitionError> {
+    let path = physical_disk_path(number);
+    let device = open_device(&path, FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let (header, mut entries) = read_layout(&device)?;
+    let index = entries
+        .iter()
+        .position(|entry| partition_number != 0 && entry.PartitionNumber == partition_number && is_used_entry(entry))
+        .ok_or_else(|| WinPartitionError::from_code("IOCTL_DISK_SET_DRIVE_LAYOUT_EX", Some(&path), ERROR_NOT_FOUND))?;
+
+    // GPT layouts list the partitions, MBR layouts keep the slots of their partition tables
+    if header.PartitionStyle as i32 == PARTITION_STYLE_GPT.0 {
+        entries.remove(index);
+    } else {
+        entries[index] = unused_mbr_entry();
+    }
+    write_layout(&device, header, &entries)
+}
+
+/// Returns the 4-byte disk signature from the master boot record of the physical disk `number`, as in
+/// `\\.\PhysicalDriveN`, read with [IOCTL_DISK_GET_DRIVE_LAYOUT_EX](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_get_drive_layout_ex).
+/// Returns `None` for GPT and raw disks
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_mbr_signature(number: u32) -> Result<Option<u32>, WinPartitionError> {
+    let device = open_physical_disk(number)?;
+    match query_drive_layout(&device)?.style {
+        PartitionStyle::Mbr { signature, .. } => Ok(Some(signature)),
+        _ => Ok(None),
+    }
+}
+
+/// Writes a new disk signature to the master boot record of the physical disk `number`, keeping its partitions.
+///
+/// Windows takes disks with the same signature as a disk it already knows offline, which happens after cloning
+/// a disk. Giving the clone a new signature brings it back. Boot entries referring to the old signature, such as
+/// BCD entries of the volumes on the disk, have to be updated too.
+///
+/// Fails with `ERROR_NOT_SUPPORTED` if the disk isn't an MBR disk and with `ERROR_INVALID_PARAMETER` for a
+/// signature of `0`, which Windows treats as no signature. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn set_mbr_signature(number: u32, signature: u32) -> Result<(), WinPartitionError> {
+    let path = physical_disk_path(number);
+    let error = |code| WinPartitionError::from_code("IOCTL_DISK_SET_DRIVE_LAYOUT_EX", Some(&path), code);
+    if signature == 0 {
+        return Err(error(ERROR_INVALID_PARAMETER));
+    }
+    let device = open_device(&path, FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let (mut header, entries) = read_layout(&device)?;
+    if header.PartitionStyle as i32 != PARTITION_STYLE_MBR.0 {
+        return Err(error(ERROR_NOT_SUPPORTED));
+    }
+
+    header.Anonymous.Mbr.Signature = signature;
+    write_layout(&device, header, &entries)
+}
+
+/// Returns how many bytes the volume at drive letter `letter` can be grown by with [extend_volume], the
+/// unallocated space between its partition and the next partition or the end of the usable area of the disk
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_max_extend_size(letter: char) -> Result<u64, WinPartitionError> {
+    let extent = volume_partition(letter)?;
+    let device = open_physical_disk(extent.disk_number)?;
+    let layout = query_drive_layout(&device)?;
+    let geometry = query_disk_geometry(&device)?;
+
+    Ok(free_space_after(&layout, &geometry, extent.starting_offset + extent.extent_length))
+}
+
+/// Grows the partition of the volume at drive letter `letter` by `bytes` with
+/// [IOCTL_DISK_GROW_PARTITION](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_grow_partition)
+/// and extends its file system over the new space with
+/// [FSCTL_EXTEND_VOLUME](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_extend_volume).
+/// The volume stays mounted.
+///
+/// `bytes` must be a multiple of the sector size. Fails with `ERROR_DISK_FULL` if it's larger than
+/// [get_max_extend_size], and with `ERROR_NOT_SUPPORTED` for volumes spanning several disks. Only NTFS and ReFS
+/// volumes can be extended. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn extend_volume(letter: char, bytes: u64) -> Result<(), WinPartitionError> {
+    let extent = volume_partition(letter)?;
+    let path = physical_disk_path(extent.disk_number);
+    let disk = open_device(&path, FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let layout = query_drive_layout(&disk)?;
+    let geometry = query_disk_geometry(&disk)?;
+    let sector_size = geometry.bytes_per_sector.max(1) as u64;
+    if bytes == 0 || !bytes.is_multiple_of(sector_size) {
+        return Err(WinPartitionError::from_code("IOCTL_DISK_GROW_PARTITION", Some(&path), ERROR_INVALID_PARAMETER));
+    }
+    if bytes > free_space_after(&layout, &geometry, extent.starting_offset + extent.extent_length) {
+        return Err(WinPartitionError::from_code("IOCTL_DISK_GROW_PARTITION", Some(&path), ERROR_DISK_FULL));
+    }
+    let partition = layout.partitions
+        .iter()
+        .find(|partition| partition.starting_offset == extent.starting_offset && partition.number != 0)
+        .ok_or_else(|| WinPartitionError::from_code("IOCTL_DISK_GET_DRIVE_LAYOUT_EX", Some(&path), ERROR_NOT_FOUND))?;
+
+    let mut grow = DiskGrowPartition { partition_number: partition.number, bytes_to_grow: bytes as i64 };
+    unsafe {
+        disk.io_control(
+            "IOCTL_DISK_GROW_PARTITION",
+            IOCTL_DISK_GROW_PARTITION,
+            &mut grow as *mut DiskGrowPartition as *mut c_void,
+            size_of::<DiskGrowPartition>() as u32,
+            null_mut(),
+            0)?;
+    }
+    disk.control("IOCTL_DISK_UPDATE_PROPERTIES", IOCTL_DISK_UPDATE_PROPERTIES)?;
+
+    // The file system keeps its size until it's extended into the grown partition
+    let root = format!("{}:\\", letter);
+    let volume = open_device(&volume_device_path(&root), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let mut sectors = ((extent.extent_length + bytes) / sector_size) as i64;
+    unsafe {
+        volume.io_control(
+            "FSCTL_EXTEND_VOLUME",
+            FSCTL_EXTEND_VOLUME,
+            &mut sectors as *mut i64 as *mut c_void,
+            size_of::<i64>() as u32,
+            null_mut(),
+            0)?;
+    }
+    Ok(())
+}
+
+/// Returns how many bytes the volume at drive letter `letter` can be shrunk by with [shrink_volume], the free
+/// space after its last allocated cluster, read from the allocation bitmap like [VolumeBitmap].
+///
+/// Files aren't moved when shrinking, so this can be much less than the free space of the volume. Defragmenting
+/// moves files to the start of the volume, except for unmovable ones such as the page file or the MFT.
+/// Requires administrator rights.
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn get_max_shrink_size(letter: char) -> Result<u64, WinPartitionError> {
+    let root = format!("{}:\\", letter);
+    let (sectors_per_cluster, bytes_per_sector, _, _) = get_disk_free_space_basic(root.clone())?;
+    let mut total_clusters: u64 = 0;
+    let mut used_clusters: u64 = 0;
+    for chunk in VolumeBitmap::open(root)? {
+        let chunk = chunk?;
+        if let Some(lcn) = chunk.last_allocated() {
+            used_clusters = lcn + 1;
+        }
+        total_clusters = chunk.starting_lcn + chunk.cluster_count;
+    }
+
+    Ok((total_clusters - used_clusters) * sectors_per_cluster as u64 * bytes_per_sector as u64)
+}
+
+/// Shrinks the file system of the volume at drive letter `letter` by `bytes` with
+/// [FSCTL_SHRINK_VOLUME](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_shrink_volume)
+/// and then its partition, leaving unallocated space after it. The volume stays mounted.
+///
+/// `bytes` must be a multiple of the sector size and at most [get_max_shrink_size]. Only NTFS volumes can be
+/// shrunk, volumes spanning several disks fail with `ERROR_NOT_SUPPORTED`. Requires administrator rights.
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn shrink_volume(letter: char, bytes: u64) -> Result<(), WinPartitionError> {
+    let root = format!("{}:\\", letter);
+    let extent = volume_partition(letter)?;
+    let sector_size = get_disk_free_space_basic(root.clone())?.1 as u64;
+    if bytes == 0 || !bytes.is_multiple_of(sector_size) || bytes >= extent.extent_length {
+        return Err(WinPartitionError::from_code("FSCTL_SHRINK_VOLUME", Some(&root), ERROR_INVALID_PARAMETER));
+    }
+    let new_length = extent.extent_length - bytes;
+
+    enable_privilege(SE_MANAGE_VOLUME_NAME)?;
+    let volume = open_device(&volume_device_path(&root), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    shrink_request(&volume, ShrinkPrepare, (new_length / sector_size) as i64)?;
+    if let Err(err) = shrink_request(&volume, ShrinkCommit, 0) {
+        let _ = shrink_request(&volume, ShrinkAbort, 0);
+        return Err(err);
+    }
+
+    // The partition keeps its size until the partition table is rewritten
+    let path = physical_disk_path(extent.disk_number);
+    let disk = open_device(&path, FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let (header, mut entries) = read_layout(&disk)?;
+    let entry = entries
+        .iter_mut()
+        .find(|entry| entry.StartingOffset as u64 == extent.starting_offset && is_used_entry(entry))
+        .ok_or_else(|| WinPartitionError::from_code("IOCTL_DISK_GET_DRIVE_LAYOUT_EX", Some(&path), ERROR_NOT_FOUND))?;
+    entry.PartitionLength = new_length as i64;
+    entry.RewritePartition = 1;
+    write_layout(&disk, header, &entries)
+}
+
+fn shrink_request(
+    volume: &DeviceHandle,
+    request: SHRINK_VOLUME_REQUEST_TYPES,
+    new_number_of_sectors: i64,
+) -> Result<(), WinPartitionError> {
+    let mut input = SHRINK_VOLUME_INFORMATION {
+        ShrinkRequestType: request,
+        Flags: 0,
+        NewNumberOfSectors: new_number_of_sectors,
+    };
+    unsafe {
+        volume.io_control(
+            "FSCTL_SHRINK_VOLUME",
+            FSCTL_SHRINK_VOLUME,
+            &mut input as *mut SHRINK_VOLUME_INFORMATION as *mut c_void,
+            size_of::<SHRINK_VOLUME_INFORMATION>() as u32,
+            null_mut(),
+            0)?;
+    }
+    Ok(())
+}
+
+/// Returns the extent of the single partition holding the volume at `letter`
+fn volume_partition(letter: char) -> Result<DiskExtent, WinPartitionError> {
+    let root = format!("{}:\\", letter);
+    let mut extents = get_volume_disk_extents(root.clone())?;
+    if extents.len() != 1 {
+        let api = "IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS";
+        return Err(WinPartitionError::from_code(api, Some(&root), ERROR_NOT_SUPPORTED));
+    }
+    Ok(extents.remove(0))
+}
+
+/// Unallocated bytes from `end` to the next partition or the end of the usable area. A logical partition
+/// can only grow up to the end of the extended partition holding it
+fn free_space_after(layout: &DriveLayout, geometry: &DiskGeometry, end: u64) -> u64 {
+    let usable_end = match layout.style {
+        PartitionStyle::Gpt { starting_usable_offset, usable_length, .. } => starting_usable_offset + usable_length,
+        _ => geometry.disk_size,
+    };
+    layout.partitions
+        .iter()
+        .fold(usable_end, |limit, partition| {
+            let partition_end = partition.starting_offset + partition.length;
+            if partition.starting_offset >= end {
+                limit.min(partition.starting_offset)
+            } else if partition_end > end {
+                limit.min(partition_end)
+            } else {
+                limit
+            }
+        })
+        .saturating_sub(end)
+}
+
+/// Whether the partition takes one of the four entries of the master boot record. Besides the logical
+/// partitions, extended boot records hold extended entries linking to the next one, which lie inside the
+/// extended partition of the master boot record
+fn is_master_boot_record_entry(layout: &DriveLayout, partition: &PartitionInfo) -> bool {
+    match partition.details {
+        PartitionDetails::Mbr { kind: MbrEntryKind::Primary, .. } => true,
+        PartitionDetails::Mbr { kind: MbrEntryKind::Extended, .. } => !layout.partitions.iter().any(|container| {
+            matches!(container.details, PartitionDetails::Mbr { kind: MbrEntryKind::Extended, .. })
+                && container != partition
+                && container.starting_offset <= partition.starting_offset
+                && partition.starting_offset + partition.length <= container.starting_offset + container.length
+        }),
+        _ => false,
+    }
+}
+
+fn is_active(partition: &PartitionInfo) -> bool {
+    matches!(partition.details, PartitionDetails::Mbr { boot_indicator: true, .. })
+}
+
+/// Unused MBR entry, rewritten so Windows clears the slot
+fn unused_mbr_entry() -> PARTITION_INFORMATION_EX {
+    PARTITION_INFORMATION_EX {
+        PartitionStyle: PARTITION_STYLE_MBR,
+        RewritePartition: 1,
+        ..unsafe { std::mem::zeroed() }
+    }
+}
+
+/// Reads the layout of a disk as the header and entries passed back to [write_layout]
+fn read_layout(
+    device: &DeviceHandle,
+) -> Result<(DRIVE_LAYOUT_INFORMATION_EX, Vec<PARTITION_INFORMATION_EX>), WinPartitionError> {
+    let buffer = unsafe {
+        device.query_variable(
+            "IOCTL_DISK_GET_DRIVE_LAYOUT_EX",
+            IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+            null_mut(),
+            0)?
+    };
+    let layout = unsafe { &*(buffer.as_ptr() as *const DRIVE_LAYOUT_INFORMATION_EX) };
+    let entries = unsafe {
+        std::slice::from_raw_parts(layout.PartitionEntry.as_ptr(), layout.PartitionCount as usize)
+    };
+
+    Ok((*layout, entries.to_vec()))
+}
+
+/// Writes a layout with the given entries and makes Windows reread the partition table
+fn write_layout(
+    device: &DeviceHandle,
+    mut header: DRIVE_LAYOUT_INFORMATION_EX,
+    entries: &[PARTITION_INFORMATION_EX],
+) -> Result<(), WinPartitionError> {
+    let header_size = size_of::<DRIVE_LAYOUT_INFORMATION_EX>() - size_of::<PARTITION_INFORMATION_EX>();
+    let size = header_size + std::mem::size_of_val(entries);
+    // Room for the declared single entry of the header, even when the layout has none
+    let mut buffer = vec![0u64; size.max(size_of::<DRIVE_LAYOUT_INFORMATION_EX>()).div_ceil(size_of::<u64>())];
+    header.PartitionCount = entries.len() as u32;
+    unsafe {
+        let layout = buffer.as_mut_ptr() as *mut DRIVE_LAYOUT_INFORMATION_EX;
+        *layout = header;
+        std::ptr::copy_nonoverlapping(entries.as_ptr(), (*layout).PartitionEntry.as_mut_ptr(), entries.len());
+        device.io_control(
+            "IOCTL_DISK_SET_DRIVE_LAYOUT_EX",
+            IOCTL_DISK_SET_DRIVE_LAYOUT_EX,
+            buffer.as_mut_ptr() as *mut c_void,
+            size as u32,
+            null_mut(),
+            0)?;
+    }
+    device.control("IOCTL_DISK_UPDATE_PROPERTIES", IOCTL_DISK_UPDATE_PROPERTIES)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::physical_disk::{Chs, MediaType};
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn geometry() -> DiskGeometry {
+        DiskGeometry {
+            cylinders: 1305,
+            media_type: MediaType::Fixed,
+            tracks_per_cylinder: 255,
+            sectors_per_track: 63,
+            bytes_per_sector: 512,
+            disk_size: 10 * 1024 * MIB,
+        }
+    }
+
+    fn gpt_layout() -> DriveLayout {
+        DriveLayout {
+            style: PartitionStyle::Gpt {
+                disk_id: Guid::default(),
+                starting_usable_offset: 17408,
+                usable_length: 10 * 1024 * MIB - 2 * 17408,
+                max_partition_count: 128,
+            },
+            partitions: vec![PartitionInfo {
+                number: 1,
+                starting_offset: MIB,
+                length: 100 * MIB,
+                is_service_partition: false,
+                details: PartitionDetails::Gpt {
+                    partition_type: GptPartitionType::EfiSystem.guid(),
+                    partition_id: Guid::default(),
+                    attributes: 0,
+                    name: "EFI system partition".to_string(),
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn check_gpt_test() {
+        let (layout, geometry) = (gpt_layout(), geometry());
+        let builder = PartitionBuilder::gpt(GptPartitionType::BasicData, 101 * MIB, 1024 * MIB);
+        assert_eq!(builder.clone().name("Data").check(&layout, &geometry), Ok(()));
+        // Overlaps the EFI system partition
+        let overlapping = PartitionBuilder::gpt(GptPartitionType::BasicData, 100 * MIB, MIB);
+        assert_eq!(overlapping.check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+        let unaligned = PartitionBuilder::gpt(GptPartitionType::BasicData, 101 * MIB + 4096, MIB);
+        assert_eq!(unaligned.check(&layout, &geometry), Err(ERROR_OFFSET_ALIGNMENT_VIOLATION));
+        assert_eq!(unaligned.alignment(4096).check(&layout, &geometry), Ok(()));
+        assert_eq!(builder.clone().attributes(0x10).check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+        assert_eq!(builder.clone().name(&"x".repeat(37)).check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+        let past_end = PartitionBuilder::gpt(GptPartitionType::BasicData, 101 * MIB, 10 * 1024 * MIB);
+        assert_eq!(past_end.check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+        assert_eq!(PartitionBuilder::mbr(0x07, 101 * MIB, MIB).check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn check_mbr_test() {
+        let geometry = geometry();
+        let active = PartitionInfo {
+            number: 1,
+            starting_offset: MIB,
+            length: 100 * MIB,
+            is_service_partition: false,
+            details: PartitionDetails::Mbr {
+                partition_type: 0x07,
+                kind: MbrEntryKind::Primary,
+                boot_indicator: true,
+                recognized: true,
+                hidden_sectors: 2048,
+                starting_lba: 2048,
+                starting_chs: Chs::from_lba(2048, 255, 63),
+            },
+        };
+        let layout = DriveLayout {
+            style: PartitionStyle::Mbr { signature: 0x1234_5678, checksum: 0 },
+            partitions: vec![active.clone()],
+        };
+        let builder = PartitionBuilder::mbr(0x07, 101 * MIB, 1024 * MIB);
+        assert_eq!(builder.check(&layout, &geometry), Ok(()));
+        assert_eq!(builder.clone().active(true).check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+        assert_eq!(PartitionBuilder::mbr(0x05, 101 * MIB, MIB).check(&layout, &geometry), Err(ERROR_INVALID_PARAMETER));
+
+        // One primary and an extended partition with three logical partitions, two primary entries are free
+        let entry = |kind, partition_type, starting_offset, length| PartitionInfo {
+            number: 0,
+            starting_offset,
+            length,
+            is_service_partition: false,
+            details: PartitionDetails::Mbr {
+                partition_type,
+                kind,
+                boot_indicator: false,
+                recognized: true,
+                hidden_sectors: 0,
+                starting_lba: starting_offset / 512,
+                starting_chs: Chs::from_lba(starting_offset / 512, 255, 63),
+            },
+        };
+        let mut partitions = vec![active.clone(), entry(MbrEntryKind::Extended, 0x0f, 101 * MIB, 3 * 101 * MIB)];
+        for index in 0..3 {
+            let starting_offset = (102 + index * 101) * MIB;
+            partitions.push(entry(MbrEntryKind::Logical, 0x07, starting_offset, 100 * MIB));
+            if index < 2 {
+                partitions.push(entry(MbrEntryKind::Extended, 0x05, starting_offset + 100 * MIB, 101 * MIB));
+            }
+        }
+        let logical = DriveLayout { partitions, ..layout.clone() };
+        assert_eq!(PartitionBuilder::mbr(0x07, 404 * MIB, MIB).check(&logical, &geometry), Ok(()));
+
+        let full = DriveLayout { partitions: vec![active; 4], ..layout };
+        assert_eq!(builder.check(&full, &geometry), Err(ERROR_INVALID_PARAMETER));
+        let raw = DriveLayout { style: PartitionStyle::Raw, partitions: vec![] };
+        assert_eq!(builder.check(&raw, &geometry), Err(ERROR_NOT_SUPPORTED));
+    }
+
+    #[test]
+    fn free_space_after_test() {
+        let (mut layout, geometry) = (gpt_layout(), geometry());
+        let usable_end = 10 * 1024 * MIB - 17408;
+        // Everything after the EFI system partition is unallocated
+        assert_eq!(free_space_after(&layout, &geometry, 101 * MIB), usable_end - 101 * MIB);
+
+        let mut data = layout.partitions[0].clone();
+        data.starting_offset = 1024 * MIB;
+        layout.partitions.push(data);
+        assert_eq!(free_space_after(&layout, &geometry, 101 * MIB), 923 * MIB);
+        assert_eq!(free_space_after(&layout, &geometry, 1124 * MIB), usable_end - 1124 * MIB);
+    }
+}
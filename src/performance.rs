@@ -0,0 +1,197 @@
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::System::Performance::PDH_FMT_COUNTERVALUE_ITEM_W,
+    Windows::Win32::System::Performance::PDH_FMT_DOUBLE,
+    Windows::Win32::System::Performance::PdhAddEnglishCounterW,
+    Windows::Win32::System::Performance::PdhCloseQuery,
+    Windows::Win32::System::Performance::PdhCollectQueryData,
+    Windows::Win32::System::Performance::PdhGetFormattedCounterArrayW,
+    Windows::Win32::System::Performance::PdhOpenQueryW,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::pwstr_to_string;
+
+/// PDH status for "The data is valid"
+const PDH_CSTATUS_VALID_DATA: u32 = 0;
+/// PDH status for "The data is valid and different from the last sample"
+const PDH_CSTATUS_NEW_DATA: u32 = 1;
+/// PDH status returned when the buffer is too small for the counter array
+const PDH_MORE_DATA: i32 = 0x8000_07D2_u32 as i32;
+
+/// Counters of the `PhysicalDisk` performance object, one instance per disk. Their order matches [DiskPerformance::set]
+const COUNTER_PATHS: [&str; 5] = [
+    "\\PhysicalDisk(*)\\Disk Reads/sec",
+    "\\PhysicalDisk(*)\\Disk Writes/sec",
+    "\\PhysicalDisk(*)\\Disk Read Bytes/sec",
+    "\\PhysicalDisk(*)\\Disk Write Bytes/sec",
+    "\\PhysicalDisk(*)\\Current Disk Queue Length",
+];
+
+/// Activity of a physical disk between two samples of a [DiskPerformanceMonitor]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskPerformance {
+    /// Disk number, as in `\\.\PhysicalDriveN` and [PhysicalDisk::number](crate::physical_disk::PhysicalDisk::number)
+    pub disk_number: u32,
+    /// Read operations per second
+    pub reads_per_second: f64,
+    /// Write operations per second
+    pub writes_per_second: f64,
+    /// Bytes read per second
+    pub read_bytes_per_second: f64,
+    /// Bytes written per second
+    pub write_bytes_per_second: f64,
+    /// Number of requests outstanding when the sample was taken
+    pub queue_length: f64,
+}
+
+impl DiskPerformance {
+    /// Read and write operations per second
+    pub fn iops(&self) -> f64 {
+        self.reads_per_second + self.writes_per_second
+    }
+
+    /// Sets the value of the counter at `index` in [COUNTER_PATHS]
+    fn set(&mut self, index: usize, value: f64) {
+        match index {
+            0 => self.reads_per_second = value,
+            1 => self.writes_per_second = value,
+            2 => self.read_bytes_per_second = value,
+            3 => self.write_bytes_per_second = value,
+            _ => self.queue_length = value,
+        }
+    }
+}
+
+/// Reads the `PhysicalDisk` performance counters of every disk through
+/// [PDH](https://docs.microsoft.com/en-us/windows/win32/perfctrs/using-the-pdh-functions-to-consume-counter-data).
+///
+/// Rates are averaged over the time between two samples, so the first [sample](DiskPerformanceMonitor::sample)
+/// should be taken a second or more after [new](DiskPerformanceMonitor::new).
+///
+/// ```no_run
+/// use std::thread::sleep;
+/// use std::time::Duration;
+/// use win_partitions::performance::DiskPerformanceMonitor;
+///
+/// let mut monitor = DiskPerformanceMonitor::new().unwrap();
+/// loop {
+///     sleep(Duration::from_secs(1));
+///     for disk in monitor.sample().unwrap() {
+///         println!("Disk {}: {:.0} IOPS, queue {}", disk.disk_number, disk.iops(), disk.queue_length);
+///     }
+/// }
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003, Windows Vista/Windows Server 2008 for `PdhAddEnglishCounterW`
+pub struct DiskPerformanceMonitor {
+    query: isize,
+    counters: [isize; COUNTER_PATHS.len()],
+}
+
+impl DiskPerformanceMonitor {
+    /// Opens a PDH query with the disk counters and collects the first sample
+    pub fn new() -> Result<DiskPerformanceMonitor, WinPartitionError> {
+        let mut query: isize = 0;
+        check("PdhOpenQueryW", None, unsafe { PdhOpenQueryW(PWSTR::NULL, 0, &mut query) })?;
+        // Closes the query if adding a counter fails
+        let mut monitor = DiskPerformanceMonitor { query, counters: [0; COUNTER_PATHS.len()] };
+        for (counter, path) in monitor.counters.iter_mut().zip(COUNTER_PATHS.iter()) {
+            let status = unsafe { PdhAddEnglishCounterW(query, *path, 0, counter) };
+            check("PdhAddEnglishCounterW", Some(path), status)?;
+        }
+        check("PdhCollectQueryData", None, unsafe { PdhCollectQueryData(query) })?;
+
+        Ok(monitor)
+    }
+
+    /// Collects a sample and returns the activity of every disk since the previous one, ordered by disk number
+    pub fn sample(&mut self) -> Result<Vec<DiskPerformance>, WinPartitionError> {
+        check("PdhCollectQueryData", None, unsafe { PdhCollectQueryData(self.query) })?;
+
+        let mut disks: Vec<DiskPerformance> = vec![];
+        for (index, counter) in self.counters.iter().enumerate() {
+            for (name, value) in counter_values(*counter)? {
+                let disk_number = match parse_instance_name(&name) {
+                    Some(disk_number) => disk_number,
+                    None => continue,
+                };
+                match disks.iter_mut().find(|disk| disk.disk_number == disk_number) {
+                    Some(disk) => disk.set(index, value),
+                    None => {
+                        let mut disk = DiskPerformance { disk_number, ..Default::default() };
+                        disk.set(index, value);
+                        disks.push(disk);
+                    }
+                }
+            }
+        }
+        disks.sort_by_key(|disk| disk.disk_number);
+
+        Ok(disks)
+    }
+}
+
+impl Drop for DiskPerformanceMonitor {
+    fn drop(&mut self) {
+        unsafe { PdhCloseQuery(self.query) };
+    }
+}
+
+/// Returns the instance names and values of a wildcard counter
+fn counter_values(counter: isize) -> Result<Vec<(String, f64)>, WinPartitionError> {
+    let mut size: u32 = 0;
+    let mut count: u32 = 0;
+    let status = unsafe { PdhGetFormattedCounterArrayW(counter, PDH_FMT_DOUBLE, &mut size, &mut count, null_mut()) };
+    if status != PDH_MORE_DATA {
+        check("PdhGetFormattedCounterArrayW", None, status)?;
+        return Ok(vec![]);
+    }
+
+    // The names the items point to are stored after the items, in the same buffer
+    let mut buffer: Vec<u64> = vec![0; (size as usize).div_ceil(8)];
+    let status = unsafe {
+        PdhGetFormattedCounterArrayW(
+            counter,
+            PDH_FMT_DOUBLE,
+            &mut size,
+            &mut count,
+            buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W)
+    };
+    check("PdhGetFormattedCounterArrayW", None, status)?;
+
+    let items = unsafe {
+        std::slice::from_raw_parts(buffer.as_ptr() as *const PDH_FMT_COUNTERVALUE_ITEM_W, count as usize)
+    };
+    Ok(items.iter()
+        .filter(|item| matches!(item.FmtValue.CStatus, PDH_CSTATUS_VALID_DATA | PDH_CSTATUS_NEW_DATA))
+        .map(|item| unsafe { (pwstr_to_string(item.szName), item.FmtValue.Anonymous.doubleValue) })
+        .collect())
+}
+
+/// Disk number of a `PhysicalDisk` instance name such as `0 C:` or `1 D: E:`, `None` for `_Total`
+fn parse_instance_name(name: &str) -> Option<u32> {
+    name.split(' ').next()?.parse().ok()
+}
+
+/// Maps a failed PDH status to [WinPartitionError], PDH statuses are HRESULT-like codes
+fn check(api: &'static str, path: Option<&str>, status: i32) -> Result<(), WinPartitionError> {
+    match status {
+        0 => Ok(()),
+        status => Err(WinPartitionError::from_hresult(api, path, status as u32)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_instance_name_test() {
+        assert_eq!(parse_instance_name("0 C:"), Some(0));
+        assert_eq!(parse_instance_name("12 D: E:"), Some(12));
+        assert_eq!(parse_instance_name("_Total"), None);
+    }
+}
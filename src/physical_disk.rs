@@ -0,0 +1,977 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ptr::null_mut;
+use std::time::Duration;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::DISK_CACHE_INFORMATION,
+    Windows::Win32::Storage::FileSystem::DISK_GEOMETRY_EX,
+    Windows::Win32::Storage::FileSystem::DISK_PERFORMANCE,
+    Windows::Win32::Storage::FileSystem::DRIVE_LAYOUT_INFORMATION_EX,
+    Windows::Win32::Storage::FileSystem::FILE_ACCESS_FLAGS,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_WRITE,
+    Windows::Win32::Storage::FileSystem::PARTITION_INFORMATION_EX,
+    Windows::Win32::Storage::FileSystem::PARTITION_STYLE_GPT,
+    Windows::Win32::Storage::FileSystem::PARTITION_STYLE_MBR,
+    Windows::Win32::System::SystemServices::DISK_ATTRIBUTE_OFFLINE,
+    Windows::Win32::System::SystemServices::DISK_ATTRIBUTE_READ_ONLY,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_CACHE_INFORMATION,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DISK_ATTRIBUTES,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DRIVE_GEOMETRY_EX,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_PERFORMANCE,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_SET_CACHE_INFORMATION,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_SET_DISK_ATTRIBUTES,
+};
+use crate::device_info::{get_disk_device, DiskDevice};
+use crate::dynamic_disk::is_dynamic_disk;
+use crate::error::WinPartitionError;
+use crate::guid::Guid;
+use crate::iscsi::{get_iscsi_target, IscsiTarget};
+use crate::smart::{get_smart_attributes, get_temperature, predicts_failure, Celsius, SmartAttribute};
+use crate::storage::{
+    get_access_alignment, get_hotplug_info, is_ssd, query_device_descriptor, supports_trim, AccessAlignment,
+    DeviceIdentity, HotplugInfo, StorageBusType,
+};
+use crate::win_api::{open_device, DeviceHandle};
+use crate::wstr::vec_u16_to_string;
+
+/// Win32 error code returned when a device doesn't exist
+const ERROR_FILE_NOT_FOUND: i32 = 2;
+
+/// Highest disk number probed by [get_physical_disks]. Disk numbers may have gaps when disks are removed
+const MAX_PHYSICAL_DISKS: u32 = 64;
+
+/// Number of 100 ns intervals per second, the unit of the `DISK_PERFORMANCE` times
+const TICKS_PER_SECOND: u64 = 10_000_000;
+
+/// Output of `IOCTL_DISK_GET_DISK_ATTRIBUTES`, missing from the Windows metadata
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct GetDiskAttributes {
+    version: u32,
+    _reserved: u32,
+    attributes: u64,
+}
+
+/// Input of `IOCTL_DISK_SET_DISK_ATTRIBUTES`, missing from the Windows metadata
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct SetDiskAttributes {
+    version: u32,
+    persist: u8,
+    _reserved1: [u8; 3],
+    attributes: u64,
+    attributes_mask: u64,
+    _reserved2: [u32; 4],
+}
+
+/// Type of media in a physical disk according to [MEDIA_TYPE](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ne-winioctl-media_type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MediaType {
+    /// Format is unknown
+    Unknown,
+    /// Removable media other than floppy, e.g. a USB stick or card reader
+    Removable,
+    /// Fixed hard disk media
+    Fixed,
+    /// One of the floppy disk formats, with its raw `MEDIA_TYPE` code
+    Floppy(u32),
+}
+
+impl From<i32> for MediaType {
+    fn from(index: i32) -> Self {
+        match index {
+            0 => MediaType::Unknown,
+            11 => MediaType::Removable,
+            12 => MediaType::Fixed,
+            _ => MediaType::Floppy(index as u32),
+        }
+    }
+}
+
+/// Provides information about a physical disk (`\\.\PhysicalDriveN`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalDisk {
+    /// Disk number, as in `\\.\PhysicalDriveN`
+    pub number: u32,
+    /// Device path of the disk, e.g. `\\.\PhysicalDrive0`
+    pub path: String,
+    /// Total size of disk in bytes
+    pub size: u64,
+    /// Media type reported by the disk driver
+    pub media_type: MediaType,
+    /// Number of partitions on the disk
+    pub partition_count: u32,
+    /// Bus the disk is attached to
+    pub bus_type: StorageBusType,
+    /// Vendor, model, serial number and firmware revision of the disk
+    pub identity: DeviceIdentity,
+}
+
+impl PhysicalDisk {
+    /// Checks whether the disk is a solid state drive, see [is_ssd](crate::storage::is_ssd)
+    pub fn is_ssd(&self) -> Result<bool, WinPartitionError> {
+        is_ssd(self.path.to_string())
+    }
+
+    /// Checks whether the disk supports TRIM, see [supports_trim](crate::storage::supports_trim)
+    pub fn supports_trim(&self) -> Result<bool, WinPartitionError> {
+        supports_trim(self.path.to_string())
+    }
+
+    /// Returns sector sizes of the disk, see [get_access_alignment](crate::storage::get_access_alignment)
+    pub fn access_alignment(&self) -> Result<AccessAlignment, WinPartitionError> {
+        get_access_alignment(self.path.to_string())
+    }
+
+    /// Checks whether the disk is a dynamic disk, see [is_dynamic_disk](crate::dynamic_disk::is_dynamic_disk)
+    pub fn is_dynamic(&self) -> Result<bool, WinPartitionError> {
+        is_dynamic_disk(self.number)
+    }
+
+    /// Returns the Plug and Play device of the disk, see [get_disk_device](crate::device_info::get_disk_device)
+    pub fn device(&self) -> Result<DiskDevice, WinPartitionError> {
+        get_disk_device(self.number)
+    }
+
+    /// Returns hot-plug capabilities and removal policy, see [get_hotplug_info](crate::storage::get_hotplug_info)
+    pub fn hotplug_info(&self) -> Result<HotplugInfo, WinPartitionError> {
+        get_hotplug_info(self.path.to_string())
+    }
+
+    /// Returns the iSCSI target the disk is a LUN of, `None` for a disk not attached over iSCSI,
+    /// see [get_iscsi_target](crate::iscsi::get_iscsi_target)
+    pub fn iscsi_target(&self) -> Result<Option<IscsiTarget>, WinPartitionError> {
+        if self.bus_type != StorageBusType::Iscsi {
+            return Ok(None);
+        }
+        get_iscsi_target(self.number)
+    }
+
+    /// Checks whether the disk predicts its own failure, see [predicts_failure](crate::smart::predicts_failure)
+    pub fn predicts_failure(&self) -> Result<bool, WinPartitionError> {
+        predicts_failure(self.number)
+    }
+
+    /// Reads the SMART attributes of an ATA disk, see [get_smart_attributes](crate::smart::get_smart_attributes)
+    pub fn smart_attributes(&self) -> Result<Vec<SmartAttribute>, WinPartitionError> {
+        get_smart_attributes(self.number)
+    }
+
+    /// Reads the temperature of the disk, see [get_temperature](crate::smart::get_temperature)
+    pub fn temperature(&self) -> Result<Option<Celsius>, WinPartitionError> {
+        get_temperature(self.number)
+    }
+
+    /// Returns the cache settings of the disk, see [get_disk_cache]
+    pub fn cache(&self) -> Result<DiskCache, WinPartitionError> {
+        get_disk_cache(self.number)
+    }
+
+    /// Returns the cumulative I/O statistics of the disk, see [get_disk_statistics]
+    pub fn statistics(&self) -> Result<DiskStatistics, WinPartitionError> {
+        get_disk_statistics(self.number)
+    }
+
+    /// Returns the offline and read-only flags of the disk, see [get_disk_attributes]
+    pub fn attributes(&self) -> Result<DiskAttributes, WinPartitionError> {
+        get_disk_attributes(self.number)
+    }
+}
+
+/// Geometry of a physical disk as reported by the disk driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskGeometry {
+    /// Number of cylinders
+    pub cylinders: u64,
+    /// Media type reported by the disk driver
+    pub media_type: MediaType,
+    /// Number of tracks per cylinder
+    pub tracks_per_cylinder: u32,
+    /// Number of sectors per track
+    pub sectors_per_track: u32,
+    /// Number of bytes per sector
+    pub bytes_per_sector: u32,
+    /// Total size of disk in bytes. May be larger than cylinders * tracks * sectors * bytes,
+    /// since the geometry is only an approximation for modern disks
+    pub disk_size: u64,
+}
+
+/// Partitioning scheme of a disk, along with the disk identifiers it defines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PartitionStyle {
+    /// Master boot record partition table
+    Mbr {
+        /// 32-bit disk signature
+        signature: u32,
+        /// Checksum of the master boot record
+        checksum: u32,
+    },
+    /// GUID partition table
+    Gpt {
+        /// Disk GUID
+        disk_id: Guid,
+        /// Offset of the first byte usable for partitions
+        starting_usable_offset: u64,
+        /// Number of bytes usable for partitions
+        usable_length: u64,
+        /// Maximum number of partitions the partition table can hold
+        max_partition_count: u32,
+    },
+    /// Disk isn't partitioned
+    Raw,
+}
+
+const PARTITION_SYSTEM_GUID: Guid =
+    Guid::from_values(0xc12a7328, 0xf81f, 0x11d2, [0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b]);
+const PARTITION_MSFT_RESERVED_GUID: Guid =
+    Guid::from_values(0xe3c9e316, 0x0b5c, 0x4db8, [0x81, 0x7d, 0xf9, 0x2d, 0xf0, 0x02, 0x15, 0xae]);
+const PARTITION_BASIC_DATA_GUID: Guid =
+    Guid::from_values(0xebd0a0a2, 0xb9e5, 0x4433, [0x87, 0xc0, 0x68, 0xb6, 0xb7, 0x26, 0x99, 0xc7]);
+const PARTITION_MSFT_RECOVERY_GUID: Guid =
+    Guid::from_values(0xde94bba4, 0x06d1, 0x4d40, [0xa1, 0x6a, 0xbf, 0xd5, 0x01, 0x79, 0xd6, 0xac]);
+const PARTITION_LDM_METADATA_GUID: Guid =
+    Guid::from_values(0x5808c8aa, 0x7e8f, 0x42e0, [0x85, 0xd2, 0xe1, 0xe9, 0x04, 0x34, 0xcf, 0xb3]);
+const PARTITION_LDM_DATA_GUID: Guid =
+    Guid::from_values(0xaf9b60a0, 0x1431, 0x4f62, [0xbc, 0x68, 0x33, 0x11, 0x71, 0x4a, 0x69, 0xad]);
+const PARTITION_SPACES_GUID: Guid =
+    Guid::from_values(0xe75caf8f, 0xf680, 0x4cee, [0xaf, 0xa3, 0xb0, 0x01, 0xe5, 0x6e, 0xfc, 0x2d]);
+const PARTITION_LINUX_FILESYSTEM_GUID: Guid =
+    Guid::from_values(0x0fc63daf, 0x8483, 0x4772, [0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4]);
+const PARTITION_LINUX_SWAP_GUID: Guid =
+    Guid::from_values(0x0657fd6d, 0xa4ab, 0x43c4, [0x84, 0xe5, 0x09, 0x33, 0xc8, 0x4b, 0x4f, 0x4f]);
+
+/// Well-known GPT partition types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GptPartitionType {
+    /// EFI system partition holding the boot loaders
+    EfiSystem,
+    /// Microsoft reserved partition (MSR)
+    MicrosoftReserved,
+    /// Basic data partition, e.g. an NTFS or FAT volume
+    BasicData,
+    /// Windows recovery environment partition
+    Recovery,
+    /// Logical Disk Manager metadata partition of a dynamic disk
+    LdmMetadata,
+    /// Logical Disk Manager data partition of a dynamic disk
+    LdmData,
+    /// Storage Spaces protective partition
+    StorageSpaces,
+    /// Linux filesystem data partition
+    LinuxFilesystem,
+    /// Linux swap partition
+    LinuxSwap,
+    /// Any other partition type, with its type GUID
+    Unknown(Guid),
+}
+
+impl GptPartitionType {
+    /// Returns partition type GUID
+    pub fn guid(&self) -> Guid {
+        match self {
+            GptPartitionType::EfiSystem => PARTITION_SYSTEM_GUID,
+            GptPartitionType::MicrosoftReserved => PARTITION_MSFT_RESERVED_GUID,
+            GptPartitionType::BasicData => PARTITION_BASIC_DATA_GUID,
+            GptPartitionType::Recovery => PARTITION_MSFT_RECOVERY_GUID,
+            GptPartitionType::LdmMetadata => PARTITION_LDM_METADATA_GUID,
+            GptPartitionType::LdmData => PARTITION_LDM_DATA_GUID,
+            GptPartitionType::StorageSpaces => PARTITION_SPACES_GUID,
+            GptPartitionType::LinuxFilesystem => PARTITION_LINUX_FILESYSTEM_GUID,
+            GptPartitionType::LinuxSwap => PARTITION_LINUX_SWAP_GUID,
+            GptPartitionType::Unknown(guid) => *guid,
+        }
+    }
+}
+
+impl From<Guid> for GptPartitionType {
+    fn from(guid: Guid) -> Self {
+        match guid {
+            PARTITION_SYSTEM_GUID => GptPartitionType::EfiSystem,
+            PARTITION_MSFT_RESERVED_GUID => GptPartitionType::MicrosoftReserved,
+            PARTITION_BASIC_DATA_GUID => GptPartitionType::BasicData,
+            PARTITION_MSFT_RECOVERY_GUID => GptPartitionType::Recovery,
+            PARTITION_LDM_METADATA_GUID => GptPartitionType::LdmMetadata,
+            PARTITION_LDM_DATA_GUID => GptPartitionType::LdmData,
+            PARTITION_SPACES_GUID => GptPartitionType::StorageSpaces,
+            PARTITION_LINUX_FILESYSTEM_GUID => GptPartitionType::LinuxFilesystem,
+            PARTITION_LINUX_SWAP_GUID => GptPartitionType::LinuxSwap,
+            _ => GptPartitionType::Unknown(guid),
+        }
+    }
+}
+
+/// Position of an entry in an MBR partition table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MbrEntryKind {
+    /// Primary partition stored in the master boot record
+    Primary,
+    /// Extended partition container holding logical partitions
+    Extended,
+    /// Logical partition inside an extended partition
+    Logical,
+}
+
+/// Most heads a CHS address of an MBR entry can hold
+const MAX_CHS_HEADS: u32 = 255;
+/// Most sectors per track a CHS address of an MBR entry can hold
+const MAX_CHS_SECTORS: u32 = 63;
+
+/// Cylinder-head-sector address of a sector, as stored in MBR partition entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chs {
+    /// Cylinder number, at most 1023
+    pub cylinder: u16,
+    /// Head number
+    pub head: u8,
+    /// Sector number, starting from 1
+    pub sector: u8,
+}
+
+impl Chs {
+    /// Converts a logical block address to CHS using the disk geometry. Addresses beyond
+    /// cylinder 1023 are reported as the maximum address, like partitioning tools store them.
+    /// The geometry is clamped to the 255 heads and 63 sectors per track an MBR entry can hold
+    pub fn from_lba(lba: u64, tracks_per_cylinder: u32, sectors_per_track: u32) -> Chs {
+        let heads = tracks_per_cylinder.clamp(1, MAX_CHS_HEADS) as u64;
+        let sectors = sectors_per_track.clamp(1, MAX_CHS_SECTORS) as u64;
+        let cylinder = lba / (heads * sectors);
+        if cylinder > 1023 {
+            return Chs {
+                cylinder: 1023,
+                head: (heads - 1) as u8,
+                sector: sectors as u8,
+            };
+        }
+        Chs {
+            cylinder: cylinder as u16,
+            head: ((lba / sectors) % heads) as u8,
+            sector: (lba % sectors + 1) as u8,
+        }
+    }
+}
+
+/// MBR partition type byte of the partition holding the LDM database and volumes of a dynamic disk
+const PARTITION_LDM: u8 = 0x42;
+
+/// MBR partition type bytes of extended partition containers
+pub(crate) fn is_extended_partition_type(partition_type: u8) -> bool {
+    matches!(partition_type, 0x05 | 0x0f | 0x85)
+}
+
+/// Partitioning scheme specific details of a partition
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PartitionDetails {
+    /// Partition of an MBR disk
+    Mbr {
+        /// Partition type byte, e.g. `0x07` for NTFS or `0x0C` for FAT32
+        partition_type: u8,
+        /// Whether this is a primary, extended or logical partition
+        kind: MbrEntryKind,
+        /// Partition is marked active (bootable)
+        boot_indicator: bool,
+        /// Windows recognizes the partition type
+        recognized: bool,
+        /// Number of sectors between the partition table and the partition
+        hidden_sectors: u32,
+        /// Logical block address of the first sector
+        starting_lba: u64,
+        /// CHS address of the first sector, computed from the disk geometry
+        starting_chs: Chs,
+    },
+    /// Partition of a GPT disk
+    Gpt {
+        /// Partition type GUID
+        partition_type: Guid,
+        /// Unique partition GUID
+        partition_id: Guid,
+        /// GPT attribute bits
+        attributes: u64,
+        /// Partition name
+        name: String,
+    },
+}
+
+/// Entry of a disk's partition table
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartitionInfo {
+    /// Partition number, as in `\Device\HarddiskN\PartitionM`
+    pub number: u32,
+    /// Offset of the partition from the start of the disk in bytes
+    pub starting_offset: u64,
+    /// Length of the partition in bytes
+    pub length: u64,
+    /// Partition is a service partition, like a recovery partition
+    pub is_service_partition: bool,
+    /// Partitioning scheme specific details
+    pub details: PartitionDetails,
+}
+
+impl PartitionInfo {
+    /// Returns the classified partition type of a GPT partition, `None` for MBR partitions
+    pub fn gpt_type(&self) -> Option<GptPartitionType> {
+        match &self.details {
+            PartitionDetails::Gpt { partition_type, .. } => Some(GptPartitionType::from(*partition_type)),
+            PartitionDetails::Mbr { .. } => None,
+        }
+    }
+}
+
+/// Partition table of a physical disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DriveLayout {
+    /// Partitioning scheme and disk identifiers
+    pub style: PartitionStyle,
+    /// Used entries of the partition table, including extended partition containers of MBR disks
+    pub partitions: Vec<PartitionInfo>,
+}
+
+impl DriveLayout {
+    /// Whether the disk is a dynamic disk, i.e. it has an LDM partition, see
+    /// [is_dynamic_disk](crate::dynamic_disk::is_dynamic_disk)
+    pub fn is_dynamic(&self) -> bool {
+        self.partitions.iter().any(|partition| match &partition.details {
+            PartitionDetails::Mbr { partition_type, .. } => *partition_type == PARTITION_LDM,
+            PartitionDetails::Gpt { .. } => partition.gpt_type() == Some(GptPartitionType::LdmMetadata),
+        })
+    }
+}
+
+/// Cache settings of a physical disk, see [get_disk_cache]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskCache {
+    /// Whether the device read cache is enabled
+    pub read_cache_enabled: bool,
+    /// Whether the device write cache is enabled. Completed writes may be lost on power failure
+    /// unless they are flushed
+    pub write_cache_enabled: bool,
+    /// Whether the device keeps changed settings across power cycles
+    pub parameters_savable: bool,
+}
+
+impl From<&DISK_CACHE_INFORMATION> for DiskCache {
+    fn from(info: &DISK_CACHE_INFORMATION) -> Self {
+        DiskCache {
+            read_cache_enabled: info.ReadCacheEnabled != 0,
+            write_cache_enabled: info.WriteCacheEnabled != 0,
+            parameters_savable: info.ParametersSavable != 0,
+        }
+    }
+}
+
+/// Cumulative I/O statistics of a physical disk since the disk counters started, see [get_disk_statistics]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskStatistics {
+    /// Number of bytes read
+    pub bytes_read: u64,
+    /// Number of bytes written
+    pub bytes_written: u64,
+    /// Time spent on reads. Overlapping requests are counted separately, so this can exceed the elapsed time
+    pub read_time: Duration,
+    /// Time spent on writes, counted like `read_time`
+    pub write_time: Duration,
+    /// Time the disk had no outstanding requests
+    pub idle_time: Duration,
+    /// Number of reads, wrapping around at `u32::MAX`
+    pub read_count: u32,
+    /// Number of writes, wrapping around at `u32::MAX`
+    pub write_count: u32,
+    /// Number of requests outstanding when the statistics were read
+    pub queue_depth: u32,
+    /// Number of requests split into several requests to the disk
+    pub split_count: u32,
+    /// System time the statistics were read at, in 100 ns intervals since January 1, 1601 (UTC)
+    pub query_time: u64,
+}
+
+impl DiskStatistics {
+    /// Averages the activity between an `earlier` sample of the same disk and this one
+    pub fn rates_since(&self, earlier: &DiskStatistics) -> DiskRates {
+        let elapsed = self.query_time.saturating_sub(earlier.query_time) as f64 / TICKS_PER_SECOND as f64;
+        if elapsed <= 0.0 {
+            return DiskRates::default();
+        }
+        let idle = self.idle_time.saturating_sub(earlier.idle_time).as_secs_f64();
+        DiskRates {
+            reads_per_second: self.read_count.wrapping_sub(earlier.read_count) as f64 / elapsed,
+            writes_per_second: self.write_count.wrapping_sub(earlier.write_count) as f64 / elapsed,
+            read_bytes_per_second: self.bytes_read.saturating_sub(earlier.bytes_read) as f64 / elapsed,
+            write_bytes_per_second: self.bytes_written.saturating_sub(earlier.bytes_written) as f64 / elapsed,
+            busy: (1.0 - idle / elapsed).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Activity of a physical disk between two [DiskStatistics] samples
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskRates {
+    /// Read operations per second
+    pub reads_per_second: f64,
+    /// Write operations per second
+    pub writes_per_second: f64,
+    /// Bytes read per second
+    pub read_bytes_per_second: f64,
+    /// Bytes written per second
+    pub write_bytes_per_second: f64,
+    /// Fraction of the time the disk had outstanding requests, from 0 to 1
+    pub busy: f64,
+}
+
+impl DiskRates {
+    /// Read and write operations per second
+    pub fn iops(&self) -> f64 {
+        self.reads_per_second + self.writes_per_second
+    }
+}
+
+/// Offline and read-only flags of a physical disk, as shown by `diskpart`'s `attributes disk`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskAttributes {
+    /// Whether the disk is offline. Windows doesn't mount the volumes of an offline disk
+    pub offline: bool,
+    /// Whether the disk is read-only
+    pub read_only: bool,
+}
+
+impl From<u64> for DiskAttributes {
+    fn from(attributes: u64) -> Self {
+        DiskAttributes {
+            offline: attributes & DISK_ATTRIBUTE_OFFLINE != 0,
+            read_only: attributes & DISK_ATTRIBUTE_READ_ONLY != 0,
+        }
+    }
+}
+
+/// Returns device path of a physical disk, e.g. `\\.\PhysicalDrive0`
+pub fn physical_disk_path(number: u32) -> String {
+    format!("\\\\.\\PhysicalDrive{}", number)
+}
+
+/// Opens a physical disk for querying its properties
+pub(crate) fn open_physical_disk(number: u32) -> Result<DeviceHandle, WinPartitionError> {
+    open_device(&physical_disk_path(number), FILE_ACCESS_FLAGS(0))
+}
+
+/// Calls [IOCTL_DISK_GET_DRIVE_GEOMETRY_EX](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_get_drive_geometry_ex)
+/// and returns geometry of a physical disk
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_disk_geometry(number: u32) -> Result<DiskGeometry, WinPartitionError> {
+    let device = open_physical_disk(number)?;
+    query_disk_geometry(&device)
+}
+
+pub(crate) fn query_disk_geometry(device: &DeviceHandle) -> Result<DiskGeometry, WinPartitionError> {
+    let geometry: DISK_GEOMETRY_EX = device.query(
+        "IOCTL_DISK_GET_DRIVE_GEOMETRY_EX",
+        IOCTL_DISK_GET_DRIVE_GEOMETRY_EX)?;
+
+    Ok(DiskGeometry {
+        cylinders: geometry.Geometry.Cylinders as u64,
+        media_type: MediaType::from(geometry.Geometry.MediaType.0),
+        tracks_per_cylinder: geometry.Geometry.TracksPerCylinder,
+        sectors_per_track: geometry.Geometry.SectorsPerTrack,
+        bytes_per_sector: geometry.Geometry.BytesPerSector,
+        disk_size: geometry.DiskSize as u64,
+    })
+}
+
+/// Gets information about a single physical disk
+pub fn get_physical_disk(number: u32) -> Result<PhysicalDisk, WinPartitionError> {
+    let device = open_physical_disk(number)?;
+    let geometry = query_disk_geometry(&device)?;
+    // Extended partition containers aren't partitions on their own and have no partition number
+    let partition_count = query_drive_layout(&device)?
+        .partitions
+        .iter()
+        .filter(|partition| partition.number != 0)
+        .count() as u32;
+    let (bus_type, identity) = query_device_descriptor(&device)?;
+
+    Ok(PhysicalDisk {
+        number,
+        path: physical_disk_path(number),
+        size: geometry.disk_size,
+        media_type: geometry.media_type,
+        partition_count,
+        bus_type,
+        identity,
+    })
+}
+
+/// Gets list of physical disks attached to the system. Disks that can't be queried, e.g. an empty card reader
+/// or a disk the caller can't access, are left out
+pub fn get_physical_disks() -> Result<Vec<PhysicalDisk>, WinPartitionError> {
+    get_physical_disks_lossy().map(|(disks, _errors)| disks)
+}
+
+/// Gets list of physical disks like [get_physical_disks], together with the errors met querying the disks
+/// that were left out. Drives without media (e.g. an empty card reader) are not reported as errors
+pub fn get_physical_disks_lossy() -> Result<(Vec<PhysicalDisk>, Vec<WinPartitionError>), WinPartitionError> {
+    let mut result: Vec<PhysicalDisk> = vec![];
+    let mut errors: Vec<WinPartitionError> = vec![];
+    for number in 0..MAX_PHYSICAL_DISKS {
+        match get_physical_disk(number) {
+            Ok(disk) => result.push(disk),
+            Err(err) if err.raw_os_error() == ERROR_FILE_NOT_FOUND => {}
+            Err(WinPartitionError::NotReady { .. }) => {}
+            Err(err) => errors.push(err),
+        }
+    }
+
+    Ok((result, errors))
+}
+
+/// Calls [IOCTL_DISK_GET_DRIVE_LAYOUT_EX](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_get_drive_layout_ex)
+/// and returns the partition table of a physical disk
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_drive_layout(number: u32) -> Result<DriveLayout, WinPartitionError> {
+    let device = open_physical_disk(number)?;
+    query_drive_layout(&device)
+}
+
+pub(crate) fn query_drive_layout(device: &DeviceHandle) -> Result<DriveLayout, WinPartitionError> {
+    let buffer = unsafe {
+        device.query_variable(
+            "IOCTL_DISK_GET_DRIVE_LAYOUT_EX",
+            IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+            std::ptr::null_mut(),
+            0)?
+    };
+
+    let layout = unsafe { &*(buffer.as_ptr() as *const DRIVE_LAYOUT_INFORMATION_EX) };
+    let style = match layout.PartitionStyle as i32 {
+        style if style == PARTITION_STYLE_MBR.0 => {
+            let mbr = unsafe { layout.Anonymous.Mbr };
+            PartitionStyle::Mbr {
+                signature: mbr.Signature,
+                checksum: mbr.CheckSum,
+            }
+        }
+        style if style == PARTITION_STYLE_GPT.0 => {
+            let gpt = unsafe { layout.Anonymous.Gpt };
+            PartitionStyle::Gpt {
+                disk_id: Guid::from(gpt.DiskId),
+                starting_usable_offset: gpt.StartingUsableOffset as u64,
+                usable_length: gpt.UsableLength as u64,
+                max_partition_count: gpt.MaxPartitionCount,
+            }
+        }
+        _ => PartitionStyle::Raw,
+    };
+
+    let geometry = query_disk_geometry(device)?;
+    let entries = unsafe {
+        std::slice::from_raw_parts(layout.PartitionEntry.as_ptr(), layout.PartitionCount as usize)
+    };
+    let partitions = entries.iter()
+        .enumerate()
+        .filter(|(_, entry)| is_used_entry(entry))
+        .map(|(index, entry)| partition_info(index, entry, &geometry))
+        .collect();
+
+    Ok(DriveLayout { style, partitions })
+}
+
+/// MBR layouts always contain four entries per partition table, unused ones have type zero
+pub(crate) fn is_used_entry(entry: &PARTITION_INFORMATION_EX) -> bool {
+    entry.PartitionStyle == PARTITION_STYLE_GPT || unsafe { entry.Anonymous.Mbr.PartitionType } != 0
+}
+
+fn partition_info(index: usize, entry: &PARTITION_INFORMATION_EX, geometry: &DiskGeometry) -> PartitionInfo {
+    let details = if entry.PartitionStyle == PARTITION_STYLE_GPT {
+        let gpt = unsafe { entry.Anonymous.Gpt };
+        PartitionDetails::Gpt {
+            partition_type: Guid::from(gpt.PartitionType),
+            partition_id: Guid::from(gpt.PartitionId),
+            attributes: gpt.Attributes.0,
+            name: vec_u16_to_string(&gpt.Name),
+        }
+    } else {
+        let mbr = unsafe { entry.Anonymous.Mbr };
+        // The first four entries come from the master boot record, the rest from extended boot records
+        let kind = if is_extended_partition_type(mbr.PartitionType) {
+            MbrEntryKind::Extended
+        } else if index < 4 {
+            MbrEntryKind::Primary
+        } else {
+            MbrEntryKind::Logical
+        };
+        let starting_lba = entry.StartingOffset as u64 / geometry.bytes_per_sector.max(1) as u64;
+        PartitionDetails::Mbr {
+            partition_type: mbr.PartitionType,
+            kind,
+            boot_indicator: mbr.BootIndicator != 0,
+            recognized: mbr.RecognizedPartition != 0,
+            hidden_sectors: mbr.HiddenSectors,
+            starting_lba,
+            starting_chs: Chs::from_lba(starting_lba, geometry.tracks_per_cylinder, geometry.sectors_per_track),
+        }
+    };
+
+    PartitionInfo {
+        number: entry.PartitionNumber,
+        starting_offset: entry.StartingOffset as u64,
+        length: entry.PartitionLength as u64,
+        is_service_partition: entry.IsServicePartition != 0,
+        details,
+    }
+}
+
+/// Calls [IOCTL_DISK_GET_CACHE_INFORMATION](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_get_cache_information)
+/// and returns the cache settings of the physical disk `number`, as in `\\.\PhysicalDriveN`.
+/// Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_disk_cache(number: u32) -> Result<DiskCache, WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let info = query_cache_information(&device)?;
+    Ok(DiskCache::from(&info))
+}
+
+/// Calls [IOCTL_DISK_SET_CACHE_INFORMATION](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_set_cache_information)
+/// and enables or disables the write cache of the physical disk `number`, keeping its other cache settings.
+/// The change is lost on a power cycle unless [DiskCache::parameters_savable] is set. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn set_write_cache_enabled(number: u32, enabled: bool) -> Result<(), WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let mut info = query_cache_information(&device)?;
+    info.WriteCacheEnabled = enabled as u8;
+    unsafe {
+        device.io_control(
+            "IOCTL_DISK_SET_CACHE_INFORMATION",
+            IOCTL_DISK_SET_CACHE_INFORMATION,
+            &mut info as *mut DISK_CACHE_INFORMATION as *mut c_void,
+            size_of::<DISK_CACHE_INFORMATION>() as u32,
+            null_mut(),
+            0)?;
+    }
+    Ok(())
+}
+
+fn query_cache_information(device: &DeviceHandle) -> Result<DISK_CACHE_INFORMATION, WinPartitionError> {
+    // DISK_CACHE_INFORMATION holds a union and has no Default to use with DeviceHandle::query
+    let mut info: DISK_CACHE_INFORMATION = unsafe { std::mem::zeroed() };
+    unsafe {
+        device.io_control(
+            "IOCTL_DISK_GET_CACHE_INFORMATION",
+            IOCTL_DISK_GET_CACHE_INFORMATION,
+            null_mut(),
+            0,
+            &mut info as *mut DISK_CACHE_INFORMATION as *mut c_void,
+            size_of::<DISK_CACHE_INFORMATION>() as u32)?;
+    }
+    Ok(info)
+}
+
+/// Calls [IOCTL_DISK_PERFORMANCE](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_performance)
+/// and returns the cumulative I/O statistics of the physical disk `number`, as in `\\.\PhysicalDriveN`.
+/// Rates are computed from two samples with [DiskStatistics::rates_since].
+///
+/// The disk counters start with the first call, earlier I/O isn't counted.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_disk_statistics(number: u32) -> Result<DiskStatistics, WinPartitionError> {
+    let device = open_physical_disk(number)?;
+    let performance: DISK_PERFORMANCE = device.query("IOCTL_DISK_PERFORMANCE", IOCTL_DISK_PERFORMANCE)?;
+
+    Ok(DiskStatistics::from(&performance))
+}
+
+impl From<&DISK_PERFORMANCE> for DiskStatistics {
+    fn from(performance: &DISK_PERFORMANCE) -> Self {
+        let duration = |ticks: i64| Duration::from_nanos(ticks.max(0) as u64 * 100);
+        DiskStatistics {
+            bytes_read: performance.BytesRead as u64,
+            bytes_written: performance.BytesWritten as u64,
+            read_time: duration(performance.ReadTime),
+            write_time: duration(performance.WriteTime),
+            idle_time: duration(performance.IdleTime),
+            read_count: performance.ReadCount,
+            write_count: performance.WriteCount,
+            queue_depth: performance.QueueDepth,
+            split_count: performance.SplitCount,
+            query_time: performance.QueryTime as u64,
+        }
+    }
+}
+
+/// Calls [IOCTL_DISK_GET_DISK_ATTRIBUTES](https://docs.microsoft.com/en-us/windows-hardware/drivers/ddi/ntdddisk/ni-ntdddisk-ioctl_disk_get_disk_attributes)
+/// and returns the offline and read-only flags of the physical disk `number`, as in `\\.\PhysicalDriveN`
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn get_disk_attributes(number: u32) -> Result<DiskAttributes, WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ)?;
+    let attributes: GetDiskAttributes = device.query(
+        "IOCTL_DISK_GET_DISK_ATTRIBUTES",
+        IOCTL_DISK_GET_DISK_ATTRIBUTES)?;
+
+    Ok(DiskAttributes::from(attributes.attributes))
+}
+
+/// Calls [IOCTL_DISK_SET_DISK_ATTRIBUTES](https://docs.microsoft.com/en-us/windows-hardware/drivers/ddi/ntdddisk/ni-ntdddisk-ioctl_disk_set_disk_attributes)
+/// and takes the physical disk `number` offline or brings it back online. Taking a disk offline dismounts its
+/// volumes, so nothing else writes to it while it's being imaged. The disk holding the system or boot volume
+/// can't be taken offline.
+///
+/// With `persist` the change survives reboots, otherwise it lasts until the disk is removed or the system
+/// restarts. Requires administrator rights.
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn set_disk_offline(number: u32, offline: bool, persist: bool) -> Result<(), WinPartitionError> {
+    set_disk_attribute(number, DISK_ATTRIBUTE_OFFLINE, offline, persist)
+}
+
+/// Calls [IOCTL_DISK_SET_DISK_ATTRIBUTES](https://docs.microsoft.com/en-us/windows-hardware/drivers/ddi/ntdddisk/ni-ntdddisk-ioctl_disk_set_disk_attributes)
+/// and makes the physical disk `number` read-only or writable, persisting the change like [set_disk_offline]
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn set_disk_read_only(number: u32, read_only: bool, persist: bool) -> Result<(), WinPartitionError> {
+    set_disk_attribute(number, DISK_ATTRIBUTE_READ_ONLY, read_only, persist)
+}
+
+/// Sets or clears `attribute` of the physical disk `number`, keeping its other attributes
+fn set_disk_attribute(number: u32, attribute: u64, set: bool, persist: bool) -> Result<(), WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let mut input = SetDiskAttributes {
+        version: size_of::<SetDiskAttributes>() as u32,
+        persist: persist as u8,
+        attributes: if set { attribute } else { 0 },
+        attributes_mask: attribute,
+        ..Default::default()
+    };
+    unsafe {
+        device.io_control(
+            "IOCTL_DISK_SET_DISK_ATTRIBUTES",
+            IOCTL_DISK_SET_DISK_ATTRIBUTES,
+            &mut input as *mut SetDiskAttributes as *mut c_void,
+            size_of::<SetDiskAttributes>() as u32,
+            null_mut(),
+            0)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn media_type_test() {
+        assert_eq!(physical_disk_path(1), "\\\\.\\PhysicalDrive1");
+        assert!(matches!(MediaType::from(12), MediaType::Fixed));
+        assert!(matches!(MediaType::from(2), MediaType::Floppy(2)));
+    }
+
+    #[test]
+    fn chs_from_lba_test() {
+        assert_eq!(Chs::from_lba(2048, 255, 63), Chs { cylinder: 0, head: 32, sector: 33 });
+        assert_eq!(Chs::from_lba(u32::MAX as u64, 255, 63), Chs { cylinder: 1023, head: 254, sector: 63 });
+        assert_eq!(Chs::from_lba(u32::MAX as u64, 256, 64), Chs { cylinder: 1023, head: 254, sector: 63 });
+        assert_eq!(Chs::from_lba(2048, 256, 64), Chs::from_lba(2048, 255, 63));
+    }
+
+    #[test]
+    fn gpt_partition_type_test() {
+        assert_eq!(GptPartitionType::from(PARTITION_SYSTEM_GUID), GptPartitionType::EfiSystem);
+        assert_eq!(GptPartitionType::Recovery.guid().to_string(), "DE94BBA4-06D1-4D40-A16A-BFD50179D6AC");
+        let guid = Guid::from_values(1, 2, 3, [4; 8]);
+        assert_eq!(GptPartitionType::from(guid), GptPartitionType::Unknown(guid));
+    }
+
+    #[test]
+    fn drive_layout_is_dynamic_test() {
+        let partition = |details| PartitionInfo {
+            number: 1,
+            starting_offset: 1024 * 1024,
+            length: 1024 * 1024 * 1024,
+            is_service_partition: false,
+            details,
+        };
+        let mbr = |partition_type| PartitionDetails::Mbr {
+            partition_type,
+            kind: MbrEntryKind::Primary,
+            boot_indicator: false,
+            recognized: true,
+            hidden_sectors: 2048,
+            starting_lba: 2048,
+            starting_chs: Chs { cylinder: 0, head: 32, sector: 33 },
+        };
+        let gpt = |partition_type: GptPartitionType| PartitionDetails::Gpt {
+            partition_type: partition_type.guid(),
+            partition_id: Guid::from_values(1, 2, 3, [4; 8]),
+            attributes: 0,
+            name: String::new(),
+        };
+        let layout = |details| DriveLayout { style: PartitionStyle::Raw, partitions: vec![partition(details)] };
+
+        assert!(layout(mbr(PARTITION_LDM)).is_dynamic());
+        assert!(!layout(mbr(0x07)).is_dynamic());
+        assert!(layout(gpt(GptPartitionType::LdmMetadata)).is_dynamic());
+        assert!(!layout(gpt(GptPartitionType::BasicData)).is_dynamic());
+    }
+
+    #[test]
+    fn disk_cache_test() {
+        let mut info: DISK_CACHE_INFORMATION = unsafe { std::mem::zeroed() };
+        info.ReadCacheEnabled = 1;
+        info.WriteCacheEnabled = 1;
+        let cache = DiskCache::from(&info);
+        assert!(cache.read_cache_enabled && cache.write_cache_enabled);
+        assert!(!cache.parameters_savable);
+    }
+
+    #[test]
+    fn disk_attributes_test() {
+        assert_eq!(size_of::<SetDiskAttributes>(), 40);
+        assert_eq!(DiskAttributes::from(0), DiskAttributes::default());
+        assert_eq!(DiskAttributes::from(3), DiskAttributes { offline: true, read_only: true });
+        assert_eq!(DiskAttributes::from(2), DiskAttributes { offline: false, read_only: true });
+    }
+
+    #[test]
+    fn disk_rates_test() {
+        let performance = DISK_PERFORMANCE {
+            BytesRead: 4096,
+            ReadCount: u32::MAX,
+            IdleTime: 5 * TICKS_PER_SECOND as i64,
+            QueryTime: 100 * TICKS_PER_SECOND as i64,
+            ..Default::default()
+        };
+        let earlier = DiskStatistics::from(&performance);
+        assert_eq!(earlier.idle_time, Duration::from_secs(5));
+
+        let later = DiskStatistics {
+            bytes_read: 4096 + 8 * 1024 * 1024,
+            read_count: 199,
+            write_count: 100,
+            idle_time: Duration::from_secs(6),
+            query_time: 102 * TICKS_PER_SECOND,
+            ..earlier
+        };
+        let rates = later.rates_since(&earlier);
+        assert_eq!(rates.reads_per_second, 100.0);
+        assert_eq!(rates.iops(), 150.0);
+        assert_eq!(rates.read_bytes_per_second, 4.0 * 1024.0 * 1024.0);
+        assert_eq!(rates.busy, 0.5);
+        assert_eq!(earlier.rates_since(&earlier), DiskRates::default());
+    }
+}
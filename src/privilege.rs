@@ -0,0 +1,65 @@
+use std::io;
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::CloseHandle,
+    Windows::Win32::Foundation::HANDLE,
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::Security::AdjustTokenPrivileges,
+    Windows::Win32::Security::LookupPrivilegeValueW,
+    Windows::Win32::Security::SE_PRIVILEGE_ENABLED,
+    Windows::Win32::Security::TOKEN_ADJUST_PRIVILEGES,
+    Windows::Win32::Security::TOKEN_PRIVILEGES,
+    Windows::Win32::Security::TOKEN_QUERY,
+    Windows::Win32::System::Threading::GetCurrentProcess,
+    Windows::Win32::System::Threading::OpenProcessToken,
+};
+use crate::error::WinPartitionError;
+
+/// Privilege to perform volume maintenance tasks, held by administrators
+pub(crate) const SE_MANAGE_VOLUME_NAME: &str = "SeManageVolumePrivilege";
+/// Win32 error code set by `AdjustTokenPrivileges` when the token doesn't hold the privilege
+const ERROR_NOT_ALL_ASSIGNED: i32 = 1300;
+
+/// Enables privilege `name`, such as `SeManageVolumePrivilege`, in the token of the current process by calling
+/// [AdjustTokenPrivileges](https://docs.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-adjusttokenprivileges).
+/// Returns `false` if the process doesn't hold the privilege, e.g. when it isn't elevated
+pub(crate) fn enable_privilege(name: &str) -> Result<bool, WinPartitionError> {
+    let mut token = HANDLE(0);
+    let result = unsafe {
+        OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token).as_bool()
+    };
+    if !result {
+        return Err(WinPartitionError::last_os_error("OpenProcessToken", None));
+    }
+
+    let result = adjust_privilege(token, name);
+    unsafe { CloseHandle(token) };
+    result
+}
+
+fn adjust_privilege(token: HANDLE, name: &str) -> Result<bool, WinPartitionError> {
+    let mut privileges = TOKEN_PRIVILEGES { PrivilegeCount: 1, ..Default::default() };
+    privileges.Privileges[0].Attributes = SE_PRIVILEGE_ENABLED;
+    let result = unsafe { LookupPrivilegeValueW(PWSTR::NULL, name, &mut privileges.Privileges[0].Luid).as_bool() };
+    if !result {
+        return Err(WinPartitionError::last_os_error("LookupPrivilegeValueW", Some(name)));
+    }
+
+    let result = unsafe {
+        AdjustTokenPrivileges(
+            token,
+            false,
+            &mut privileges,
+            size_of::<TOKEN_PRIVILEGES>() as u32,
+            null_mut(),
+            null_mut()).as_bool()
+    };
+    // AdjustTokenPrivileges succeeds for privileges the token doesn't hold and only reports them in the last error
+    match (result, io::Error::last_os_error().raw_os_error()) {
+        (false, _) => Err(WinPartitionError::last_os_error("AdjustTokenPrivileges", Some(name))),
+        (true, Some(ERROR_NOT_ALL_ASSIGNED)) => Ok(false),
+        (true, _) => Ok(true),
+    }
+}
@@ -0,0 +1,171 @@
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::Storage::FileSystem::{
+        CLSID_DiskQuotaControl, IDiskQuotaControl, IDiskQuotaUser, IEnumDiskQuotaUsers, DISKQUOTA_STATE_ENFORCE,
+        DISKQUOTA_STATE_MASK, DISKQUOTA_STATE_TRACK, DISKQUOTA_USERNAME_RESOLVE_SYNC,
+    },
+    Windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+};
+use crate::com::{com_error, ComGuard};
+use crate::error::WinPartitionError;
+use crate::wstr::vec_u16_to_string;
+
+/// Whether disk quotas are tracked or enforced on a volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuotaState {
+    /// Quotas are disabled
+    Disabled,
+    /// Usage is tracked, but users may exceed their limit
+    Tracked,
+    /// Users are denied disk space beyond their limit
+    Enforced,
+}
+
+/// Disk quota entry of a user on a volume, see [IDiskQuotaUser](https://docs.microsoft.com/en-us/windows/win32/api/dskquota/nn-dskquota-idiskquotauser)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuotaEntry {
+    /// Logon name of the user, e.g. `DOMAIN\user`. Empty if the account couldn't be resolved
+    pub logon_name: String,
+    /// Display name of the user, e.g. `John Doe`
+    pub display_name: String,
+    /// Disk space charged to the user in bytes
+    pub quota_used: u64,
+    /// Warning threshold in bytes, `None` if there is no threshold
+    pub quota_threshold: Option<u64>,
+    /// Quota limit in bytes, `None` if there is no limit
+    pub quota_limit: Option<u64>,
+}
+
+impl QuotaEntry {
+    /// Whether the user uses more than the warning threshold
+    pub fn is_over_threshold(&self) -> bool {
+        self.quota_threshold.is_some_and(|threshold| self.quota_used > threshold)
+    }
+
+    /// Whether the user uses more than the quota limit
+    pub fn is_over_limit(&self) -> bool {
+        self.quota_limit.is_some_and(|limit| self.quota_used > limit)
+    }
+}
+
+/// Creates a quota control object for the volume at `lprootpathname`
+fn open_quota_control(lprootpathname: &str) -> Result<IDiskQuotaControl, WinPartitionError> {
+    let control: IDiskQuotaControl = unsafe { CoCreateInstance(&CLSID_DiskQuotaControl, None, CLSCTX_INPROC_SERVER) }
+        .map_err(com_error("CoCreateInstance", Some(lprootpathname)))?;
+    unsafe { control.Initialize(lprootpathname, false) }
+        .map_err(com_error("IDiskQuotaControl::Initialize", Some(lprootpathname)))?;
+    Ok(control)
+}
+
+/// Converts a quota value where -1 means "none" to an `Option`
+fn quota_value(value: i64) -> Option<u64> {
+    if value < 0 {
+        None
+    } else {
+        Some(value as u64)
+    }
+}
+
+/// Calls [IDiskQuotaControl::GetQuotaState](https://docs.microsoft.com/en-us/windows/win32/api/dskquota/nf-dskquota-idiskquotacontrol-getquotastate)
+/// and returns whether quotas are tracked or enforced on the volume at `lprootpathname`, such as `C:\`
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_quota_state(lprootpathname: String) -> Result<QuotaState, WinPartitionError> {
+    let _com = ComGuard::new()?;
+    let control = open_quota_control(&lprootpathname)?;
+    let mut state: u32 = 0;
+    unsafe { control.GetQuotaState(&mut state) }
+        .map_err(com_error("IDiskQuotaControl::GetQuotaState", Some(&lprootpathname)))?;
+
+    Ok(match state & DISKQUOTA_STATE_MASK {
+        DISKQUOTA_STATE_TRACK => QuotaState::Tracked,
+        DISKQUOTA_STATE_ENFORCE => QuotaState::Enforced,
+        _ => QuotaState::Disabled,
+    })
+}
+
+/// Lists the disk quota entries of the volume at `lprootpathname`, such as `C:\`, using
+/// [IDiskQuotaControl::CreateEnumUsers](https://docs.microsoft.com/en-us/windows/win32/api/dskquota/nf-dskquota-idiskquotacontrol-createenumusers).
+/// Account names are resolved synchronously, which can be slow for domain accounts.
+/// The volume must support quotas, see [FileSystemFlags::supports_quotas](crate::win_api::FileSystemFlags::supports_quotas)
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_quota_entries(lprootpathname: String) -> Result<Vec<QuotaEntry>, WinPartitionError> {
+    let _com = ComGuard::new()?;
+    let control = open_quota_control(&lprootpathname)?;
+    let mut users: Option<IEnumDiskQuotaUsers> = None;
+    unsafe { control.CreateEnumUsers(null_mut(), 0, DISKQUOTA_USERNAME_RESOLVE_SYNC, &mut users) }
+        .map_err(com_error("IDiskQuotaControl::CreateEnumUsers", Some(&lprootpathname)))?;
+    let users = match users {
+        Some(users) => users,
+        None => return Ok(vec![]),
+    };
+
+    let mut result: Vec<QuotaEntry> = vec![];
+    loop {
+        let mut user: Option<IDiskQuotaUser> = None;
+        let mut fetched: u32 = 0;
+        unsafe { users.Next(1, &mut user, &mut fetched) }
+            .map_err(com_error("IEnumDiskQuotaUsers::Next", Some(&lprootpathname)))?;
+        match user {
+            Some(user) if fetched == 1 => result.push(read_quota_user(&user, &lprootpathname)?),
+            _ => break,
+        }
+    }
+
+    Ok(result)
+}
+
+fn read_quota_user(user: &IDiskQuotaUser, lprootpathname: &str) -> Result<QuotaEntry, WinPartitionError> {
+    let error = com_error("IDiskQuotaUser", Some(lprootpathname));
+    let mut container_buf: Vec<u16> = vec![0; 261];
+    let mut logon_name_buf: Vec<u16> = vec![0; 261];
+    let mut display_name_buf: Vec<u16> = vec![0; 261];
+    let mut used: i64 = 0;
+    let mut threshold: i64 = 0;
+    let mut limit: i64 = 0;
+    unsafe {
+        // Accounts that can't be resolved, e.g. deleted users, have no name
+        let _ = user.GetName(
+            PWSTR(container_buf.as_mut_ptr()),
+            container_buf.len() as u32,
+            PWSTR(logon_name_buf.as_mut_ptr()),
+            logon_name_buf.len() as u32,
+            PWSTR(display_name_buf.as_mut_ptr()),
+            display_name_buf.len() as u32);
+        user.GetQuotaUsed(&mut used).map_err(&error)?;
+        user.GetQuotaThreshold(&mut threshold).map_err(&error)?;
+        user.GetQuotaLimit(&mut limit).map_err(&error)?;
+    }
+
+    Ok(QuotaEntry {
+        logon_name: vec_u16_to_string(&logon_name_buf),
+        display_name: vec_u16_to_string(&display_name_buf),
+        quota_used: used.max(0) as u64,
+        quota_threshold: quota_value(threshold),
+        quota_limit: quota_value(limit),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quota_entry_test() {
+        let entry = QuotaEntry {
+            logon_name: "DOMAIN\\user".to_string(),
+            display_name: "User".to_string(),
+            quota_used: 150,
+            quota_threshold: quota_value(100),
+            quota_limit: quota_value(-1),
+        };
+        assert!(entry.is_over_threshold());
+        assert!(!entry.is_over_limit());
+        assert_eq!(entry.quota_limit, None);
+    }
+}
@@ -0,0 +1,394 @@
+use std::convert::TryFrom;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::GET_LENGTH_INFORMATION,
+    Windows::Win32::System::SystemServices::IOCTL_DISK_GET_LENGTH_INFO,
+};
+use crate::error::WinPartitionError;
+use crate::physical_disk::{physical_disk_path, query_disk_geometry};
+use crate::win_api::{open_unbuffered, volume_device_path, AlignedBuffer, DeviceHandle};
+#[cfg(feature = "dangerous")]
+use crate::{
+    bindings::Windows::Win32::Storage::FileSystem::FILE_GENERIC_WRITE,
+    physical_disk::{get_disk_attributes, set_disk_offline},
+    volume_lock::VolumeLock,
+    win_api::open_device,
+};
+
+/// Win32 error code for "The drive cannot find the sector requested"
+const ERROR_SECTOR_NOT_FOUND: i32 = 27;
+/// Win32 error code for "The system cannot write to the specified device"
+#[cfg(feature = "dangerous")]
+const ERROR_WRITE_FAULT: i32 = 29;
+/// Win32 error code for "Reached the end of the file"
+const ERROR_HANDLE_EOF: i32 = 38;
+/// Win32 error code for "The parameter is incorrect"
+const ERROR_INVALID_PARAMETER: i32 = 87;
+/// Largest single read or write, disk drivers may split or reject larger transfers
+const MAX_TRANSFER_SIZE: usize = 1024 * 1024;
+/// Smallest alignment of transfer buffers, enough for 512 and 4096 byte sectors
+const MIN_BUFFER_ALIGNMENT: usize = 4096;
+
+/// Physical disk or volume opened for reading raw sectors, bypassing the file system and the cache.
+///
+/// Reads may start at any byte offset and have any length, they are extended to whole sectors internally.
+/// Opening a device requires administrator rights. Reading a mounted volume returns what is on the disk,
+/// which may be older than data the file system still caches.
+///
+/// ```no_run
+/// use win_partitions::raw_device::RawDevice;
+///
+/// let disk = RawDevice::open_physical_disk(0).unwrap();
+/// let mbr = disk.read_sectors(0, 1).unwrap();
+/// println!("Boot signature: {:02X} {:02X}", mbr[510], mbr[511]);
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub struct RawDevice {
+    device: DeviceHandle,
+    sector_size: u32,
+    size: u64,
+}
+
+impl RawDevice {
+    /// Opens the physical disk `number`, as in `\\.\PhysicalDriveN`
+    pub fn open_physical_disk(number: u32) -> Result<RawDevice, WinPartitionError> {
+        RawDevice::open(physical_disk_path(number))
+    }
+
+    /// Opens the volume at `lprootpathname`, a root path such as `C:\` or a volume GUID path
+    pub fn open_volume(lprootpathname: String) -> Result<RawDevice, WinPartitionError> {
+        RawDevice::open(volume_device_path(&lprootpathname))
+    }
+
+    /// Opens a device path such as `\\.\PhysicalDrive0`, `\\.\C:` or `\\?\Volume{...}`, without trailing backslash
+    pub fn open(path: String) -> Result<RawDevice, WinPartitionError> {
+        let device = open_unbuffered(&path, FILE_GENERIC_READ)?;
+        let (sector_size, size) = query_extent(&device)?;
+
+        Ok(RawDevice { device, sector_size, size })
+    }
+
+    /// Device path the device was opened with
+    pub fn path(&self) -> &str {
+        self.device.path()
+    }
+
+    /// Logical sector size in bytes, the unit of [read_sectors](RawDevice::read_sectors)
+    pub fn sector_size(&self) -> u32 {
+        self.sector_size
+    }
+
+    /// Size of the device in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Number of whole sectors of the device
+    pub fn sector_count(&self) -> u64 {
+        self.size / self.sector_size as u64
+    }
+
+    /// Reads `count` sectors starting at sector `first_sector`. Fails with `ERROR_SECTOR_NOT_FOUND` if the range
+    /// goes past the end of the device
+    pub fn read_sectors(&self, first_sector: u64, count: u32) -> Result<Vec<u8>, WinPartitionError> {
+        let invalid = || WinPartitionError::from_code("ReadFile", Some(self.path()), ERROR_INVALID_PARAMETER);
+        let offset = first_sector.checked_mul(self.sector_size as u64).ok_or_else(invalid)?;
+        let len = usize::try_from(count as u64 * self.sector_size as u64).map_err(|_| invalid())?;
+
+        let mut buffer = vec![0; len];
+        self.read_at(offset, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Fills `buffer` with the bytes at byte `offset`. Fails with `ERROR_SECTOR_NOT_FOUND` if the range goes
+    /// past the end of the device
+    pub fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), WinPartitionError> {
+        check_range("ReadFile", &self.device, self.size, offset, buffer.len())?;
+        read_range(&self.device, self.sector_size, offset, buffer)
+    }
+}
+
+/// Logical sector size and size in bytes of an opened disk or volume
+fn query_extent(device: &DeviceHandle) -> Result<(u32, u64), WinPartitionError> {
+    let sector_size = query_disk_geometry(device)?.bytes_per_sector;
+    let length: GET_LENGTH_INFORMATION = device.query("IOCTL_DISK_GET_LENGTH_INFO", IOCTL_DISK_GET_LENGTH_INFO)?;
+    Ok((sector_size, length.Length.max(0) as u64))
+}
+
+/// Fails with `ERROR_SECTOR_NOT_FOUND` if `len` bytes at `offset` don't fit in a device of `size` bytes
+fn check_range(
+    api: &'static str,
+    device: &DeviceHandle,
+    size: u64,
+    offset: u64,
+    len: usize,
+) -> Result<(), WinPartitionError> {
+    match offset.checked_add(len as u64) {
+        Some(end) if end <= size => Ok(()),
+        _ => Err(WinPartitionError::from_code(api, Some(device.path()), ERROR_SECTOR_NOT_FOUND)),
+    }
+}
+
+/// Reads any byte range through whole-sector transfers
+fn read_range(
+    device: &DeviceHandle,
+    sector_size: u32,
+    offset: u64,
+    buffer: &mut [u8],
+) -> Result<(), WinPartitionError> {
+    for transfer in transfers(offset, buffer.len(), sector_size) {
+        let sectors = read_transfer(device, sector_size, &transfer)?;
+        buffer[transfer.buffer_range()].copy_from_slice(&sectors.as_slice()[transfer.sector_range()]);
+    }
+    Ok(())
+}
+
+/// Reads the whole sectors of a transfer into an aligned buffer
+fn read_transfer(
+    device: &DeviceHandle,
+    sector_size: u32,
+    transfer: &Transfer,
+) -> Result<AlignedBuffer, WinPartitionError> {
+    let mut sectors = AlignedBuffer::new(transfer.len, buffer_alignment(sector_size));
+    let read = device.read_at(transfer.device_offset, sectors.as_mut_slice())?;
+    if (read as usize) < transfer.len {
+        return Err(WinPartitionError::from_code("ReadFile", Some(device.path()), ERROR_HANDLE_EOF));
+    }
+    Ok(sectors)
+}
+
+/// Offline physical disk opened for writing, taken offline by [OfflineDisk::take] so no volume of the disk is
+/// mounted while its sectors are rewritten. The disk is brought back online when dropped, unless it was
+/// already offline.
+///
+/// Only available with the `dangerous` feature.
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+#[cfg(feature = "dangerous")]
+pub struct OfflineDisk {
+    device: DeviceHandle,
+    number: u32,
+    was_offline: bool,
+}
+
+#[cfg(feature = "dangerous")]
+impl OfflineDisk {
+    /// Takes the physical disk `number`, as in `\\.\PhysicalDriveN`, offline until the next restart like
+    /// [set_disk_offline] without `persist`, and opens it for writing. Fails for the disk holding the system
+    /// or boot volume. Requires administrator rights
+    pub fn take(number: u32) -> Result<OfflineDisk, WinPartitionError> {
+        let was_offline = get_disk_attributes(number)?.offline;
+        if !was_offline {
+            set_disk_offline(number, true, false)?;
+        }
+        let device = match open_device(&physical_disk_path(number), FILE_GENERIC_READ | FILE_GENERIC_WRITE) {
+            Ok(device) => device,
+            Err(err) => {
+                if !was_offline {
+                    let _ = set_disk_offline(number, false, false);
+                }
+                return Err(err);
+            }
+        };
+
+        Ok(OfflineDisk { device, number, was_offline })
+    }
+
+    /// Disk number, as in `\\.\PhysicalDriveN`
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+}
+
+#[cfg(feature = "dangerous")]
+impl Drop for OfflineDisk {
+    fn drop(&mut self) {
+        if !self.was_offline {
+            let _ = set_disk_offline(self.number, false, false);
+        }
+    }
+}
+
+/// Raw sector writer for a [VolumeLock] or an [OfflineDisk]. Borrowing the lock or the offline disk guarantees
+/// nothing else has the volume mounted while the writer lives.
+///
+/// Writes may start at any byte offset and have any length, partial sectors are read and merged first.
+/// Wrong writes destroy file systems and partition tables, so this is only available with the `dangerous`
+/// feature.
+///
+/// ```no_run
+/// use win_partitions::raw_device::RawWriter;
+/// use win_partitions::volume_lock::VolumeLock;
+///
+/// let lock = VolumeLock::lock("E:\\".to_string()).unwrap();
+/// let writer = RawWriter::volume(&lock).unwrap();
+/// writer.write_sectors(0, &vec![0; writer.sector_size() as usize]).unwrap();
+/// ```
+///
+/// Minimum OS: Windows XP/Windows Server 2003, Windows Vista/Windows Server 2008 for [OfflineDisk]
+#[cfg(feature = "dangerous")]
+pub struct RawWriter<'a> {
+    device: &'a DeviceHandle,
+    sector_size: u32,
+    size: u64,
+}
+
+#[cfg(feature = "dangerous")]
+impl<'a> RawWriter<'a> {
+    /// Writer for a locked volume. The volume is dismounted with [VolumeLock::dismount] first, so the file system
+    /// doesn't overwrite the changes from its cache when the lock is released
+    pub fn volume(lock: &'a VolumeLock) -> Result<RawWriter<'a>, WinPartitionError> {
+        lock.dismount()?;
+        RawWriter::new(lock.device())
+    }
+
+    /// Writer for an offline physical disk
+    pub fn disk(disk: &'a OfflineDisk) -> Result<RawWriter<'a>, WinPartitionError> {
+        RawWriter::new(&disk.device)
+    }
+
+    fn new(device: &'a DeviceHandle) -> Result<RawWriter<'a>, WinPartitionError> {
+        let (sector_size, size) = query_extent(device)?;
+        Ok(RawWriter { device, sector_size, size })
+    }
+
+    /// Logical sector size in bytes, the unit of [write_sectors](RawWriter::write_sectors)
+    pub fn sector_size(&self) -> u32 {
+        self.sector_size
+    }
+
+    /// Size of the device in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Writes `data`, a whole number of sectors, starting at sector `first_sector`. Fails with
+    /// `ERROR_INVALID_PARAMETER` if `data` isn't a multiple of the sector size and with `ERROR_SECTOR_NOT_FOUND`
+    /// if it goes past the end of the device
+    pub fn write_sectors(&self, first_sector: u64, data: &[u8]) -> Result<(), WinPartitionError> {
+        let offset = first_sector.checked_mul(self.sector_size as u64);
+        match offset {
+            Some(offset) if data.len().is_multiple_of(self.sector_size as usize) => self.write_at(offset, data),
+            _ => Err(WinPartitionError::from_code("WriteFile", Some(self.device.path()), ERROR_INVALID_PARAMETER)),
+        }
+    }
+
+    /// Writes `data` at byte `offset`. Fails with `ERROR_SECTOR_NOT_FOUND` if it goes past the end of the device
+    pub fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), WinPartitionError> {
+        check_range("WriteFile", self.device, self.size, offset, data.len())?;
+
+        for transfer in transfers(offset, data.len(), self.sector_size) {
+            let mut sectors = if transfer.skip != 0 || transfer.buffer_len != transfer.len {
+                read_transfer(self.device, self.sector_size, &transfer)?
+            } else {
+                AlignedBuffer::new(transfer.len, buffer_alignment(self.sector_size))
+            };
+            sectors.as_mut_slice()[transfer.sector_range()].copy_from_slice(&data[transfer.buffer_range()]);
+
+            let written = self.device.write_at(transfer.device_offset, sectors.as_slice())?;
+            if (written as usize) < transfer.len {
+                return Err(WinPartitionError::from_code("WriteFile", Some(self.device.path()), ERROR_WRITE_FAULT));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads back the bytes at byte `offset` like [RawDevice::read_at], through the locked handle
+    pub fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), WinPartitionError> {
+        check_range("ReadFile", self.device, self.size, offset, buffer.len())?;
+        read_range(self.device, self.sector_size, offset, buffer)
+    }
+}
+
+/// Alignment of transfer buffers for a sector size
+fn buffer_alignment(sector_size: u32) -> usize {
+    (sector_size as usize).max(MIN_BUFFER_ALIGNMENT).next_power_of_two()
+}
+
+/// Sector-aligned device transfer covering part of a byte range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transfer {
+    /// Sector-aligned offset of the transfer on the device
+    device_offset: u64,
+    /// Length of the transfer, a whole number of sectors
+    len: usize,
+    /// Offset of the requested bytes within the transfer, non-zero only for the first one
+    skip: usize,
+    /// Offset of the requested bytes within the caller's buffer
+    buffer_offset: usize,
+    /// Number of requested bytes in the transfer
+    buffer_len: usize,
+}
+
+impl Transfer {
+    fn buffer_range(&self) -> std::ops::Range<usize> {
+        self.buffer_offset..self.buffer_offset + self.buffer_len
+    }
+
+    fn sector_range(&self) -> std::ops::Range<usize> {
+        self.skip..self.skip + self.buffer_len
+    }
+}
+
+/// Splits `len` bytes at byte `offset` into sector-aligned transfers of at most [MAX_TRANSFER_SIZE] bytes
+fn transfers(offset: u64, len: usize, sector_size: u32) -> Vec<Transfer> {
+    let sector_size = sector_size as u64;
+    let max_len = (MAX_TRANSFER_SIZE as u64 / sector_size).max(1) * sector_size;
+    let end = offset + len as u64;
+
+    let mut transfers = vec![];
+    let mut device_offset = offset / sector_size * sector_size;
+    while device_offset < end {
+        let transfer_end = (device_offset + max_len).min(end.div_ceil(sector_size) * sector_size);
+        let start = offset.max(device_offset);
+        transfers.push(Transfer {
+            device_offset,
+            len: (transfer_end - device_offset) as usize,
+            skip: (start - device_offset) as usize,
+            buffer_offset: (start - offset) as usize,
+            buffer_len: (end.min(transfer_end) - start) as usize,
+        });
+        device_offset = transfer_end;
+    }
+    transfers
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transfers_test() {
+        // Unaligned start and end are extended to whole sectors
+        assert_eq!(transfers(700, 100, 512), vec![
+            Transfer { device_offset: 512, len: 512, skip: 188, buffer_offset: 0, buffer_len: 100 },
+        ]);
+        assert_eq!(transfers(1000, 100, 512), vec![
+            Transfer { device_offset: 512, len: 1024, skip: 488, buffer_offset: 0, buffer_len: 100 },
+        ]);
+
+        // Large ranges are split at the maximum transfer size
+        let split = transfers(100, MAX_TRANSFER_SIZE, 4096);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0], Transfer {
+            device_offset: 0,
+            len: MAX_TRANSFER_SIZE,
+            skip: 100,
+            buffer_offset: 0,
+            buffer_len: MAX_TRANSFER_SIZE - 100,
+        });
+        assert_eq!(split[1], Transfer {
+            device_offset: MAX_TRANSFER_SIZE as u64,
+            len: 4096,
+            skip: 0,
+            buffer_offset: MAX_TRANSFER_SIZE - 100,
+            buffer_len: 100,
+        });
+
+        assert!(transfers(512, 0, 512).is_empty());
+        assert_eq!(buffer_alignment(512), 4096);
+        assert_eq!(buffer_alignment(8192), 8192);
+    }
+}
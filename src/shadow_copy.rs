@@ -0,0 +1,173 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::bindings::{
+    Windows::Win32::Storage::Vss::{
+        IVssEnumObject, IVssSnapshotMgmt, VssSnapshotMgmt, VSS_OBJECT_PROP, VSS_OBJECT_SNAPSHOT, VSS_SNAPSHOT_PROP,
+    },
+    Windows::Win32::System::Com::{CoCreateInstance, CoTaskMemFree, CLSCTX_ALL},
+};
+use crate::com::{com_error, ComGuard};
+use crate::error::WinPartitionError;
+use crate::guid::Guid;
+use crate::win_api::{enumerate_volumes, get_volume_name_for_mount_point};
+
+/// ID of the Microsoft software shadow copy provider, which creates the shadow copies of System Restore,
+/// Windows Backup and `vssadmin`
+const VSS_SOFTWARE_PROVIDER_ID: windows::Guid =
+    windows::Guid::from_values(0xb5946137, 0x7b9f, 0x4925, [0xaf, 0x80, 0x51, 0xab, 0xd6, 0x0b, 0x20, 0xd5]);
+/// HRESULT returned when no shadow copy of the volume exists
+const VSS_E_OBJECT_NOT_FOUND: u32 = 0x80042308;
+/// HRESULT returned for volumes that can't have shadow copies, e.g. FAT volumes
+const VSS_E_VOLUME_NOT_SUPPORTED: u32 = 0x8004230C;
+/// Number of 100 ns intervals between 1601-01-01, the FILETIME epoch, and 1970-01-01
+const FILETIME_UNIX_EPOCH: i64 = 116_444_736_000_000_000;
+
+/// Existing Volume Shadow Copy of a volume, see [VSS_SNAPSHOT_PROP](https://docs.microsoft.com/en-us/windows/win32/api/vss/ns-vss-vss_snapshot_prop)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShadowCopy {
+    /// ID of the shadow copy, as shown by `vssadmin list shadows`
+    pub id: Guid,
+    /// ID of the set of shadow copies created together with this one
+    pub snapshot_set_id: Guid,
+    /// Time the shadow copy was created
+    pub creation_time: SystemTime,
+    /// Volume GUID path of the volume the shadow copy was taken of,
+    /// e.g. `\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\`
+    pub original_volume: String,
+    /// Device object to read the shadow copy from, e.g. `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy1`
+    pub device_object: String,
+    /// Name of the machine the shadow copy was created on
+    pub originating_machine: String,
+}
+
+/// Converts a FILETIME timestamp to `SystemTime`
+fn filetime_to_system_time(filetime: i64) -> SystemTime {
+    let since_unix_epoch = filetime - FILETIME_UNIX_EPOCH;
+    let duration = Duration::from_nanos(since_unix_epoch.unsigned_abs() * 100);
+    if since_unix_epoch >= 0 {
+        UNIX_EPOCH + duration
+    } else {
+        UNIX_EPOCH - duration
+    }
+}
+
+/// Reads a null terminated UTF-16 string allocated by COM and releases it
+unsafe fn take_co_task_string(value: *mut u16) -> String {
+    if value.is_null() {
+        return String::new();
+    }
+    let length = (0..).take_while(|&index| *value.add(index) != 0).count();
+    let result = String::from_utf16_lossy(std::slice::from_raw_parts(value, length));
+    CoTaskMemFree(value as _);
+    result
+}
+
+/// Converts the properties of a shadow copy, releasing their strings
+unsafe fn take_snapshot_prop(snapshot: &VSS_SNAPSHOT_PROP) -> ShadowCopy {
+    let copy = ShadowCopy {
+        id: Guid::from(snapshot.m_SnapshotId),
+        snapshot_set_id: Guid::from(snapshot.m_SnapshotSetId),
+        creation_time: filetime_to_system_time(snapshot.m_tsCreationTimestamp),
+        original_volume: take_co_task_string(snapshot.m_pwszOriginalVolumeName),
+        device_object: take_co_task_string(snapshot.m_pwszSnapshotDeviceObject),
+        originating_machine: take_co_task_string(snapshot.m_pwszOriginatingMachine),
+    };
+    take_co_task_string(snapshot.m_pwszServiceMachine);
+    take_co_task_string(snapshot.m_pwszExposedName);
+    take_co_task_string(snapshot.m_pwszExposedPath);
+    copy
+}
+
+/// Lists the existing shadow copies of a volume using
+/// [IVssSnapshotMgmt::QuerySnapshotsByVolume](https://docs.microsoft.com/en-us/windows/win32/api/vsmgmt/nf-vsmgmt-ivsssnapshotmgmt-querysnapshotsbyvolume).
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Only shadow copies of the
+/// Microsoft software provider are listed, which are the ones Windows itself creates.
+/// Requires administrator rights.
+///
+/// Minimum OS: Windows Vista/Windows Server 2003
+pub fn get_shadow_copies(lprootpathname: String) -> Result<Vec<ShadowCopy>, WinPartitionError> {
+    let _com = ComGuard::new()?;
+    query_shadow_copies(&lprootpathname)
+}
+
+/// Lists the existing shadow copies of every volume, including volumes without a drive letter.
+/// Volumes that don't support shadow copies or can't be queried, e.g. an empty card reader or a volume the
+/// caller can't access, are skipped. See [get_shadow_copies]
+///
+/// Minimum OS: Windows Vista/Windows Server 2003
+pub fn get_all_shadow_copies() -> Result<Vec<ShadowCopy>, WinPartitionError> {
+    get_all_shadow_copies_lossy().map(|(copies, _errors)| copies)
+}
+
+/// Lists the existing shadow copies of every volume like [get_all_shadow_copies], together with the errors met
+/// querying the skipped volumes. Volumes without media are not reported as errors. Only a failure to list the
+/// volumes themselves is returned as `Err`
+///
+/// Minimum OS: Windows Vista/Windows Server 2003
+pub fn get_all_shadow_copies_lossy() -> Result<(Vec<ShadowCopy>, Vec<WinPartitionError>), WinPartitionError> {
+    let _com = ComGuard::new()?;
+    let mut result: Vec<ShadowCopy> = vec![];
+    let mut errors: Vec<WinPartitionError> = vec![];
+    for volume in enumerate_volumes()? {
+        match query_shadow_copies(&volume) {
+            Ok(copies) => result.extend(copies),
+            Err(WinPartitionError::Os { code, .. }) if code as u32 == VSS_E_VOLUME_NOT_SUPPORTED => {}
+            Err(WinPartitionError::NotReady { .. }) => {}
+            Err(err) => errors.push(err),
+        }
+    }
+
+    Ok((result, errors))
+}
+
+fn query_shadow_copies(lprootpathname: &str) -> Result<Vec<ShadowCopy>, WinPartitionError> {
+    let volume_name = if lprootpathname.starts_with("\\\\?\\") {
+        lprootpathname.to_string()
+    } else {
+        get_volume_name_for_mount_point(lprootpathname.to_string())?
+    };
+    let mut volume_name_buf: Vec<u16> = volume_name.encode_utf16().chain(Some(0)).collect();
+
+    let management: IVssSnapshotMgmt = unsafe { CoCreateInstance(&VssSnapshotMgmt, None, CLSCTX_ALL) }
+        .map_err(com_error("CoCreateInstance", Some(lprootpathname)))?;
+    let snapshots: IVssEnumObject = match unsafe {
+        management.QuerySnapshotsByVolume(volume_name_buf.as_mut_ptr(), VSS_SOFTWARE_PROVIDER_ID)
+    } {
+        Ok(snapshots) => snapshots,
+        Err(err) if err.code().0 == VSS_E_OBJECT_NOT_FOUND => return Ok(vec![]),
+        Err(err) => return Err(com_error("IVssSnapshotMgmt::QuerySnapshotsByVolume", Some(lprootpathname))(err)),
+    };
+
+    let mut result: Vec<ShadowCopy> = vec![];
+    loop {
+        let mut prop: VSS_OBJECT_PROP = unsafe { std::mem::zeroed() };
+        let mut fetched: u32 = 0;
+        unsafe { snapshots.Next(1, &mut prop, &mut fetched) }
+            .map_err(com_error("IVssEnumObject::Next", Some(lprootpathname)))?;
+        if fetched == 0 {
+            break;
+        }
+        if prop.Type == VSS_OBJECT_SNAPSHOT {
+            result.push(unsafe { take_snapshot_prop(&prop.Obj.Snap) });
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filetime_to_system_time_test() {
+        assert_eq!(filetime_to_system_time(FILETIME_UNIX_EPOCH), UNIX_EPOCH);
+        // 2021-01-01T00:00:00Z
+        assert_eq!(
+            filetime_to_system_time(132_539_328_000_000_000),
+            UNIX_EPOCH + Duration::from_secs(1_609_459_200));
+        assert_eq!(filetime_to_system_time(0), UNIX_EPOCH - Duration::from_secs(11_644_473_600));
+    }
+}
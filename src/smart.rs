@@ -0,0 +1,339 @@
+use std::ffi::c_void;
+use std::fmt;
+use std::mem::size_of;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_WRITE,
+    Windows::Win32::Storage::FileSystem::NVMeDataTypeLogPage,
+    Windows::Win32::Storage::FileSystem::PropertyStandardQuery,
+    Windows::Win32::Storage::FileSystem::ProtocolTypeNvme,
+    Windows::Win32::Storage::FileSystem::STORAGE_PROPERTY_ID,
+    Windows::Win32::Storage::FileSystem::STORAGE_PROTOCOL_SPECIFIC_DATA,
+    Windows::Win32::Storage::FileSystem::STORAGE_QUERY_TYPE,
+    Windows::Win32::Storage::FileSystem::StorageDeviceProtocolSpecificProperty,
+    Windows::Win32::Storage::IscsiDisc::ATA_PASS_THROUGH_EX,
+    Windows::Win32::Storage::IscsiDisc::IOCTL_ATA_PASS_THROUGH,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_PREDICT_FAILURE,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_QUERY_PROPERTY,
+};
+use crate::error::WinPartitionError;
+use crate::physical_disk::physical_disk_path;
+use crate::storage::{get_storage_bus_type, StorageBusType};
+use crate::win_api::{open_device, DeviceHandle};
+
+/// ATA `SMART` command
+const ATA_SMART: u8 = 0xB0;
+/// `SMART` feature reading the attribute values
+const SMART_READ_DATA: u8 = 0xD0;
+/// `SMART` feature reading the attribute thresholds
+const SMART_READ_THRESHOLDS: u8 = 0xD1;
+/// `ATA_FLAGS_DRDY_REQUIRED | ATA_FLAGS_DATA_IN`
+const ATA_FLAGS_READ: u16 = 0x01 | 0x02;
+/// Size of a SMART data sector in bytes
+const SMART_SECTOR_SIZE: usize = 512;
+/// Number of attribute entries in a SMART data sector
+const SMART_ATTRIBUTE_COUNT: usize = 30;
+/// Size of an attribute entry in bytes, both in the data and the threshold sector
+const SMART_ATTRIBUTE_SIZE: usize = 12;
+/// SMART attribute holding the drive temperature in the low byte of its raw value
+const SMART_TEMPERATURE: u8 = 194;
+/// SMART attribute holding the airflow temperature, reported instead of 194 by some drives
+const SMART_AIRFLOW_TEMPERATURE: u8 = 190;
+/// NVMe log page identifier of the SMART / health information log
+const NVME_LOG_PAGE_HEALTH_INFO: u32 = 0x02;
+/// Size of the NVMe SMART / health information log in bytes
+const NVME_HEALTH_INFO_LOG_SIZE: usize = 512;
+/// Offset of 0 degrees Celsius in Kelvin, as rounded by the NVMe specification
+const KELVIN_OFFSET: i16 = 273;
+
+/// Output of `IOCTL_STORAGE_PREDICT_FAILURE`, missing from the Windows metadata
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct StoragePredictFailure {
+    predict_failure: u32,
+    _vendor_specific: [u8; 512],
+}
+
+impl Default for StoragePredictFailure {
+    fn default() -> Self {
+        StoragePredictFailure { predict_failure: 0, _vendor_specific: [0; 512] }
+    }
+}
+
+/// `ATA_PASS_THROUGH_EX` followed by its data buffer
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct AtaPassThroughBuffer {
+    header: ATA_PASS_THROUGH_EX,
+    data: [u8; SMART_SECTOR_SIZE],
+}
+
+/// `STORAGE_PROPERTY_QUERY` carrying a `STORAGE_PROTOCOL_SPECIFIC_DATA` request in its additional
+/// parameters, followed by the data buffer. Windows returns a `STORAGE_PROTOCOL_DATA_DESCRIPTOR` in the same layout
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ProtocolDataBuffer {
+    property_id: STORAGE_PROPERTY_ID,
+    query_type: STORAGE_QUERY_TYPE,
+    protocol: STORAGE_PROTOCOL_SPECIFIC_DATA,
+    data: [u8; NVME_HEALTH_INFO_LOG_SIZE],
+}
+
+/// Temperature in degrees Celsius
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Celsius(pub i16);
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} °C", self.0)
+    }
+}
+
+/// SMART attribute of an ATA disk, as listed by `smartctl -A`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmartAttribute {
+    /// Attribute ID, e.g. 5 for the reallocated sector count
+    pub id: u8,
+    /// Status flags, bit 0 marks a pre-failure attribute
+    pub flags: u16,
+    /// Normalized current value, usually counting down from 100 or 200
+    pub current: u8,
+    /// Lowest normalized value seen
+    pub worst: u8,
+    /// Normalized value at or below which the attribute is failing, 0 if it never fails
+    pub threshold: u8,
+    /// Vendor specific raw value, e.g. a count of sectors or hours
+    pub raw: u64,
+}
+
+impl SmartAttribute {
+    /// Whether a normalized value at or below the threshold predicts failure of the disk
+    pub fn is_pre_failure(&self) -> bool {
+        self.flags & 0x1 != 0
+    }
+
+    /// Whether the current value is at or below the threshold
+    pub fn is_failing(&self) -> bool {
+        self.threshold != 0 && self.current <= self.threshold
+    }
+
+    /// Common name of well-known attributes, e.g. `Reallocated_Sector_Ct` as printed by smartctl
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self.id {
+            1 => "Raw_Read_Error_Rate",
+            3 => "Spin_Up_Time",
+            4 => "Start_Stop_Count",
+            5 => "Reallocated_Sector_Ct",
+            7 => "Seek_Error_Rate",
+            9 => "Power_On_Hours",
+            10 => "Spin_Retry_Count",
+            12 => "Power_Cycle_Count",
+            177 => "Wear_Leveling_Count",
+            184 => "End-to-End_Error",
+            187 => "Reported_Uncorrect",
+            188 => "Command_Timeout",
+            190 => "Airflow_Temperature_Cel",
+            194 => "Temperature_Celsius",
+            196 => "Reallocated_Event_Count",
+            197 => "Current_Pending_Sector",
+            198 => "Offline_Uncorrectable",
+            199 => "UDMA_CRC_Error_Count",
+            231 => "SSD_Life_Left",
+            241 => "Total_LBAs_Written",
+            242 => "Total_LBAs_Read",
+            _ => return None,
+        })
+    }
+}
+
+/// Reads the SMART attributes of the physical disk `number`, as in `\\.\PhysicalDriveN`, by sending
+/// the `SMART READ DATA` and `SMART READ THRESHOLDS` commands with
+/// [IOCTL_ATA_PASS_THROUGH](https://docs.microsoft.com/en-us/windows-hardware/drivers/ddi/ntddscsi/ni-ntddscsi-ioctl_ata_pass_through).
+///
+/// Only ATA disks answer, such as SATA hard disks and SSDs. NVMe disks, most USB enclosures and
+/// RAID controllers fail with `ERROR_INVALID_PARAMETER` or `ERROR_NOT_SUPPORTED`. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_smart_attributes(number: u32) -> Result<Vec<SmartAttribute>, WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let data = smart_command(&device, SMART_READ_DATA)?;
+    let thresholds = smart_command(&device, SMART_READ_THRESHOLDS)?;
+    Ok(parse_smart_attributes(&data, &thresholds))
+}
+
+/// Sends a `SMART` command reading one sector
+fn smart_command(device: &DeviceHandle, feature: u8) -> Result<[u8; SMART_SECTOR_SIZE], WinPartitionError> {
+    let mut buffer = AtaPassThroughBuffer {
+        header: ATA_PASS_THROUGH_EX {
+            Length: size_of::<ATA_PASS_THROUGH_EX>() as u16,
+            AtaFlags: ATA_FLAGS_READ,
+            DataTransferLength: SMART_SECTOR_SIZE as u32,
+            TimeOutValue: 10,
+            DataBufferOffset: size_of::<ATA_PASS_THROUGH_EX>(),
+            // Features, sector count, LBA low, the SMART signature in LBA mid and high, device, command
+            CurrentTaskFile: [feature, 1, 1, 0x4F, 0xC2, 0xA0, ATA_SMART, 0],
+            ..Default::default()
+        },
+        data: [0; SMART_SECTOR_SIZE],
+    };
+    unsafe {
+        device.io_control(
+            "IOCTL_ATA_PASS_THROUGH",
+            IOCTL_ATA_PASS_THROUGH,
+            &mut buffer as *mut AtaPassThroughBuffer as *mut c_void,
+            size_of::<AtaPassThroughBuffer>() as u32,
+            &mut buffer as *mut AtaPassThroughBuffer as *mut c_void,
+            size_of::<AtaPassThroughBuffer>() as u32)?;
+    }
+    Ok(buffer.data)
+}
+
+/// Parses the attribute table of a SMART data sector, taking thresholds from the matching entries
+/// of the threshold sector. Both tables start at offset 2 with 12 bytes per entry, unused entries have ID 0
+fn parse_smart_attributes(data: &[u8], thresholds: &[u8]) -> Vec<SmartAttribute> {
+    attribute_entries(data)
+        .filter(|entry| entry[0] != 0)
+        .map(|entry| {
+            let mut raw = [0u8; 8];
+            raw[..6].copy_from_slice(&entry[5..11]);
+            let threshold = attribute_entries(thresholds)
+                .find(|threshold| threshold[0] == entry[0])
+                .map_or(0, |threshold| threshold[1]);
+            SmartAttribute {
+                id: entry[0],
+                flags: u16::from_le_bytes([entry[1], entry[2]]),
+                current: entry[3],
+                worst: entry[4],
+                threshold,
+                raw: u64::from_le_bytes(raw),
+            }
+        })
+        .collect()
+}
+
+fn attribute_entries(sector: &[u8]) -> impl Iterator<Item = &[u8]> {
+    sector[2..2 + SMART_ATTRIBUTE_COUNT * SMART_ATTRIBUTE_SIZE].chunks_exact(SMART_ATTRIBUTE_SIZE)
+}
+
+/// Calls [IOCTL_STORAGE_PREDICT_FAILURE](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_predict_failure)
+/// and checks whether the physical disk `number`, as in `\\.\PhysicalDriveN`, predicts its own failure.
+/// For ATA disks this is the SMART overall health status, set once an attribute crosses its threshold.
+///
+/// Disks without failure prediction, such as most USB enclosures and virtual disks, fail with
+/// `ERROR_INVALID_FUNCTION` or `ERROR_NOT_SUPPORTED`. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn predicts_failure(number: u32) -> Result<bool, WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ)?;
+    let prediction: StoragePredictFailure = device.query(
+        "IOCTL_STORAGE_PREDICT_FAILURE",
+        IOCTL_STORAGE_PREDICT_FAILURE)?;
+
+    Ok(prediction.predict_failure != 0)
+}
+
+/// Reads the temperature of the physical disk `number`, as in `\\.\PhysicalDriveN`, whatever bus it's attached to.
+/// NVMe disks report the composite temperature of their SMART / health information log, read with
+/// [IOCTL_STORAGE_QUERY_PROPERTY](https://docs.microsoft.com/en-us/windows/win32/fileio/working-with-nvme-devices).
+/// Other disks are read like [get_smart_attributes], taking SMART attribute 194 or 190.
+///
+/// Returns `None` if the disk doesn't report a temperature. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003, Windows 10 for NVMe disks
+pub fn get_temperature(number: u32) -> Result<Option<Celsius>, WinPartitionError> {
+    if get_storage_bus_type(physical_disk_path(number))? == StorageBusType::Nvme {
+        let log = read_nvme_health_log(number)?;
+        return Ok(nvme_temperature(&log));
+    }
+    Ok(smart_temperature(&get_smart_attributes(number)?))
+}
+
+/// Reads the SMART / health information log page of an NVMe disk
+fn read_nvme_health_log(number: u32) -> Result<[u8; NVME_HEALTH_INFO_LOG_SIZE], WinPartitionError> {
+    let device = open_device(&physical_disk_path(number), FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+    let mut buffer = ProtocolDataBuffer {
+        property_id: StorageDeviceProtocolSpecificProperty,
+        query_type: PropertyStandardQuery,
+        protocol: STORAGE_PROTOCOL_SPECIFIC_DATA {
+            ProtocolType: ProtocolTypeNvme,
+            DataType: NVMeDataTypeLogPage.0 as u32,
+            ProtocolDataRequestValue: NVME_LOG_PAGE_HEALTH_INFO,
+            // The data follows the request, offsets are relative to its start
+            ProtocolDataOffset: size_of::<STORAGE_PROTOCOL_SPECIFIC_DATA>() as u32,
+            ProtocolDataLength: NVME_HEALTH_INFO_LOG_SIZE as u32,
+            ..Default::default()
+        },
+        data: [0; NVME_HEALTH_INFO_LOG_SIZE],
+    };
+    unsafe {
+        device.io_control(
+            "IOCTL_STORAGE_QUERY_PROPERTY",
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            &mut buffer as *mut ProtocolDataBuffer as *mut c_void,
+            size_of::<ProtocolDataBuffer>() as u32,
+            &mut buffer as *mut ProtocolDataBuffer as *mut c_void,
+            size_of::<ProtocolDataBuffer>() as u32)?;
+    }
+    Ok(buffer.data)
+}
+
+/// Composite temperature from bytes 1-2 of the NVMe health log, in Kelvin. 0 means not reported
+fn nvme_temperature(log: &[u8]) -> Option<Celsius> {
+    match u16::from_le_bytes([log[1], log[2]]) {
+        0 => None,
+        kelvin => Some(Celsius(kelvin as i16 - KELVIN_OFFSET)),
+    }
+}
+
+/// Temperature from the low byte of the raw value of SMART attribute 194, or else 190
+fn smart_temperature(attributes: &[SmartAttribute]) -> Option<Celsius> {
+    [SMART_TEMPERATURE, SMART_AIRFLOW_TEMPERATURE].iter()
+        .find_map(|id| attributes.iter().find(|attribute| attribute.id == *id))
+        .map(|attribute| Celsius((attribute.raw & 0xFF) as i16))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_smart_attributes_test() {
+        let mut data = [0u8; SMART_SECTOR_SIZE];
+        let mut thresholds = [0u8; SMART_SECTOR_SIZE];
+        // Reallocated sector count: pre-failure, current 100, worst 100, 8 sectors
+        data[2..14].copy_from_slice(&[5, 0x33, 0x00, 100, 100, 8, 0, 0, 0, 0, 0, 0]);
+        // Power on hours: current 95, worst 95, 43210 hours
+        data[14..26].copy_from_slice(&[9, 0x32, 0x00, 95, 95, 0xCA, 0xA8, 0, 0, 0, 0, 0]);
+        thresholds[2..4].copy_from_slice(&[5, 10]);
+
+        let attributes = parse_smart_attributes(&data, &thresholds);
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes[0].name(), Some("Reallocated_Sector_Ct"));
+        assert_eq!(attributes[0].threshold, 10);
+        assert_eq!(attributes[0].raw, 8);
+        assert!(attributes[0].is_pre_failure() && !attributes[0].is_failing());
+        assert_eq!(attributes[1].raw, 43210);
+        assert_eq!(attributes[1].threshold, 0);
+        assert!(!attributes[1].is_pre_failure());
+    }
+
+    #[test]
+    fn temperature_test() {
+        let mut log = [0u8; NVME_HEALTH_INFO_LOG_SIZE];
+        assert_eq!(nvme_temperature(&log), None);
+        // 311 K
+        log[1..3].copy_from_slice(&[0x37, 0x01]);
+        assert_eq!(nvme_temperature(&log), Some(Celsius(38)));
+
+        let attribute = |id, raw| SmartAttribute { id, flags: 0x22, current: 64, worst: 50, threshold: 0, raw };
+        // Temperature 36, minimum 20 and maximum 51 packed in the higher bytes
+        let attributes = [attribute(190, 35), attribute(194, 0x33_0014_0024)];
+        assert_eq!(smart_temperature(&attributes), Some(Celsius(36)));
+        assert_eq!(smart_temperature(&attributes[..1]), Some(Celsius(35)));
+        assert_eq!(smart_temperature(&[]), None);
+        assert_eq!(Celsius(36).to_string(), "36 °C");
+    }
+}
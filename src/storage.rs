@@ -0,0 +1,497 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::DEVICE_SEEK_PENALTY_DESCRIPTOR,
+    Windows::Win32::Storage::FileSystem::DEVICE_TRIM_DESCRIPTOR,
+    Windows::Win32::Storage::FileSystem::FILE_ACCESS_FLAGS,
+    Windows::Win32::Storage::FileSystem::PropertyStandardQuery,
+    Windows::Win32::Storage::FileSystem::STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR,
+    Windows::Win32::Storage::FileSystem::STORAGE_DEVICE_DESCRIPTOR,
+    Windows::Win32::Storage::FileSystem::STORAGE_PROPERTY_ID,
+    Windows::Win32::Storage::FileSystem::STORAGE_PROPERTY_QUERY,
+    Windows::Win32::Storage::FileSystem::StorageAccessAlignmentProperty,
+    Windows::Win32::Storage::FileSystem::StorageDeviceProperty,
+    Windows::Win32::Storage::FileSystem::StorageDeviceSeekPenaltyProperty,
+    Windows::Win32::Storage::FileSystem::StorageDeviceTrimProperty,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_EJECT_MEDIA,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_GET_HOTPLUG_INFO,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_MEDIA_REMOVAL,
+    Windows::Win32::System::SystemServices::IOCTL_STORAGE_QUERY_PROPERTY,
+    Windows::Win32::System::SystemServices::PREVENT_MEDIA_REMOVAL,
+    Windows::Win32::System::SystemServices::STORAGE_HOTPLUG_INFO,
+};
+use crate::error::WinPartitionError;
+use crate::volume_lock::VolumeLock;
+use crate::win_api::{get_drive_type, open_device, query_dos_device, volume_device_path, DeviceHandle, DriveType};
+
+/// Bus a storage device is attached to according to [STORAGE_BUS_TYPE](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ne-winioctl-storage_bus_type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StorageBusType {
+    /// The bus type cannot be determined
+    Unknown,
+    /// SCSI bus
+    Scsi,
+    /// ATAPI bus, e.g. an optical drive
+    Atapi,
+    /// Parallel ATA bus
+    Ata,
+    /// IEEE 1394 (FireWire) bus
+    Ieee1394,
+    /// Serial Storage Architecture bus
+    Ssa,
+    /// Fibre Channel bus
+    Fibre,
+    /// USB bus
+    Usb,
+    /// Hardware RAID controller
+    Raid,
+    /// iSCSI network storage
+    Iscsi,
+    /// Serial Attached SCSI bus
+    Sas,
+    /// Serial ATA bus
+    Sata,
+    /// Secure Digital card
+    Sd,
+    /// MultiMedia card
+    Mmc,
+    /// Virtual bus, e.g. a Hyper-V virtual disk
+    Virtual,
+    /// File backed virtual disk, e.g. a mounted VHD or ISO
+    FileBackedVirtual,
+    /// Storage Spaces virtual disk
+    Spaces,
+    /// NVM Express bus
+    Nvme,
+    /// Storage class memory
+    Scm,
+    /// Universal Flash Storage
+    Ufs,
+    /// Bus type unknown to this crate, with its raw code
+    Other(u32),
+}
+
+impl From<i32> for StorageBusType {
+    fn from(index: i32) -> Self {
+        match index {
+            0 => StorageBusType::Unknown,
+            1 => StorageBusType::Scsi,
+            2 => StorageBusType::Atapi,
+            3 => StorageBusType::Ata,
+            4 => StorageBusType::Ieee1394,
+            5 => StorageBusType::Ssa,
+            6 => StorageBusType::Fibre,
+            7 => StorageBusType::Usb,
+            8 => StorageBusType::Raid,
+            9 => StorageBusType::Iscsi,
+            10 => StorageBusType::Sas,
+            11 => StorageBusType::Sata,
+            12 => StorageBusType::Sd,
+            13 => StorageBusType::Mmc,
+            14 => StorageBusType::Virtual,
+            15 => StorageBusType::FileBackedVirtual,
+            16 => StorageBusType::Spaces,
+            17 => StorageBusType::Nvme,
+            18 => StorageBusType::Scm,
+            19 => StorageBusType::Ufs,
+            _ => StorageBusType::Other(index as u32),
+        }
+    }
+}
+
+/// Hardware identity of a storage device. Fields the device doesn't report are `None`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceIdentity {
+    /// Vendor ID, e.g. `ATA` or `Samsung`
+    pub vendor_id: Option<String>,
+    /// Product ID (model), e.g. `Samsung SSD 970 EVO 1TB`
+    pub product_id: Option<String>,
+    /// Hardware serial number of the device
+    pub serial_number: Option<String>,
+    /// Product revision, usually the firmware version
+    pub firmware_revision: Option<String>,
+}
+
+/// Sector sizes and alignment of a storage device, see [STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-storage_access_alignment_descriptor)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessAlignment {
+    /// Size of a logical sector in bytes, the unit used for addressing the device
+    pub logical_sector_size: u32,
+    /// Size of a physical sector in bytes, the unit the device actually writes
+    pub physical_sector_size: u32,
+    /// Offset in bytes of the first logical sector from the start of a physical sector
+    pub alignment_offset: u32,
+}
+
+impl AccessAlignment {
+    /// Whether the device emulates 512 byte logical sectors on top of larger physical sectors (512e)
+    pub fn is_512e(&self) -> bool {
+        self.logical_sector_size == 512 && self.physical_sector_size > 512
+    }
+
+    /// Whether the device uses 4096 byte logical and physical sectors (4Kn)
+    pub fn is_native_4k(&self) -> bool {
+        self.logical_sector_size == 4096 && self.physical_sector_size == 4096
+    }
+}
+
+/// Kind of media in a removable drive, see [get_removable_media_kind]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RemovableMediaKind {
+    /// SD, microSD or MMC card, in a built-in slot or a card reader
+    SdCard,
+    /// CompactFlash card in a card reader
+    CompactFlash,
+    /// USB flash drive
+    UsbStick,
+    /// Floppy disk drive
+    Floppy,
+    /// Other removable media, such as Zip disks or RDX cartridges
+    Other,
+}
+
+/// Removal policy of a storage device, set on the Policies tab of its Device Manager properties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RemovalPolicy {
+    /// The write cache is disabled so the device can be unplugged at any time, the default for USB devices
+    QuickRemoval,
+    /// Writes are cached, the device must be ejected before it's unplugged or data may be lost.
+    /// Fixed disks always use this policy
+    BetterPerformance,
+}
+
+/// Hot-plug capabilities of a storage device returned by [get_hotplug_info], see
+/// [STORAGE_HOTPLUG_INFO](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-storage_hotplug_info)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotplugInfo {
+    /// Whether the media can be removed from the device, such as a disc or a memory card
+    pub media_removable: bool,
+    /// Whether the media can be removed while the device is in use
+    pub media_hotplug: bool,
+    /// Whether the device itself can be surprise removed, which is the case under the quick removal policy
+    pub device_hotplug: bool,
+    /// Whether the write cache was enabled although the device is hot-pluggable
+    pub write_cache_enable_override: bool,
+}
+
+impl HotplugInfo {
+    /// Removal policy the device uses. Under [RemovalPolicy::BetterPerformance] callers should flush and
+    /// [eject] the drive before asking the user to unplug it
+    pub fn removal_policy(&self) -> RemovalPolicy {
+        if self.device_hotplug {
+            RemovalPolicy::QuickRemoval
+        } else {
+            RemovalPolicy::BetterPerformance
+        }
+    }
+}
+
+impl From<&STORAGE_HOTPLUG_INFO> for HotplugInfo {
+    fn from(info: &STORAGE_HOTPLUG_INFO) -> Self {
+        HotplugInfo {
+            media_removable: info.MediaRemovable != 0,
+            media_hotplug: info.MediaHotplug != 0,
+            device_hotplug: info.DeviceHotplug != 0,
+            write_cache_enable_override: info.WriteCacheEnableOverride != 0,
+        }
+    }
+}
+
+/// Opens a volume (`C:\`, volume GUID path) or a physical disk (`\\.\PhysicalDrive0`) for querying its properties
+pub(crate) fn open_storage_device(path: &str) -> Result<DeviceHandle, WinPartitionError> {
+    open_device(&volume_device_path(path), FILE_ACCESS_FLAGS(0))
+}
+
+/// Calls [IOCTL_STORAGE_QUERY_PROPERTY](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_query_property)
+/// with a standard query for the given property and returns the raw descriptor
+pub(crate) fn query_property(
+    device: &DeviceHandle,
+    property_id: STORAGE_PROPERTY_ID,
+) -> Result<Vec<u64>, WinPartitionError> {
+    let mut query = STORAGE_PROPERTY_QUERY {
+        PropertyId: property_id,
+        QueryType: PropertyStandardQuery,
+        AdditionalParameters: [0],
+    };
+    unsafe {
+        device.query_variable(
+            "IOCTL_STORAGE_QUERY_PROPERTY",
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            &mut query as *mut STORAGE_PROPERTY_QUERY as *mut c_void,
+            size_of::<STORAGE_PROPERTY_QUERY>() as u32)
+    }
+}
+
+/// Queries a property whose descriptor has a fixed size
+pub(crate) fn query_fixed_property<T: Copy>(
+    device: &DeviceHandle,
+    property_id: STORAGE_PROPERTY_ID,
+) -> Result<T, WinPartitionError> {
+    let buffer = query_property(device, property_id)?;
+    Ok(unsafe { *(buffer.as_ptr() as *const T) })
+}
+
+/// Returns bus type of the storage device behind a volume or physical disk, using
+/// [StorageDeviceProperty](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-storage_device_descriptor).
+///
+/// `path` is a root path such as `C:\`, a volume GUID path or a physical disk path such as `\\.\PhysicalDrive0`.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_storage_bus_type(path: String) -> Result<StorageBusType, WinPartitionError> {
+    let device = open_storage_device(&path)?;
+    query_bus_type(&device)
+}
+
+/// Returns vendor, model, serial number and firmware revision of the storage device behind
+/// a volume or physical disk, using [StorageDeviceProperty](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-storage_device_descriptor).
+///
+/// Unlike the volume serial returned by [get_volume_information](crate::win_api::get_volume_information),
+/// the serial number is assigned by the hardware manufacturer and survives reformatting.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_device_identity(path: String) -> Result<DeviceIdentity, WinPartitionError> {
+    let device = open_storage_device(&path)?;
+    Ok(query_device_descriptor(&device)?.1)
+}
+
+/// Checks whether the storage device behind a volume or physical disk is a solid state drive, using
+/// [StorageDeviceSeekPenaltyProperty](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-device_seek_penalty_descriptor).
+/// Devices without a seek penalty (SSD, NVMe, flash) are reported as SSD.
+///
+/// Minimum OS: Windows 8/Windows Server 2012
+pub fn is_ssd(path: String) -> Result<bool, WinPartitionError> {
+    let device = open_storage_device(&path)?;
+    let descriptor: DEVICE_SEEK_PENALTY_DESCRIPTOR =
+        query_fixed_property(&device, StorageDeviceSeekPenaltyProperty)?;
+
+    Ok(descriptor.IncursSeekPenalty == 0)
+}
+
+/// Checks whether the storage device behind a volume or physical disk supports TRIM/UNMAP, using
+/// [StorageDeviceTrimProperty](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-device_trim_descriptor).
+///
+/// Minimum OS: Windows 7/Windows Server 2008 R2
+pub fn supports_trim(path: String) -> Result<bool, WinPartitionError> {
+    let device = open_storage_device(&path)?;
+    let descriptor: DEVICE_TRIM_DESCRIPTOR =
+        query_fixed_property(&device, StorageDeviceTrimProperty)?;
+
+    Ok(descriptor.TrimEnabled != 0)
+}
+
+/// Returns logical and physical sector size of the storage device behind a volume or physical disk, using
+/// [StorageAccessAlignmentProperty](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-storage_access_alignment_descriptor).
+///
+/// Minimum OS: Windows Vista/Windows Server 2008
+pub fn get_access_alignment(path: String) -> Result<AccessAlignment, WinPartitionError> {
+    let device = open_storage_device(&path)?;
+    query_access_alignment(&device)
+}
+
+/// Calls [IOCTL_STORAGE_GET_HOTPLUG_INFO](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_get_hotplug_info)
+/// and returns whether the media and the device behind a volume or physical disk are removable and
+/// hot-pluggable, and with [HotplugInfo::removal_policy] which removal policy applies.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_hotplug_info(path: String) -> Result<HotplugInfo, WinPartitionError> {
+    let device = open_storage_device(&path)?;
+    query_hotplug_info(&device)
+}
+
+/// Ejects the media of a removable drive, such as a USB stick or an optical disc.
+///
+/// The volume is locked and dismounted first, so this fails if files on it are still open. Removal is then
+/// allowed with [IOCTL_STORAGE_MEDIA_REMOVAL](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_media_removal)
+/// and the media ejected with [IOCTL_STORAGE_EJECT_MEDIA](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_eject_media).
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn eject(letter: char) -> Result<(), WinPartitionError> {
+    let lock = VolumeLock::lock(format!("{}:\\", letter))?;
+    lock.dismount()?;
+
+    let device = lock.device();
+    let mut removal = PREVENT_MEDIA_REMOVAL { PreventMediaRemoval: 0 };
+    unsafe {
+        device.io_control(
+            "IOCTL_STORAGE_MEDIA_REMOVAL",
+            IOCTL_STORAGE_MEDIA_REMOVAL,
+            &mut removal as *mut PREVENT_MEDIA_REMOVAL as *mut c_void,
+            size_of::<PREVENT_MEDIA_REMOVAL>() as u32,
+            null_mut(),
+            0)?;
+    }
+    device.control("IOCTL_STORAGE_EJECT_MEDIA", IOCTL_STORAGE_EJECT_MEDIA)
+}
+
+/// Tells apart the media of a drive of type [DriveType::DriveRemovable], returns `None` for other drive types.
+///
+/// Floppy drives are recognized by their `\Device\Floppy` device name. Cards are recognized from the bus type
+/// and from the product ID of the [storage device descriptor](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-storage_device_descriptor),
+/// which card readers set to names such as `SD/MMC` or `Compact Flash`, and from
+/// [IOCTL_STORAGE_GET_HOTPLUG_INFO](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_get_hotplug_info):
+/// built-in slots whose media can be removed but not the device are taken as SD card slots.
+/// Other USB devices are reported as [RemovableMediaKind::UsbStick]. This is a heuristic, unusual card
+/// readers may be reported as USB sticks.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_removable_media_kind(letter: char) -> Result<Option<RemovableMediaKind>, WinPartitionError> {
+    let root = format!("{}:\\", letter);
+    if get_drive_type(&root) != DriveType::DriveRemovable {
+        return Ok(None);
+    }
+    let is_floppy = query_dos_device(format!("{}:", letter))?
+        .first()
+        .is_some_and(|target| target.to_ascii_lowercase().starts_with("\\device\\floppy"));
+    if is_floppy {
+        return Ok(Some(RemovableMediaKind::Floppy));
+    }
+
+    let device = open_storage_device(&root)?;
+    let (bus_type, identity) = query_device_descriptor(&device)?;
+    let hotplug = query_hotplug_info(&device)?;
+
+    Ok(Some(classify_removable_media(
+        bus_type,
+        identity.product_id.as_deref(),
+        hotplug.media_removable && !hotplug.device_hotplug)))
+}
+
+/// Classifies removable media from the bus type, the product ID and whether the device is a built-in slot
+/// with removable media
+fn classify_removable_media(
+    bus_type: StorageBusType,
+    product_id: Option<&str>,
+    built_in_slot: bool,
+) -> RemovableMediaKind {
+    let product_id = product_id.unwrap_or_default().to_ascii_uppercase();
+    let words: Vec<&str> = product_id.split(|c: char| !c.is_ascii_alphanumeric()).collect();
+    let has_word = |names: &[&str]| words.iter().any(|word| names.contains(word));
+
+    if has_word(&["CF", "CFC", "COMPACTFLASH"]) || product_id.contains("COMPACT FLASH") {
+        RemovableMediaKind::CompactFlash
+    } else if matches!(bus_type, StorageBusType::Sd | StorageBusType::Mmc)
+        || has_word(&["SD", "SDHC", "SDXC", "MICROSD", "MMC", "SDMMC"])
+        || product_id.contains("CARD")
+    {
+        RemovableMediaKind::SdCard
+    } else if bus_type == StorageBusType::Usb {
+        RemovableMediaKind::UsbStick
+    } else if built_in_slot {
+        RemovableMediaKind::SdCard
+    } else {
+        RemovableMediaKind::Other
+    }
+}
+
+pub(crate) fn query_hotplug_info(device: &DeviceHandle) -> Result<HotplugInfo, WinPartitionError> {
+    let info: STORAGE_HOTPLUG_INFO = device.query("IOCTL_STORAGE_GET_HOTPLUG_INFO", IOCTL_STORAGE_GET_HOTPLUG_INFO)?;
+    Ok(HotplugInfo::from(&info))
+}
+
+pub(crate) fn query_access_alignment(device: &DeviceHandle) -> Result<AccessAlignment, WinPartitionError> {
+    let descriptor: STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR =
+        query_fixed_property(device, StorageAccessAlignmentProperty)?;
+
+    Ok(AccessAlignment {
+        logical_sector_size: descriptor.BytesPerLogicalSector,
+        physical_sector_size: descriptor.BytesPerPhysicalSector,
+        alignment_offset: descriptor.BytesOffsetForSectorAlignment,
+    })
+}
+
+pub(crate) fn query_bus_type(device: &DeviceHandle) -> Result<StorageBusType, WinPartitionError> {
+    Ok(query_device_descriptor(device)?.0)
+}
+
+/// Reads bus type and device identity from the storage device descriptor
+pub(crate) fn query_device_descriptor(
+    device: &DeviceHandle,
+) -> Result<(StorageBusType, DeviceIdentity), WinPartitionError> {
+    let buffer = query_property(device, StorageDeviceProperty)?;
+    let descriptor = unsafe { &*(buffer.as_ptr() as *const STORAGE_DEVICE_DESCRIPTOR) };
+    let bytes = unsafe {
+        std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * size_of::<u64>())
+    };
+    // Only the bytes the driver actually filled in are valid
+    let bytes = &bytes[..(descriptor.Size as usize).min(bytes.len())];
+
+    let identity = DeviceIdentity {
+        vendor_id: descriptor_string(bytes, descriptor.VendorIdOffset),
+        product_id: descriptor_string(bytes, descriptor.ProductIdOffset),
+        serial_number: descriptor_string(bytes, descriptor.SerialNumberOffset),
+        firmware_revision: descriptor_string(bytes, descriptor.ProductRevisionOffset),
+    };
+    Ok((StorageBusType::from(descriptor.BusType.0), identity))
+}
+
+/// Reads a null terminated ASCII string at `offset` of a descriptor. Offset zero means the
+/// string isn't present. Devices pad these strings with spaces, which are trimmed
+fn descriptor_string(bytes: &[u8], offset: u32) -> Option<String> {
+    let offset = offset as usize;
+    if offset == 0 || offset >= bytes.len() {
+        return None;
+    }
+    let value = &bytes[offset..];
+    let end = value.iter().position(|byte| *byte == 0).unwrap_or(value.len());
+    let value = String::from_utf8_lossy(&value[..end]).trim().to_string();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn descriptor_string_test() {
+        let bytes = b"\0\0\0\0ATA     \0  \0";
+        assert_eq!(descriptor_string(bytes, 4).as_deref(), Some("ATA"));
+        assert_eq!(descriptor_string(bytes, 13), None);
+        assert_eq!(descriptor_string(bytes, 0), None);
+        assert_eq!(descriptor_string(bytes, 100), None);
+    }
+
+    #[test]
+    fn classify_removable_media_test() {
+        let classify = classify_removable_media;
+        assert_eq!(classify(StorageBusType::Usb, Some("SD/MMC"), false), RemovableMediaKind::SdCard);
+        assert_eq!(classify(StorageBusType::Usb, Some("Multi-Card"), false), RemovableMediaKind::SdCard);
+        assert_eq!(classify(StorageBusType::Usb, Some("Compact Flash"), false), RemovableMediaKind::CompactFlash);
+        assert_eq!(classify(StorageBusType::Usb, Some("CF"), false), RemovableMediaKind::CompactFlash);
+        assert_eq!(classify(StorageBusType::Sd, None, true), RemovableMediaKind::SdCard);
+        assert_eq!(classify(StorageBusType::Usb, Some("DataTraveler 3.0"), false), RemovableMediaKind::UsbStick);
+        // Names merely containing the letters aren't card readers
+        assert_eq!(classify(StorageBusType::Usb, Some("Cruzer Glide"), false), RemovableMediaKind::UsbStick);
+        assert_eq!(classify(StorageBusType::Scsi, Some("USB3.0 CRW"), true), RemovableMediaKind::SdCard);
+        assert_eq!(classify(StorageBusType::Atapi, Some("ZIP 250"), false), RemovableMediaKind::Other);
+    }
+
+    #[test]
+    fn removal_policy_test() {
+        let usb_stick = STORAGE_HOTPLUG_INFO { MediaRemovable: 1, DeviceHotplug: 1, ..Default::default() };
+        assert_eq!(HotplugInfo::from(&usb_stick).removal_policy(), RemovalPolicy::QuickRemoval);
+        assert_eq!(HotplugInfo::default().removal_policy(), RemovalPolicy::BetterPerformance);
+    }
+
+    #[test]
+    fn access_alignment_test() {
+        let alignment = AccessAlignment { logical_sector_size: 512, physical_sector_size: 4096, alignment_offset: 0 };
+        assert!(alignment.is_512e());
+        assert!(!alignment.is_native_4k());
+
+        let alignment = AccessAlignment { logical_sector_size: 4096, physical_sector_size: 4096, alignment_offset: 0 };
+        assert!(!alignment.is_512e());
+        assert!(alignment.is_native_4k());
+    }
+}
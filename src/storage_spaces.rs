@@ -0,0 +1,98 @@
+use crate::error::WinPartitionError;
+use crate::wmi::{associators_query, WmiConnection, WmiObject, STORAGE_NAMESPACE};
+
+/// How the capacity of a Storage Spaces virtual disk is allocated from its pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProvisioningType {
+    /// Pool capacity is allocated on write, the size may exceed the free capacity of the pool
+    Thin,
+    /// The whole size is allocated from the pool up front
+    Fixed,
+    /// The provisioning type couldn't be determined
+    Unknown,
+}
+
+impl From<u64> for ProvisioningType {
+    fn from(value: u64) -> Self {
+        match value {
+            1 => ProvisioningType::Thin,
+            2 => ProvisioningType::Fixed,
+            _ => ProvisioningType::Unknown,
+        }
+    }
+}
+
+/// Storage Spaces virtual disk a physical disk number is backed by, see
+/// [MSFT_VirtualDisk](https://docs.microsoft.com/en-us/previous-versions/windows/desktop/stormgmt/msft-virtualdisk)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageSpace {
+    /// Name of the virtual disk, as shown by `Get-VirtualDisk`
+    pub name: String,
+    /// Name of the storage pool the virtual disk is allocated from, as shown by `Get-StoragePool`
+    pub pool_name: String,
+    /// How the capacity of the virtual disk is allocated
+    pub provisioning_type: ProvisioningType,
+    /// Size of the virtual disk as seen by Windows in bytes
+    pub size: u64,
+    /// Capacity of the pool currently allocated to the virtual disk in bytes, excluding resiliency copies
+    pub allocated_size: u64,
+    /// Capacity of the pool used by the virtual disk in bytes, including resiliency copies
+    pub footprint_on_pool: u64,
+}
+
+impl StorageSpace {
+    /// Whether the virtual disk is thin-provisioned, i.e. its free space may not be backed by the pool
+    pub fn is_thin(&self) -> bool {
+        self.provisioning_type == ProvisioningType::Thin
+    }
+}
+
+/// Returns the Storage Spaces virtual disk backing the physical disk `disk_number`, as in
+/// `\\.\PhysicalDriveN`, or `None` for a plain physical disk. Queried from the
+/// [Windows Storage Management API](https://docs.microsoft.com/en-us/previous-versions/windows/desktop/stormgmt/windows-storage-management-api-portal)
+/// over WMI.
+///
+/// Minimum OS: Windows 8/Windows Server 2012
+pub fn get_storage_space(disk_number: u32) -> Result<Option<StorageSpace>, WinPartitionError> {
+    let wmi = WmiConnection::connect(STORAGE_NAMESPACE)?;
+    let disk = match first(wmi.query(&format!("SELECT * FROM MSFT_Disk WHERE Number = {}", disk_number))?) {
+        Some(disk) => disk,
+        None => return Ok(None),
+    };
+    let virtual_disk = match first(wmi.query(&associators_query(&disk, "MSFT_VirtualDisk")?)?) {
+        Some(virtual_disk) => virtual_disk,
+        None => return Ok(None),
+    };
+    let pool_name = match first(wmi.query(&associators_query(&virtual_disk, "MSFT_StoragePool")?)?) {
+        Some(pool) => pool.get_string("FriendlyName")?.unwrap_or_default(),
+        None => String::new(),
+    };
+
+    let provisioning_type = virtual_disk.get_u64("ProvisioningType")?;
+
+    Ok(Some(StorageSpace {
+        name: virtual_disk.get_string("FriendlyName")?.unwrap_or_default(),
+        pool_name,
+        provisioning_type: provisioning_type.map_or(ProvisioningType::Unknown, ProvisioningType::from),
+        size: virtual_disk.get_u64("Size")?.unwrap_or(0),
+        allocated_size: virtual_disk.get_u64("AllocatedSize")?.unwrap_or(0),
+        footprint_on_pool: virtual_disk.get_u64("FootprintOnPool")?.unwrap_or(0),
+    }))
+}
+
+fn first(objects: Vec<WmiObject>) -> Option<WmiObject> {
+    objects.into_iter().next()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn provisioning_type_test() {
+        assert_eq!(ProvisioningType::from(1), ProvisioningType::Thin);
+        assert_eq!(ProvisioningType::from(0), ProvisioningType::Unknown);
+    }
+}
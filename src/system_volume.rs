@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use crate::bindings::{
+    Windows::Win32::System::SystemInformation::GetFirmwareType,
+    Windows::Win32::System::SystemInformation::FirmwareTypeBios,
+    Windows::Win32::System::SystemInformation::FirmwareTypeUefi,
+    Windows::Win32::System::SystemInformation::FIRMWARE_TYPE,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::{
+    enumerate_volumes, get_registry_dword, get_registry_value, get_system_windows_directory, multi_sz_to_vec,
+    query_dos_device,
+};
+use crate::wstr::vec_u16_to_string;
+
+/// Win32 error code for "The system cannot find the file specified"
+const ERROR_FILE_NOT_FOUND: i32 = 2;
+/// Win32 error code for "The system cannot find the path specified"
+const ERROR_PATH_NOT_FOUND: i32 = 3;
+
+/// Firmware the system was booted with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FirmwareType {
+    /// Legacy BIOS, booting from the active partition of an MBR disk
+    Bios,
+    /// UEFI, booting from the EFI system partition
+    Uefi,
+    /// The firmware type couldn't be determined
+    Unknown,
+}
+
+/// Calls [GetFirmwareType](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getfirmwaretype)
+/// and returns the firmware the system was booted with
+///
+/// Minimum OS: Windows 8/Windows Server 2012
+pub fn get_firmware_type() -> Result<FirmwareType, WinPartitionError> {
+    let mut firmware_type = FIRMWARE_TYPE::default();
+    let result = unsafe { GetFirmwareType(&mut firmware_type).as_bool() };
+
+    if !result {
+        return Err(WinPartitionError::last_os_error("GetFirmwareType", None));
+    }
+    if firmware_type == FirmwareTypeBios {
+        Ok(FirmwareType::Bios)
+    } else if firmware_type == FirmwareTypeUefi {
+        Ok(FirmwareType::Uefi)
+    } else {
+        Ok(FirmwareType::Unknown)
+    }
+}
+
+/// Returns the drive letter of the boot volume, the volume Windows is installed on, from
+/// [get_system_windows_directory]
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_boot_volume_letter() -> Result<char, WinPartitionError> {
+    let directory = get_system_windows_directory()?;
+    match directory.chars().next() {
+        Some(letter) if letter.is_ascii_alphabetic() => Ok(letter.to_ascii_uppercase()),
+        _ => Err(WinPartitionError::from_code("GetSystemWindowsDirectoryW", Some(&directory), ERROR_PATH_NOT_FOUND)),
+    }
+}
+
+/// Returns the NT device path of the system volume, the volume holding the boot loader, e.g.
+/// `\Device\HarddiskVolume1`. On UEFI systems this is the EFI system partition, on BIOS systems the active
+/// partition, which is often the `System Reserved` partition or the boot volume itself.
+///
+/// Read from the `SystemPartition` value of `HKEY_LOCAL_MACHINE\SYSTEM\Setup`.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_system_partition_device() -> Result<String, WinPartitionError> {
+    let value = get_registry_value("SYSTEM\\Setup", "SystemPartition")?;
+    Ok(vec_u16_to_string(&value))
+}
+
+/// Returns the volume GUID path of the system volume (see [get_system_partition_device]), e.g.
+/// `\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\`. Unlike the boot volume, the system volume
+/// usually has no drive letter
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_system_volume() -> Result<String, WinPartitionError> {
+    let device = get_system_partition_device()?;
+    for guid_path in enumerate_volumes()? {
+        let targets = query_dos_device(dos_device_name(&guid_path).to_string())?;
+        if targets.iter().any(|target| target.eq_ignore_ascii_case(&device)) {
+            return Ok(guid_path);
+        }
+    }
+
+    Err(WinPartitionError::from_code("QueryDosDeviceW", Some(&device), ERROR_FILE_NOT_FOUND))
+}
+
+/// Returns the page files the system is currently using, e.g. `C:\pagefile.sys`.
+///
+/// Read from the `ExistingPageFiles` value of
+/// `HKEY_LOCAL_MACHINE\SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management`.
+/// Volumes hosting a page file can't be dismounted or removed while Windows runs.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_page_files() -> Result<Vec<PathBuf>, WinPartitionError> {
+    let value = get_registry_value(
+        "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Memory Management",
+        "ExistingPageFiles")?;
+    Ok(multi_sz_to_vec(&value).iter().map(|path| parse_page_file(path)).collect())
+}
+
+/// Parses a page file entry such as `\??\C:\pagefile.sys`
+fn parse_page_file(path: &str) -> PathBuf {
+    PathBuf::from(path.strip_prefix("\\??\\").unwrap_or(path))
+}
+
+/// Checks whether the Encrypting File System is turned off on this machine, either by the
+/// `EfsConfiguration` group policy or by the `NtfsDisableEncryption` file system setting. Encrypting files
+/// fails with `ERROR_ACCESS_DENIED` then, even on volumes that support it
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn is_efs_disabled() -> Result<bool, WinPartitionError> {
+    let policy = get_optional_registry_dword(
+        "SOFTWARE\\Policies\\Microsoft\\Windows NT\\CurrentVersion\\EFS",
+        "EfsConfiguration")?;
+    let file_system = get_optional_registry_dword(
+        "SYSTEM\\CurrentControlSet\\Control\\FileSystem",
+        "NtfsDisableEncryption")?;
+    Ok(policy == Some(1) || file_system == Some(1))
+}
+
+/// Reads a `REG_DWORD` value that is absent unless configured, returning `None` if it's not set
+fn get_optional_registry_dword(lpsubkey: &str, lpvalue: &str) -> Result<Option<u32>, WinPartitionError> {
+    match get_registry_dword(lpsubkey, lpvalue) {
+        Ok(value) => Ok(Some(value)),
+        Err(WinPartitionError::Os { code, .. }) if code == ERROR_FILE_NOT_FOUND => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Returns the DOS device name of a volume GUID path, e.g.
+/// `Volume{26a21bda-a627-11d7-9931-806e6f6e6963}` for `\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\`
+fn dos_device_name(guid_path: &str) -> &str {
+    guid_path.trim_start_matches("\\\\?\\").trim_end_matches('\\')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dos_device_name_test() {
+        assert_eq!(
+            dos_device_name("\\\\?\\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\\"),
+            "Volume{26a21bda-a627-11d7-9931-806e6f6e6963}");
+        assert_eq!(parse_page_file("\\??\\C:\\pagefile.sys"), PathBuf::from("C:\\pagefile.sys"));
+        assert_eq!(parse_page_file("D:\\pagefile.sys"), PathBuf::from("D:\\pagefile.sys"));
+    }
+}
@@ -0,0 +1,156 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::CREATE_USN_JOURNAL_DATA,
+    Windows::Win32::Storage::FileSystem::DELETE_USN_JOURNAL_DATA,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_WRITE,
+    Windows::Win32::Storage::FileSystem::USN_DELETE_FLAG_DELETE,
+    Windows::Win32::Storage::FileSystem::USN_DELETE_FLAG_NOTIFY,
+    Windows::Win32::Storage::FileSystem::USN_JOURNAL_DATA_V0,
+    Windows::Win32::System::SystemServices::FSCTL_CREATE_USN_JOURNAL,
+    Windows::Win32::System::SystemServices::FSCTL_DELETE_USN_JOURNAL,
+    Windows::Win32::System::SystemServices::FSCTL_QUERY_USN_JOURNAL,
+};
+use crate::error::WinPartitionError;
+use crate::privilege::{enable_privilege, SE_MANAGE_VOLUME_NAME};
+use crate::win_api::{open_device, volume_device_path, DeviceHandle};
+
+/// Win32 error code for "The volume change journal is not active"
+const ERROR_JOURNAL_NOT_ACTIVE: i32 = 1179;
+
+/// State of the USN change journal of a volume, see [USN_JOURNAL_DATA_V0](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-usn_journal_data_v0).
+/// Update sequence numbers (USN) are byte offsets into the journal, growing with every change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsnJournal {
+    /// ID of the journal instance. A new ID means the journal was recreated and earlier USNs are invalid
+    pub journal_id: u64,
+    /// First USN that can be read from the journal
+    pub first_usn: i64,
+    /// USN the next change will be written at
+    pub next_usn: i64,
+    /// Lowest USN valid for this journal instance, records below it were lost or never written
+    pub lowest_valid_usn: i64,
+    /// Largest USN the journal can reach before it has to be recreated
+    pub max_usn: i64,
+    /// Target maximum size of the journal in bytes
+    pub maximum_size: u64,
+    /// Number of bytes removed from the start of the journal whenever it exceeds `maximum_size`
+    pub allocation_delta: u64,
+}
+
+impl UsnJournal {
+    /// Whether changes since `usn`, recorded for the same `journal_id`, can still be read in full
+    pub fn can_read_from(&self, journal_id: u64, usn: i64) -> bool {
+        self.journal_id == journal_id && usn >= self.first_usn && usn <= self.next_usn
+    }
+}
+
+/// Calls [FSCTL_QUERY_USN_JOURNAL](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_query_usn_journal)
+/// and returns the USN change journal of an NTFS or ReFS volume, or `None` if the journal isn't active.
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn query_usn_journal(lprootpathname: String) -> Result<Option<UsnJournal>, WinPartitionError> {
+    let device = open_device(&volume_device_path(&lprootpathname), FILE_GENERIC_READ)?;
+    let data: USN_JOURNAL_DATA_V0 = match device.query("FSCTL_QUERY_USN_JOURNAL", FSCTL_QUERY_USN_JOURNAL) {
+        Ok(data) => data,
+        Err(err) if err.raw_os_error() == ERROR_JOURNAL_NOT_ACTIVE => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    Ok(Some(UsnJournal {
+        journal_id: data.UsnJournalID,
+        first_usn: data.FirstUsn,
+        next_usn: data.NextUsn,
+        lowest_valid_usn: data.LowestValidUsn,
+        max_usn: data.MaxUsn,
+        maximum_size: data.MaximumSize,
+        allocation_delta: data.AllocationDelta,
+    }))
+}
+
+/// Calls [FSCTL_CREATE_USN_JOURNAL](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_create_usn_journal)
+/// and creates the USN change journal of the volume at drive letter `letter`, or changes the sizes of an
+/// active one. `max_size` is the target maximum size of the journal and `allocation_delta` the number of
+/// bytes trimmed from its start once it grows past that, both in bytes. Zero sizes select the file system defaults.
+///
+/// Requires administrator rights. `SeManageVolumePrivilege` is enabled for the process when it holds it.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn create_usn_journal(letter: char, max_size: u64, allocation_delta: u64) -> Result<(), WinPartitionError> {
+    let device = open_journal_volume(letter)?;
+    let mut data = CREATE_USN_JOURNAL_DATA { MaximumSize: max_size, AllocationDelta: allocation_delta };
+    unsafe {
+        device.io_control(
+            "FSCTL_CREATE_USN_JOURNAL",
+            FSCTL_CREATE_USN_JOURNAL,
+            &mut data as *mut CREATE_USN_JOURNAL_DATA as *mut c_void,
+            size_of::<CREATE_USN_JOURNAL_DATA>() as u32,
+            null_mut(),
+            0)?;
+    }
+    Ok(())
+}
+
+/// Calls [FSCTL_DELETE_USN_JOURNAL](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_delete_usn_journal)
+/// and deletes the USN change journal of the volume at drive letter `letter`, waiting until the deletion
+/// completes. Does nothing if the journal isn't active. Applications that track the journal, such as
+/// Windows Search, may recreate it.
+///
+/// Requires administrator rights. `SeManageVolumePrivilege` is enabled for the process when it holds it.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn delete_usn_journal(letter: char) -> Result<(), WinPartitionError> {
+    let journal = match query_usn_journal(format!("{}:\\", letter))? {
+        Some(journal) => journal,
+        None => return Ok(()),
+    };
+    let device = open_journal_volume(letter)?;
+    let mut data = DELETE_USN_JOURNAL_DATA {
+        UsnJournalID: journal.journal_id,
+        DeleteFlags: USN_DELETE_FLAG_DELETE | USN_DELETE_FLAG_NOTIFY,
+    };
+    unsafe {
+        device.io_control(
+            "FSCTL_DELETE_USN_JOURNAL",
+            FSCTL_DELETE_USN_JOURNAL,
+            &mut data as *mut DELETE_USN_JOURNAL_DATA as *mut c_void,
+            size_of::<DELETE_USN_JOURNAL_DATA>() as u32,
+            null_mut(),
+            0)?;
+    }
+    Ok(())
+}
+
+/// Opens the volume at `letter` for changing its journal
+fn open_journal_volume(letter: char) -> Result<DeviceHandle, WinPartitionError> {
+    // Opening the volume for writing is what the FSCTLs check, the privilege only helps accounts granted it by policy
+    enable_privilege(SE_MANAGE_VOLUME_NAME)?;
+    open_device(&volume_device_path(&format!("{}:\\", letter)), FILE_GENERIC_READ | FILE_GENERIC_WRITE)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_read_from_test() {
+        let journal = UsnJournal {
+            journal_id: 7,
+            first_usn: 4096,
+            next_usn: 65536,
+            lowest_valid_usn: 0,
+            max_usn: i64::MAX,
+            maximum_size: 32 * 1024 * 1024,
+            allocation_delta: 8 * 1024 * 1024,
+        };
+        assert!(journal.can_read_from(7, 8192));
+        assert!(!journal.can_read_from(7, 0));
+        assert!(!journal.can_read_from(8, 8192));
+    }
+}
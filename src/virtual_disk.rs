@@ -0,0 +1,319 @@
+use std::path::Path;
+use std::ptr::null_mut;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bitflags::bitflags;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::CloseHandle,
+    Windows::Win32::Foundation::HANDLE,
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::Storage::Vhd::AttachVirtualDisk,
+    Windows::Win32::Storage::Vhd::CreateVirtualDisk,
+    Windows::Win32::Storage::Vhd::DetachVirtualDisk,
+    Windows::Win32::Storage::Vhd::GetVirtualDiskPhysicalPath,
+    Windows::Win32::Storage::Vhd::OpenVirtualDisk,
+    Windows::Win32::Storage::Vhd::ATTACH_VIRTUAL_DISK_FLAG,
+    Windows::Win32::Storage::Vhd::ATTACH_VIRTUAL_DISK_PARAMETERS,
+    Windows::Win32::Storage::Vhd::ATTACH_VIRTUAL_DISK_VERSION_1,
+    Windows::Win32::Storage::Vhd::CREATE_VIRTUAL_DISK_FLAG_FULL_PHYSICAL_ALLOCATION,
+    Windows::Win32::Storage::Vhd::CREATE_VIRTUAL_DISK_FLAG_NONE,
+    Windows::Win32::Storage::Vhd::CREATE_VIRTUAL_DISK_PARAMETERS,
+    Windows::Win32::Storage::Vhd::CREATE_VIRTUAL_DISK_VERSION_2,
+    Windows::Win32::Storage::Vhd::DETACH_VIRTUAL_DISK_FLAG_NONE,
+    Windows::Win32::Storage::Vhd::OPEN_VIRTUAL_DISK_FLAG_NONE,
+    Windows::Win32::Storage::Vhd::VIRTUAL_DISK_ACCESS_ALL,
+    Windows::Win32::Storage::Vhd::VIRTUAL_DISK_ACCESS_MASK,
+    Windows::Win32::Storage::Vhd::VIRTUAL_DISK_ACCESS_NONE,
+    Windows::Win32::Storage::Vhd::VIRTUAL_DISK_ACCESS_READ,
+    Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE,
+    Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_ISO,
+    Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_VHD,
+    Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_DEVICE_VHDX,
+    Windows::Win32::Storage::Vhd::VIRTUAL_STORAGE_TYPE_VENDOR_MICROSOFT,
+};
+use crate::error::WinPartitionError;
+use crate::windows_partitions::{get_partitions, WindowsPartition};
+use crate::wstr::vec_u16_to_string;
+
+/// Win32 error code for "The parameter is incorrect"
+const ERROR_INVALID_PARAMETER: i32 = 87;
+
+/// How the file of a new virtual disk is allocated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VirtualDiskAllocation {
+    /// The file grows as data is written to the disk
+    Dynamic,
+    /// The file is allocated to the full size of the disk up front, which is slower to create but faster to use
+    Fixed,
+}
+
+bitflags! {
+    /// Options of [VirtualDisk::attach], see [ATTACH_VIRTUAL_DISK_FLAG](https://docs.microsoft.com/en-us/windows/win32/api/virtdisk/ne-virtdisk-attach_virtual_disk_flag)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct AttachFlags: u32 {
+        /// Attach the disk read only
+        const READ_ONLY = 0x0000_0001;
+        /// Don't assign drive letters to the volumes of the disk
+        const NO_DRIVE_LETTER = 0x0000_0002;
+        /// Keep the disk attached after the [VirtualDisk] is dropped, until it is detached explicitly
+        /// or the system restarts. Requires administrator rights
+        const PERMANENT_LIFETIME = 0x0000_0004;
+    }
+}
+
+/// Virtual hard disk file (VHD or VHDX) or ISO image opened with
+/// [OpenVirtualDisk](https://docs.microsoft.com/en-us/windows/win32/api/virtdisk/nf-virtdisk-openvirtualdisk).
+///
+/// Once attached, its volumes are listed by [get_partitions](crate::windows_partitions::get_partitions) like those
+/// of any other disk. A disk attached without [AttachFlags::PERMANENT_LIFETIME] is detached when this is dropped.
+///
+/// ```no_run
+/// use win_partitions::virtual_disk::{AttachFlags, VirtualDisk};
+///
+/// let disk = VirtualDisk::open("C:\\Images\\test.vhdx".to_string()).unwrap();
+/// disk.attach(AttachFlags::empty()).unwrap();
+/// println!("{}", disk.physical_path().unwrap());
+/// ```
+pub struct VirtualDisk {
+    handle: HANDLE,
+    path: String,
+}
+
+impl VirtualDisk {
+    /// Opens the virtual disk file at `path` for attaching. The format is detected from the `.vhd` or `.vhdx`
+    /// extension. Requires administrator rights
+    ///
+    /// Minimum OS: Windows 7/Windows Server 2008 R2
+    pub fn open(path: String) -> Result<VirtualDisk, WinPartitionError> {
+        // A zeroed storage type detects the format from the file extension
+        VirtualDisk::open_with(path, VIRTUAL_STORAGE_TYPE::default(), VIRTUAL_DISK_ACCESS_ALL)
+    }
+
+    /// Opens the ISO image at `path` for attaching as a virtual CD-Rom drive, which must be attached
+    /// with [AttachFlags::READ_ONLY]. See [mount_iso]. Requires administrator rights
+    ///
+    /// Minimum OS: Windows 8/Windows Server 2012
+    pub fn open_iso(path: String) -> Result<VirtualDisk, WinPartitionError> {
+        let storage_type = VIRTUAL_STORAGE_TYPE {
+            DeviceId: VIRTUAL_STORAGE_TYPE_DEVICE_ISO,
+            VendorId: VIRTUAL_STORAGE_TYPE_VENDOR_MICROSOFT,
+        };
+        VirtualDisk::open_with(path, storage_type, VIRTUAL_DISK_ACCESS_READ)
+    }
+
+    fn open_with(
+        path: String,
+        mut storage_type: VIRTUAL_STORAGE_TYPE,
+        access: VIRTUAL_DISK_ACCESS_MASK,
+    ) -> Result<VirtualDisk, WinPartitionError> {
+        let mut handle = HANDLE::default();
+        let result = unsafe {
+            OpenVirtualDisk(
+                &mut storage_type,
+                path.as_str(),
+                access,
+                OPEN_VIRTUAL_DISK_FLAG_NONE,
+                null_mut(),
+                &mut handle)
+        };
+
+        match result {
+            0 => Ok(VirtualDisk { handle, path }),
+            code => Err(WinPartitionError::from_code("OpenVirtualDisk", Some(&path), code as i32)),
+        }
+    }
+
+    /// Path of the opened virtual disk file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Attaches the disk by calling [AttachVirtualDisk](https://docs.microsoft.com/en-us/windows/win32/api/virtdisk/nf-virtdisk-attachvirtualdisk),
+    /// so Windows mounts its volumes
+    pub fn attach(&self, flags: AttachFlags) -> Result<(), WinPartitionError> {
+        let mut parameters = ATTACH_VIRTUAL_DISK_PARAMETERS {
+            Version: ATTACH_VIRTUAL_DISK_VERSION_1,
+            ..unsafe { std::mem::zeroed() }
+        };
+        let result = unsafe {
+            AttachVirtualDisk(
+                self.handle,
+                null_mut(),
+                ATTACH_VIRTUAL_DISK_FLAG(flags.bits()),
+                0,
+                &mut parameters,
+                null_mut())
+        };
+
+        match result {
+            0 => Ok(()),
+            code => Err(WinPartitionError::from_code("AttachVirtualDisk", Some(&self.path), code as i32)),
+        }
+    }
+
+    /// Detaches the disk by calling [DetachVirtualDisk](https://docs.microsoft.com/en-us/windows/win32/api/virtdisk/nf-virtdisk-detachvirtualdisk),
+    /// removing its volumes
+    pub fn detach(&self) -> Result<(), WinPartitionError> {
+        let result = unsafe { DetachVirtualDisk(self.handle, DETACH_VIRTUAL_DISK_FLAG_NONE, 0) };
+
+        match result {
+            0 => Ok(()),
+            code => Err(WinPartitionError::from_code("DetachVirtualDisk", Some(&self.path), code as i32)),
+        }
+    }
+
+    /// Calls [GetVirtualDiskPhysicalPath](https://docs.microsoft.com/en-us/windows/win32/api/virtdisk/nf-virtdisk-getvirtualdiskphysicalpath)
+    /// and returns the device path of the attached disk, e.g. `\\.\PhysicalDrive3`
+    pub fn physical_path(&self) -> Result<String, WinPartitionError> {
+        let mut disk_path_buf: Vec<u16> = vec![0; 261];
+        let mut size = (disk_path_buf.len() * 2) as u32;
+        let result = unsafe {
+            GetVirtualDiskPhysicalPath(self.handle, &mut size, PWSTR(disk_path_buf.as_mut_ptr()))
+        };
+
+        match result {
+            0 => Ok(vec_u16_to_string(&disk_path_buf)),
+            code => Err(WinPartitionError::from_code("GetVirtualDiskPhysicalPath", Some(&self.path), code as i32)),
+        }
+    }
+
+    /// Partitions of the attached disk that have a drive letter, found through [get_partitions].
+    /// Windows assigns the drive letters shortly after attaching, so this can be empty right after [attach](VirtualDisk::attach)
+    pub fn partitions(&self) -> Result<Vec<WindowsPartition>, WinPartitionError> {
+        let physical_path = self.physical_path()?;
+        Ok(get_partitions()?
+            .into_iter()
+            .filter(|partition| is_on_device(&physical_path, partition))
+            .collect())
+    }
+}
+
+/// Whether a partition is stored on the device at `physical_path`,
+/// e.g. `\\.\PhysicalDrive3` for virtual hard disks or `\\.\CDROM1` for ISO images
+fn is_on_device(physical_path: &str, partition: &WindowsPartition) -> bool {
+    let device = physical_path.trim_start_matches("\\\\.\\").to_ascii_lowercase();
+    if let Some(number) = device.strip_prefix("physicaldrive") {
+        return partition.disk_extents.iter().any(|extent| extent.disk_number.to_string() == number);
+    }
+    partition
+        .device_path
+        .as_deref()
+        .and_then(|device_path| device_path.strip_prefix("\\Device\\"))
+        .is_some_and(|device_path| device_path.eq_ignore_ascii_case(&device))
+}
+
+/// Attaches the ISO image at `path` as a virtual CD-Rom drive and waits up to `timeout` for Windows to assign
+/// it a drive letter. Returns the attached image together with its partition; the drive disappears
+/// when the [VirtualDisk] is dropped. Requires administrator rights
+///
+/// Minimum OS: Windows 8/Windows Server 2012
+pub fn mount_iso(path: String, timeout: Duration) -> Result<(VirtualDisk, WindowsPartition), WinPartitionError> {
+    let disk = VirtualDisk::open_iso(path)?;
+    disk.attach(AttachFlags::READ_ONLY)?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(partition) = disk.partitions()?.into_iter().next() {
+            return Ok((disk, partition));
+        }
+        if Instant::now() >= deadline {
+            return Err(WinPartitionError::TimedOut { api: "AttachVirtualDisk", path: Some(disk.path.to_string()) });
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Returns the virtual storage device type of a `.vhd` or `.vhdx` file path
+fn storage_device_id(path: &str) -> Option<u32> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "vhd" => Some(VIRTUAL_STORAGE_TYPE_DEVICE_VHD),
+        "vhdx" => Some(VIRTUAL_STORAGE_TYPE_DEVICE_VHDX),
+        _ => None,
+    }
+}
+
+/// Creates a new virtual disk file by calling [CreateVirtualDisk](https://docs.microsoft.com/en-us/windows/win32/api/virtdisk/nf-virtdisk-createvirtualdisk)
+/// and returns it opened, ready to be attached with [VirtualDisk::attach].
+///
+/// The format is chosen from the `.vhd` or `.vhdx` extension of `path`, and `size` in bytes must be a multiple of 512.
+/// The new disk has no partitions. Requires administrator rights
+///
+/// Minimum OS: Windows 8/Windows Server 2012
+pub fn create_virtual_disk(
+    path: String,
+    size: u64,
+    allocation: VirtualDiskAllocation,
+) -> Result<VirtualDisk, WinPartitionError> {
+    let device_id = match storage_device_id(&path) {
+        Some(device_id) => device_id,
+        None => return Err(WinPartitionError::from_code("CreateVirtualDisk", Some(&path), ERROR_INVALID_PARAMETER)),
+    };
+    let mut storage_type = VIRTUAL_STORAGE_TYPE {
+        DeviceId: device_id,
+        VendorId: VIRTUAL_STORAGE_TYPE_VENDOR_MICROSOFT,
+    };
+    let mut parameters = CREATE_VIRTUAL_DISK_PARAMETERS {
+        Version: CREATE_VIRTUAL_DISK_VERSION_2,
+        ..unsafe { std::mem::zeroed() }
+    };
+    // Zero block and sector sizes select the defaults of the format
+    parameters.Anonymous.Version2.MaximumSize = size;
+    let flags = match allocation {
+        VirtualDiskAllocation::Dynamic => CREATE_VIRTUAL_DISK_FLAG_NONE,
+        VirtualDiskAllocation::Fixed => CREATE_VIRTUAL_DISK_FLAG_FULL_PHYSICAL_ALLOCATION,
+    };
+    let mut handle = HANDLE::default();
+    let result = unsafe {
+        CreateVirtualDisk(
+            &mut storage_type,
+            path.as_str(),
+            VIRTUAL_DISK_ACCESS_NONE,
+            null_mut(),
+            flags,
+            0,
+            &mut parameters,
+            null_mut(),
+            &mut handle)
+    };
+
+    match result {
+        0 => Ok(VirtualDisk { handle, path }),
+        code => Err(WinPartitionError::from_code("CreateVirtualDisk", Some(&path), code as i32)),
+    }
+}
+
+impl Drop for VirtualDisk {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.handle) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn storage_device_id_test() {
+        assert_eq!(storage_device_id("C:\\Images\\test.vhd"), Some(VIRTUAL_STORAGE_TYPE_DEVICE_VHD));
+        assert_eq!(storage_device_id("C:\\Images\\test.VHDX"), Some(VIRTUAL_STORAGE_TYPE_DEVICE_VHDX));
+        assert_eq!(storage_device_id("C:\\Images\\test.img"), None);
+    }
+
+    #[test]
+    fn is_on_device_test() {
+        let mut partition = WindowsPartition {
+            device_path: Some("\\Device\\CdRom1".to_string()),
+            ..Default::default()
+        };
+        assert!(is_on_device("\\\\.\\CDROM1", &partition));
+        assert!(!is_on_device("\\\\.\\CDROM2", &partition));
+
+        partition.disk_extents = vec![crate::win_api::DiskExtent { disk_number: 3, starting_offset: 0, extent_length: 0 }];
+        assert!(is_on_device("\\\\.\\PhysicalDrive3", &partition));
+        assert!(!is_on_device("\\\\.\\PhysicalDrive30", &partition));
+    }
+}
@@ -0,0 +1,252 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::STARTING_LCN_INPUT_BUFFER,
+    Windows::Win32::System::SystemServices::FSCTL_GET_VOLUME_BITMAP,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::{get_disk_free_space_basic, open_device, volume_device_path, DeviceHandle};
+
+/// Win32 error code for "More data is available"
+const ERROR_MORE_DATA: i32 = 234;
+/// Size of the `StartingLcn` and `BitmapSize` header of `VOLUME_BITMAP_BUFFER` in bytes
+const BITMAP_HEADER_SIZE: usize = 16;
+/// Number of bitmap bytes read per `FSCTL_GET_VOLUME_BITMAP` call, covering 2 Mi clusters
+const BITMAP_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Layout of the free space of a volume, see [analyze_free_space]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FreeSpaceAnalysis {
+    /// Size of a cluster in bytes
+    pub bytes_per_cluster: u64,
+    /// Number of clusters in the volume
+    pub total_clusters: u64,
+    /// Number of free clusters
+    pub free_clusters: u64,
+    /// Number of runs of contiguous free clusters
+    pub free_extents: u64,
+    /// Length of the longest run of contiguous free clusters
+    pub largest_free_extent: u64,
+}
+
+impl FreeSpaceAnalysis {
+    /// Length of the longest run of contiguous free clusters in bytes, the largest file that can be
+    /// allocated without fragmentation
+    pub fn largest_free_extent_bytes(&self) -> u64 {
+        self.largest_free_extent * self.bytes_per_cluster
+    }
+
+    /// Average length of a free extent in clusters, 0 for a full volume
+    pub fn average_free_extent(&self) -> u64 {
+        match self.free_extents {
+            0 => 0,
+            extents => self.free_clusters / extents,
+        }
+    }
+
+    /// Adds `bits` clusters of a bitmap chunk, where a set bit marks an allocated cluster
+    fn add(&mut self, bitmap: &[u8], bits: u64, current_extent: &mut u64) {
+        for index in 0..bits {
+            let allocated = bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0;
+            if allocated {
+                self.end_extent(current_extent);
+            } else {
+                *current_extent += 1;
+                self.free_clusters += 1;
+            }
+        }
+        self.total_clusters += bits;
+    }
+
+    fn end_extent(&mut self, current_extent: &mut u64) {
+        if *current_extent > 0 {
+            self.free_extents += 1;
+            self.largest_free_extent = self.largest_free_extent.max(*current_extent);
+            *current_extent = 0;
+        }
+    }
+}
+
+/// Allocation state of a run of clusters, read by [VolumeBitmap]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitmapChunk {
+    /// LCN of the first cluster of the chunk
+    pub starting_lcn: u64,
+    /// Number of clusters in the chunk
+    pub cluster_count: u64,
+    /// One bit per cluster, least significant bit first, set for allocated clusters
+    pub bitmap: Vec<u8>,
+}
+
+impl BitmapChunk {
+    /// Whether cluster `lcn` is allocated, `None` if it's outside the chunk
+    pub fn is_allocated(&self, lcn: u64) -> Option<bool> {
+        if lcn < self.starting_lcn || lcn >= self.starting_lcn + self.cluster_count {
+            return None;
+        }
+        let index = lcn - self.starting_lcn;
+        Some(self.bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0)
+    }
+
+    /// LCN of the last allocated cluster of the chunk, `None` if all of its clusters are free
+    pub fn last_allocated(&self) -> Option<u64> {
+        (0..self.cluster_count)
+            .rev()
+            .find(|index| self.bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0)
+            .map(|index| self.starting_lcn + index)
+    }
+}
+
+/// Streaming reader over the allocation bitmap of a volume, returned by [VolumeBitmap::open].
+/// Yields chunks of up to 2 Mi clusters read with
+/// [FSCTL_GET_VOLUME_BITMAP](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_get_volume_bitmap),
+/// so the bitmap of a large volume doesn't need to fit in memory. The bitmap may change while it's read.
+///
+/// ```no_run
+/// use win_partitions::volume_bitmap::VolumeBitmap;
+///
+/// let mut allocated: u64 = 0;
+/// for chunk in VolumeBitmap::open("C:\\".to_string()).unwrap() {
+///     let chunk = chunk.unwrap();
+///     allocated += (0..chunk.cluster_count)
+///         .filter(|index| chunk.is_allocated(chunk.starting_lcn + index) == Some(true))
+///         .count() as u64;
+/// }
+/// ```
+pub struct VolumeBitmap {
+    device: DeviceHandle,
+    buffer: Vec<u64>,
+    next_lcn: u64,
+    done: bool,
+}
+
+impl VolumeBitmap {
+    /// Opens the bitmap of the volume at `lprootpathname`, a root path such as `C:\` or a volume GUID path.
+    /// Requires administrator rights.
+    ///
+    /// Minimum OS: Windows XP/Windows Server 2003
+    pub fn open(lprootpathname: String) -> Result<VolumeBitmap, WinPartitionError> {
+        let device = open_device(&volume_device_path(&lprootpathname), FILE_GENERIC_READ)?;
+        Ok(VolumeBitmap {
+            device,
+            buffer: vec![0; (BITMAP_HEADER_SIZE + BITMAP_CHUNK_SIZE) / size_of::<u64>()],
+            next_lcn: 0,
+            done: false,
+        })
+    }
+
+    /// Starts reading at cluster `lcn`, rounded down to a multiple of 8 by Windows
+    pub fn starting_at(mut self, lcn: u64) -> VolumeBitmap {
+        self.next_lcn = lcn;
+        self
+    }
+
+    fn read_chunk(&mut self) -> Result<Option<BitmapChunk>, WinPartitionError> {
+        let mut input = STARTING_LCN_INPUT_BUFFER { StartingLcn: self.next_lcn as i64 };
+        let result = unsafe {
+            self.device.io_control(
+                "FSCTL_GET_VOLUME_BITMAP",
+                FSCTL_GET_VOLUME_BITMAP,
+                &mut input as *mut STARTING_LCN_INPUT_BUFFER as *mut c_void,
+                size_of::<STARTING_LCN_INPUT_BUFFER>() as u32,
+                self.buffer.as_mut_ptr() as *mut c_void,
+                (self.buffer.len() * size_of::<u64>()) as u32)
+        };
+        match result {
+            Ok(_) => self.done = true,
+            Err(err) if err.raw_os_error() == ERROR_MORE_DATA => {}
+            Err(err) => return Err(err),
+        };
+
+        // The header holds the LCN the chunk starts at and the number of clusters left from there
+        let starting_lcn = self.buffer[0];
+        let cluster_count = self.buffer[1].min(BITMAP_CHUNK_SIZE as u64 * 8);
+        if cluster_count == 0 {
+            self.done = true;
+            return Ok(None);
+        }
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (self.buffer.as_ptr() as *const u8).add(BITMAP_HEADER_SIZE),
+                cluster_count.div_ceil(8) as usize)
+        };
+        self.next_lcn = starting_lcn + cluster_count;
+
+        Ok(Some(BitmapChunk { starting_lcn, cluster_count, bitmap: bytes.to_vec() }))
+    }
+}
+
+impl Iterator for VolumeBitmap {
+    type Item = Result<BitmapChunk, WinPartitionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_chunk() {
+            Ok(chunk) => chunk.map(Ok),
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Reads the allocation bitmap of the volume, see [VolumeBitmap], and counts its free extents.
+/// Shrinking a volume or preallocating a large contiguous file only succeeds within the free extents,
+/// however much free space the volume has.
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn analyze_free_space(lprootpathname: String) -> Result<FreeSpaceAnalysis, WinPartitionError> {
+    let (sectors_per_cluster, bytes_per_sector, _, _) = get_disk_free_space_basic(lprootpathname.clone())?;
+
+    let mut analysis = FreeSpaceAnalysis {
+        bytes_per_cluster: sectors_per_cluster as u64 * bytes_per_sector as u64,
+        ..Default::default()
+    };
+    let mut current_extent: u64 = 0;
+    for chunk in VolumeBitmap::open(lprootpathname)? {
+        let chunk = chunk?;
+        analysis.add(&chunk.bitmap, chunk.cluster_count, &mut current_extent);
+    }
+    analysis.end_extent(&mut current_extent);
+
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn free_space_analysis_test() {
+        let mut analysis = FreeSpaceAnalysis { bytes_per_cluster: 4096, ..Default::default() };
+        let mut current_extent: u64 = 0;
+        // Clusters 0-1 allocated, 2-7 free, 8 allocated, 9-11 free, continued by the next chunk
+        analysis.add(&[0b0000_0011, 0b0000_0001], 12, &mut current_extent);
+        // Clusters 12-13 free, 14-15 allocated
+        analysis.add(&[0b0000_1100], 4, &mut current_extent);
+        analysis.end_extent(&mut current_extent);
+
+        assert_eq!(analysis.total_clusters, 16);
+        assert_eq!(analysis.free_clusters, 11);
+        assert_eq!(analysis.free_extents, 2);
+        assert_eq!(analysis.largest_free_extent, 6);
+        assert_eq!(analysis.largest_free_extent_bytes(), 6 * 4096);
+        assert_eq!(analysis.average_free_extent(), 5);
+
+        let chunk = BitmapChunk { starting_lcn: 16, cluster_count: 4, bitmap: vec![0b0000_0101] };
+        assert_eq!(chunk.is_allocated(16), Some(true));
+        assert_eq!(chunk.is_allocated(17), Some(false));
+        assert_eq!(chunk.is_allocated(20), None);
+        assert_eq!(chunk.last_allocated(), Some(18));
+        assert_eq!(BitmapChunk { bitmap: vec![0], ..chunk }.last_allocated(), None);
+    }
+}
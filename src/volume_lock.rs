@@ -0,0 +1,49 @@
+use crate::bindings::{
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_WRITE,
+    Windows::Win32::System::SystemServices::FSCTL_DISMOUNT_VOLUME,
+    Windows::Win32::System::SystemServices::FSCTL_LOCK_VOLUME,
+    Windows::Win32::System::SystemServices::FSCTL_UNLOCK_VOLUME,
+};
+use crate::error::WinPartitionError;
+use crate::win_api::{open_device, volume_device_path, DeviceHandle};
+
+/// Exclusive lock on a volume taken with [FSCTL_LOCK_VOLUME](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_lock_volume).
+/// While the lock is held no other process can open files on the volume. The volume is unlocked when the lock is dropped
+pub struct VolumeLock {
+    device: DeviceHandle,
+}
+
+impl VolumeLock {
+    /// Opens and locks a volume. Fails if any file on the volume is open.
+    ///
+    /// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Requires administrator rights.
+    ///
+    /// Minimum OS: Windows XP/Windows Server 2003
+    pub fn lock(lprootpathname: String) -> Result<VolumeLock, WinPartitionError> {
+        let device = open_device(
+            &volume_device_path(&lprootpathname),
+            FILE_GENERIC_READ | FILE_GENERIC_WRITE)?;
+        device.control("FSCTL_LOCK_VOLUME", FSCTL_LOCK_VOLUME)?;
+
+        Ok(VolumeLock { device })
+    }
+
+    /// Dismounts the locked volume by calling [FSCTL_DISMOUNT_VOLUME](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_dismount_volume).
+    /// The file system is mounted again on the next access after the lock is released
+    pub fn dismount(&self) -> Result<(), WinPartitionError> {
+        self.device.control("FSCTL_DISMOUNT_VOLUME", FSCTL_DISMOUNT_VOLUME)
+    }
+
+    /// Handle of the locked volume, for raw access while the lock is held
+    pub(crate) fn device(&self) -> &DeviceHandle {
+        &self.device
+    }
+}
+
+impl Drop for VolumeLock {
+    fn drop(&mut self) {
+        // Closing the handle releases the lock too, so a failure here isn't fatal
+        let _ = self.device.control("FSCTL_UNLOCK_VOLUME", FSCTL_UNLOCK_VOLUME);
+    }
+}
@@ -1,29 +1,370 @@
-use std::io::{Error};
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::convert::TryFrom;
+use std::ffi::{c_void, OsStr};
+use std::fmt;
+use std::mem::size_of;
+use std::ptr::null_mut;
+use std::str::FromStr;
 
+use bitflags::bitflags;
+
+use crate::error::WinPartitionError;
+use crate::wstr::{to_wide_null, vec_u16_to_string};
 use crate::bindings::{
+    Windows::Win32::Foundation::CloseHandle,
+    Windows::Win32::Foundation::HANDLE,
     Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::NetworkManagement::WNet::WNetGetConnectionW,
+    Windows::Win32::Storage::FileSystem::CreateFileW,
+    Windows::Win32::Storage::FileSystem::DeleteVolumeMountPointW,
+    Windows::Win32::Storage::FileSystem::FILE_ACCESS_FLAGS,
+    Windows::Win32::Storage::FileSystem::FILE_FLAG_BACKUP_SEMANTICS,
+    Windows::Win32::Storage::FileSystem::FILE_FLAG_NO_BUFFERING,
+    Windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES,
+    Windows::Win32::Storage::FileSystem::FILE_GENERIC_READ,
+    Windows::Win32::Storage::FileSystem::FILE_SHARE_READ,
+    Windows::Win32::Storage::FileSystem::FILE_SHARE_WRITE,
+    Windows::Win32::Storage::FileSystem::OPEN_EXISTING,
+    Windows::Win32::Storage::FileSystem::QueryDosDeviceW,
+    Windows::Win32::Storage::FileSystem::ReadFile,
+    Windows::Win32::Storage::FileSystem::VOLUME_DISK_EXTENTS,
+    Windows::Win32::Storage::FileSystem::FindFirstVolumeW,
+    Windows::Win32::Storage::FileSystem::FindNextVolumeW,
+    Windows::Win32::Storage::FileSystem::FindVolumeClose,
     Windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW,
+    Windows::Win32::Storage::FileSystem::GetDiskFreeSpaceW,
     Windows::Win32::Storage::FileSystem::GetDriveTypeW,
+    Windows::Win32::Storage::FileSystem::GetFileAttributesW,
+    Windows::Win32::Storage::FileSystem::GetLogicalDriveStringsW,
     Windows::Win32::Storage::FileSystem::GetLogicalDrives,
     Windows::Win32::Storage::FileSystem::GetVolumeInformationW,
+    Windows::Win32::Storage::FileSystem::GetVolumeNameForVolumeMountPointW,
+    Windows::Win32::Storage::FileSystem::GetVolumePathNameW,
+    Windows::Win32::Storage::FileSystem::GetVolumePathNamesForVolumeNameW,
+    Windows::Win32::Storage::FileSystem::SetVolumeLabelW,
+    Windows::Win32::Storage::FileSystem::SetVolumeMountPointW,
+    Windows::Win32::System::SystemServices::DeviceIoControl,
+    Windows::Win32::System::SystemServices::FSCTL_IS_VOLUME_DIRTY,
+    Windows::Win32::System::SystemServices::IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS,
+    Windows::Win32::System::SystemServices::OVERLAPPED,
+    Windows::Win32::System::SystemServices::VOLUME_IS_DIRTY,
+    Windows::Win32::System::Registry::HKEY_LOCAL_MACHINE,
+    Windows::Win32::System::Registry::RegGetValueW,
+    Windows::Win32::System::Registry::RRF_RT_REG_DWORD,
+    Windows::Win32::System::Registry::RRF_RT_REG_MULTI_SZ,
+    Windows::Win32::System::Registry::RRF_RT_REG_SZ,
+    Windows::Win32::System::SystemInformation::GetSystemWindowsDirectoryW,
 };
+#[cfg(feature = "dangerous")]
+use crate::bindings::Windows::Win32::Storage::FileSystem::WriteFile;
+
+/// Win32 error code returned when an enumeration has no more items
+const ERROR_NO_MORE_FILES: i32 = 18;
+/// Win32 error code for "The program issued a command but the command length is incorrect"
+const ERROR_BAD_LENGTH: i32 = 24;
+/// Win32 error code returned when a buffer is too small for the result
+const ERROR_INSUFFICIENT_BUFFER: i32 = 122;
+/// Win32 error code for "The filename, directory name, or volume label syntax is incorrect"
+const ERROR_INVALID_NAME: i32 = 123;
+/// Win32 error code for "The filename or extension is too long"
+const ERROR_FILENAME_EXCED_RANGE: i32 = 206;
+/// Win32 error code returned when a buffer is too small to hold the whole result
+const ERROR_MORE_DATA: i32 = 234;
+/// Largest output buffer [DeviceHandle::query_variable] grows to, in bytes
+const MAX_IO_CONTROL_OUTPUT: usize = 64 * 1024 * 1024;
+/// `MAX_PATH` of `minwindef.h`
+const MAX_PATH: usize = 260;
+/// Largest buffer tried for the names of [get_volume_information], the length limit of a `UNICODE_STRING`
+const MAX_VOLUME_INFORMATION_LENGTH: usize = 32768;
+
+/// Copies a null-terminated string owned by the system, e.g. one pointed to from a buffer filled by an API
+pub(crate) unsafe fn pwstr_to_string(string: PWSTR) -> String {
+    let mut length = 0;
+    while *string.0.add(length) != 0 {
+        length += 1;
+    }
+    String::from_utf16_lossy(std::slice::from_raw_parts(string.0, length))
+}
+
+/// Splits a null separated list of strings ending with an empty string (`REG_MULTI_SZ` style) into a vector
+pub(crate) fn multi_sz_to_vec(vec: &[u16]) -> Vec<String> {
+    vec.split(|item| *item == 0)
+        .take_while(|item| !item.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect()
+}
+
+/// Owned handle to a device or volume opened by [open_device], closed on drop
+pub(crate) struct DeviceHandle {
+    handle: HANDLE,
+    path: String,
+}
+
+impl DeviceHandle {
+    /// Raw handle, valid as long as `self` lives
+    pub(crate) fn handle(&self) -> HANDLE {
+        self.handle
+    }
 
-/// Creates Rust String from vector u16
-fn vec_u16_to_string(vec: &Vec<u16>) -> String {
-    let mut index = 0;
-    for item in 0..vec.len() {
-        if vec[item] == 0 {
-            break;
+    /// Path the handle was opened with
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Calls [DeviceIoControl](https://docs.microsoft.com/en-us/windows/win32/api/ioapiset/nf-ioapiset-deviceiocontrol)
+    /// and returns number of bytes written to the output buffer. `name` is the control code name used in errors
+    ///
+    /// # Safety
+    /// Buffers must be valid for the given sizes and match the layout expected by `code`
+    pub(crate) unsafe fn io_control(
+        &self,
+        name: &'static str,
+        code: u32,
+        input: *mut c_void,
+        input_size: u32,
+        output: *mut c_void,
+        output_size: u32,
+    ) -> Result<u32, WinPartitionError> {
+        let mut lpbytesreturned: u32 = 0;
+        let result = DeviceIoControl(
+            self.handle,
+            code,
+            input,
+            input_size,
+            output,
+            output_size,
+            &mut lpbytesreturned,
+            null_mut()).as_bool();
+
+        if result {
+            Ok(lpbytesreturned)
+        } else {
+            Err(WinPartitionError::last_os_error(name, Some(&self.path)))
+        }
+    }
+
+    /// Sends a control code with no input and no output
+    pub(crate) fn control(&self, name: &'static str, code: u32) -> Result<(), WinPartitionError> {
+        unsafe { self.io_control(name, code, null_mut(), 0, null_mut(), 0)? };
+        Ok(())
+    }
+
+    /// Sends a control code with no input and a fixed size output structure
+    pub(crate) fn query<T: Default>(&self, name: &'static str, code: u32) -> Result<T, WinPartitionError> {
+        let mut output = T::default();
+        unsafe {
+            self.io_control(
+                name,
+                code,
+                null_mut(),
+                0,
+                &mut output as *mut T as *mut c_void,
+                size_of::<T>() as u32)?;
         }
-        index = item + 1;
+        Ok(output)
     }
-    String::from_utf16_lossy(&vec[0..index])
+
+    /// Sends a control code whose output has a variable length, growing the output buffer up to
+    /// [MAX_IO_CONTROL_OUTPUT] bytes until the result fits, past that the last error is returned.
+    /// The returned buffer is 8-byte aligned so it can be cast to the output structure
+    ///
+    /// # Safety
+    /// Input must be valid for `input_size` bytes and match the layout expected by `code`
+    pub(crate) unsafe fn query_variable(
+        &self,
+        name: &'static str,
+        code: u32,
+        input: *mut c_void,
+        input_size: u32,
+    ) -> Result<Vec<u64>, WinPartitionError> {
+        let mut buffer: Vec<u64> = vec![0; 512];
+        loop {
+            let result = self.io_control(
+                name,
+                code,
+                input,
+                input_size,
+                buffer.as_mut_ptr() as *mut c_void,
+                (buffer.len() * size_of::<u64>()) as u32);
+            match result {
+                Ok(_) => return Ok(buffer),
+                Err(err) if (err.raw_os_error() == ERROR_MORE_DATA
+                    || err.raw_os_error() == ERROR_INSUFFICIENT_BUFFER)
+                    && buffer.len() * size_of::<u64>() < MAX_IO_CONTROL_OUTPUT => {
+                    let len = buffer.len() * 2;
+                    buffer.resize(len, 0);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Calls [ReadFile](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-readfile) at byte
+    /// `offset` and returns the number of bytes read. Devices need sector-aligned offsets, sizes and buffers
+    pub(crate) fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<u32, WinPartitionError> {
+        let mut overlapped = offset_overlapped(offset);
+        let mut read: u32 = 0;
+        let result = unsafe {
+            ReadFile(
+                self.handle,
+                buffer.as_mut_ptr() as *mut c_void,
+                buffer.len() as u32,
+                &mut read,
+                &mut overlapped).as_bool()
+        };
+
+        if result {
+            Ok(read)
+        } else {
+            Err(WinPartitionError::last_os_error("ReadFile", Some(&self.path)))
+        }
+    }
+
+    /// Calls [WriteFile](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-writefile) at byte
+    /// `offset` and returns the number of bytes written. Devices need sector-aligned offsets, sizes and buffers
+    #[cfg(feature = "dangerous")]
+    pub(crate) fn write_at(&self, offset: u64, buffer: &[u8]) -> Result<u32, WinPartitionError> {
+        let mut overlapped = offset_overlapped(offset);
+        let mut written: u32 = 0;
+        let result = unsafe {
+            WriteFile(
+                self.handle,
+                buffer.as_ptr() as *const c_void,
+                buffer.len() as u32,
+                &mut written,
+                &mut overlapped).as_bool()
+        };
+
+        if result {
+            Ok(written)
+        } else {
+            Err(WinPartitionError::last_os_error("WriteFile", Some(&self.path)))
+        }
+    }
+}
+
+/// `OVERLAPPED` carrying the file offset of a synchronous read or write
+fn offset_overlapped(offset: u64) -> OVERLAPPED {
+    let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+    overlapped.Anonymous.Anonymous.Offset = offset as u32;
+    overlapped.Anonymous.Anonymous.OffsetHigh = (offset >> 32) as u32;
+    overlapped
+}
+
+/// Zeroed heap buffer whose address is a multiple of `alignment`, as needed for unbuffered and raw device I/O
+pub(crate) struct AlignedBuffer {
+    pointer: *mut u8,
+    len: usize,
+    layout: Layout,
+}
+
+impl AlignedBuffer {
+    /// `alignment` must be a power of two
+    pub(crate) fn new(len: usize, alignment: usize) -> AlignedBuffer {
+        let layout = Layout::from_size_align(len.max(1), alignment).expect("alignment is a power of two");
+        let pointer = unsafe { alloc_zeroed(layout) };
+        if pointer.is_null() {
+            handle_alloc_error(layout);
+        }
+        AlignedBuffer { pointer, len, layout }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.pointer, self.len) }
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.pointer, self.len) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.pointer, self.layout) };
+    }
+}
+
+impl Drop for DeviceHandle {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.handle) };
+    }
+}
+
+/// Opens a device such as `\\.\C:` or `\\.\PhysicalDrive0` by calling
+/// [CreateFileW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-createfilew).
+/// An `access` of zero is enough to query device properties without administrator rights
+pub(crate) fn open_device(
+    path: &str,
+    access: FILE_ACCESS_FLAGS,
+) -> Result<DeviceHandle, WinPartitionError> {
+    open_handle(path, access, FILE_FLAGS_AND_ATTRIBUTES(0))
+}
+
+/// Opens a file or directory such as `C:\` to query its information. Directories need `FILE_FLAG_BACKUP_SEMANTICS`
+pub(crate) fn open_file(
+    path: &str,
+    access: FILE_ACCESS_FLAGS,
+) -> Result<DeviceHandle, WinPartitionError> {
+    open_handle(path, access, FILE_FLAG_BACKUP_SEMANTICS)
+}
+
+/// Opens a device such as `\\.\C:` or `\\.\PhysicalDrive0` with `FILE_FLAG_NO_BUFFERING`, reading and writing
+/// it directly instead of through the cache. Offsets, lengths and buffer addresses must be multiples of the sector size
+pub(crate) fn open_unbuffered(
+    path: &str,
+    access: FILE_ACCESS_FLAGS,
+) -> Result<DeviceHandle, WinPartitionError> {
+    open_handle(path, access, FILE_FLAG_NO_BUFFERING)
+}
+
+fn open_handle(
+    path: &str,
+    access: FILE_ACCESS_FLAGS,
+    flags: FILE_FLAGS_AND_ATTRIBUTES,
+) -> Result<DeviceHandle, WinPartitionError> {
+    let handle = unsafe {
+        CreateFileW(
+            path,
+            access,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            null_mut(),
+            OPEN_EXISTING,
+            flags,
+            HANDLE(0))
+    };
+
+    if handle.0 == -1 {
+        Err(WinPartitionError::last_os_error("CreateFileW", Some(path)))
+    } else {
+        Ok(DeviceHandle { handle, path: path.to_string() })
+    }
+}
+
+/// Converts a root path (`C:\` or `\\?\Volume{...}\`) to the device path of the volume (`\\.\C:` or `\\?\Volume{...}`)
+pub(crate) fn volume_device_path(root_path: &str) -> String {
+    let path = root_path.trim_end_matches('\\');
+    if path.starts_with("\\\\") {
+        path.to_string()
+    } else {
+        format!("\\\\.\\{}", path)
+    }
+}
+
+/// `\\server\share` part of a UNC path such as `\\server\share\dir\file.txt`, `None` for other paths including
+/// device paths such as `\\?\Volume{...}\`
+pub(crate) fn unc_share_root(path: &str) -> Option<String> {
+    let mut parts = path.strip_prefix("\\\\")?.split('\\');
+    let server = parts.next().filter(|server| !server.is_empty() && *server != "?" && *server != ".")?;
+    let share = parts.next().filter(|share| !share.is_empty())?;
+    Some(format!("\\\\{}\\{}", server, share))
 }
 
 /// Defines different drive types according to [GetDriveTypeW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdrivetypew)
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DriveType {
     /// The drive type cannot be determined
+    #[default]
     DriveUnknown = 0,
     /// The root path is invalid; for example, there is no volume mounted at the specified path
     DriveNoRootDir = 1,
@@ -39,94 +380,404 @@ pub enum DriveType {
     DriveRamDisk = 6,
 }
 
-impl From<u32> for DriveType {
-    fn from(index: u32) -> Self {
+/// Error returned when a raw drive type code doesn't match any known [DriveType]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidDriveType(pub u32);
+
+impl fmt::Display for InvalidDriveType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid drive type code {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidDriveType {}
+
+impl TryFrom<u32> for DriveType {
+    type Error = InvalidDriveType;
+
+    fn try_from(index: u32) -> Result<Self, Self::Error> {
         match index {
-            0 => DriveType::DriveUnknown,
-            1 => DriveType::DriveNoRootDir,
-            2 => DriveType::DriveRemovable,
-            3 => DriveType::DriveFixed,
-            4 => DriveType::DriveRemote,
-            5 => DriveType::DriveCDRom,
-            6 => DriveType::DriveRamDisk,
-            _ => {
-                panic!("Invalid Drive Type")
-            }
+            0 => Ok(DriveType::DriveUnknown),
+            1 => Ok(DriveType::DriveNoRootDir),
+            2 => Ok(DriveType::DriveRemovable),
+            3 => Ok(DriveType::DriveFixed),
+            4 => Ok(DriveType::DriveRemote),
+            5 => Ok(DriveType::DriveCDRom),
+            6 => Ok(DriveType::DriveRamDisk),
+            _ => Err(InvalidDriveType(index)),
+        }
+    }
+}
+
+/// Formats as `Unknown`, `No root`, `Removable`, `Fixed`, `Network`, `CD-ROM` or `RAM disk`
+impl fmt::Display for DriveType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DriveType::DriveUnknown => "Unknown",
+            DriveType::DriveNoRootDir => "No root",
+            DriveType::DriveRemovable => "Removable",
+            DriveType::DriveFixed => "Fixed",
+            DriveType::DriveRemote => "Network",
+            DriveType::DriveCDRom => "CD-ROM",
+            DriveType::DriveRamDisk => "RAM disk",
+        };
+        f.pad(name)
+    }
+}
+
+/// Error returned when a string isn't the name of a [DriveType]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDriveTypeError(pub String);
+
+impl fmt::Display for ParseDriveTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid drive type {:?}, expected fixed, removable, network, cd-rom, ram-disk, no root or unknown",
+            self.0)
+    }
+}
+
+impl std::error::Error for ParseDriveTypeError {}
+
+/// Parses the names [DriveType] displays as, ignoring case, spaces, dashes and underscores, so `cd-rom`,
+/// `CDROM` and `CD-ROM` are all accepted. `remote`, `cd` and `ram` are accepted as well
+impl FromStr for DriveType {
+    type Err = ParseDriveTypeError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let name: String = value
+            .chars()
+            .filter(|char| !matches!(char, ' ' | '-' | '_'))
+            .map(|char| char.to_ascii_lowercase())
+            .collect();
+        match name.as_str() {
+            "unknown" => Ok(DriveType::DriveUnknown),
+            "noroot" | "norootdir" => Ok(DriveType::DriveNoRootDir),
+            "removable" => Ok(DriveType::DriveRemovable),
+            "fixed" => Ok(DriveType::DriveFixed),
+            "network" | "remote" => Ok(DriveType::DriveRemote),
+            "cdrom" | "cd" => Ok(DriveType::DriveCDRom),
+            "ramdisk" | "ram" => Ok(DriveType::DriveRamDisk),
+            _ => Err(ParseDriveTypeError(value.to_string())),
         }
     }
 }
 
+/// Serial number the file system assigned to a volume when it was formatted, reported by
+/// [GetVolumeInformationW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumeinformationw).
+/// Displays and parses as `XXXX-XXXX`, the form shown by `dir` and `vol`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeSerial(pub u32);
+
+impl fmt::Display for VolumeSerial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04X}-{:04X}", self.0 >> 16, self.0 & 0xffff)
+    }
+}
+
+/// Error returned when a string isn't a volume serial in `XXXX-XXXX` form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVolumeSerialError(pub String);
+
+impl fmt::Display for ParseVolumeSerialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid volume serial {:?}, expected XXXX-XXXX", self.0)
+    }
+}
+
+impl std::error::Error for ParseVolumeSerialError {}
+
+impl FromStr for VolumeSerial {
+    type Err = ParseVolumeSerialError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVolumeSerialError(value.to_string());
+        let (high, low) = value.split_once('-').ok_or_else(err)?;
+        let is_group = |group: &str| group.len() == 4 && group.chars().all(|char| char.is_ascii_hexdigit());
+        if !is_group(high) || !is_group(low) {
+            return Err(err());
+        }
+        let high = u16::from_str_radix(high, 16).map_err(|_| err())?;
+        let low = u16::from_str_radix(low, 16).map_err(|_| err())?;
+        Ok(VolumeSerial((high as u32) << 16 | low as u32))
+    }
+}
+
+bitflags! {
+    /// File system flags reported by [GetVolumeInformationW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumeinformationw).
+    /// Unknown bits are kept as is, so nothing the API returns gets lost.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct FileSystemFlags: u32 {
+        /// The file system supports case-sensitive file names
+        const FILE_CASE_SENSITIVE_SEARCH = 0x0000_0001;
+        /// The file system preserves the case of file names when it places a name on disk
+        const FILE_CASE_PRESERVED_NAMES = 0x0000_0002;
+        /// The file system supports Unicode in file names as they appear on disk
+        const FILE_UNICODE_ON_DISK = 0x0000_0004;
+        /// The file system preserves and enforces access control lists (ACL)
+        const FILE_PERSISTENT_ACLS = 0x0000_0008;
+        /// The file system supports file-based compression
+        const FILE_FILE_COMPRESSION = 0x0000_0010;
+        /// The file system supports disk quotas
+        const FILE_VOLUME_QUOTAS = 0x0000_0020;
+        /// The file system supports sparse files
+        const FILE_SUPPORTS_SPARSE_FILES = 0x0000_0040;
+        /// The file system supports reparse points
+        const FILE_SUPPORTS_REPARSE_POINTS = 0x0000_0080;
+        /// The file system supports remote storage
+        const FILE_SUPPORTS_REMOTE_STORAGE = 0x0000_0100;
+        /// On a successful cleanup operation, the file system returns information describing additional actions taken
+        const FILE_RETURNS_CLEANUP_RESULT_INFO = 0x0000_0200;
+        /// The file system supports POSIX-style delete and rename operations
+        const FILE_SUPPORTS_POSIX_UNLINK_RENAME = 0x0000_0400;
+        /// The specified volume is a compressed volume, for example, a DoubleSpace volume
+        const FILE_VOLUME_IS_COMPRESSED = 0x0000_8000;
+        /// The file system supports object identifiers
+        const FILE_SUPPORTS_OBJECT_IDS = 0x0001_0000;
+        /// The file system supports the Encrypted File System (EFS)
+        const FILE_SUPPORTS_ENCRYPTION = 0x0002_0000;
+        /// The file system supports named streams
+        const FILE_NAMED_STREAMS = 0x0004_0000;
+        /// The specified volume is read-only
+        const FILE_READ_ONLY_VOLUME = 0x0008_0000;
+        /// The specified volume supports a single sequential write
+        const FILE_SEQUENTIAL_WRITE_ONCE = 0x0010_0000;
+        /// The specified volume supports transactions
+        const FILE_SUPPORTS_TRANSACTIONS = 0x0020_0000;
+        /// The specified volume supports hard links
+        const FILE_SUPPORTS_HARD_LINKS = 0x0040_0000;
+        /// The specified volume supports extended attributes
+        const FILE_SUPPORTS_EXTENDED_ATTRIBUTES = 0x0080_0000;
+        /// The file system supports open by FileID
+        const FILE_SUPPORTS_OPEN_BY_FILE_ID = 0x0100_0000;
+        /// The specified volume supports update sequence number (USN) journals
+        const FILE_SUPPORTS_USN_JOURNAL = 0x0200_0000;
+        /// The file system supports integrity streams
+        const FILE_SUPPORTS_INTEGRITY_STREAMS = 0x0400_0000;
+        /// The specified volume supports sharing logical clusters between files on the same volume
+        const FILE_SUPPORTS_BLOCK_REFCOUNTING = 0x0800_0000;
+        /// The file system tracks whether each cluster of a file contains valid data
+        const FILE_SUPPORTS_SPARSE_VDL = 0x1000_0000;
+        /// The specified volume is a direct access (DAX) volume
+        const FILE_DAX_VOLUME = 0x2000_0000;
+    }
+}
+
+impl FileSystemFlags {
+    /// The file system supports case-sensitive file names
+    pub fn is_case_sensitive(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_CASE_SENSITIVE_SEARCH)
+    }
+
+    /// The file system supports file-based compression
+    pub fn supports_compression(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_FILE_COMPRESSION)
+    }
+
+    /// The volume itself is compressed
+    pub fn is_compressed(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_VOLUME_IS_COMPRESSED)
+    }
+
+    /// The file system supports the Encrypted File System (EFS)
+    pub fn supports_encryption(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_SUPPORTS_ENCRYPTION)
+    }
+
+    /// The file system supports disk quotas
+    pub fn supports_quotas(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_VOLUME_QUOTAS)
+    }
+
+    /// The file system supports sparse files
+    pub fn supports_sparse_files(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_SUPPORTS_SPARSE_FILES)
+    }
+
+    /// The file system preserves and enforces access control lists
+    pub fn supports_acls(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_PERSISTENT_ACLS)
+    }
+
+    /// The file system supports hard links
+    pub fn supports_hard_links(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_SUPPORTS_HARD_LINKS)
+    }
+
+    /// The volume supports update sequence number (USN) journals
+    pub fn supports_usn_journal(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_SUPPORTS_USN_JOURNAL)
+    }
+
+    /// The volume is read-only
+    pub fn is_read_only(&self) -> bool {
+        self.contains(FileSystemFlags::FILE_READ_ONLY_VOLUME)
+    }
+}
+
 /// Use [GetVolumeInformationW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumeinformationw) API function
-/// and returns tuple of (volume name, file system name,volume serial, max length, file system flags)
+/// and returns tuple of (volume name, file system name, volume serial, max length, file system flags)
+///
+/// `lprootpathname` is a root path such as `C:\`, given as `&str`, `&Path`, `OsString` or the like. The name
+/// buffers start at the documented `MAX_PATH + 1` and grow when a file system driver needs more
 ///
 /// Minimum OS Version: Windows XP/Windows Server 2003
 pub fn get_volume_information(
-    lprootpathname: String
-) -> Result<(String, String, u32, u32, u32), Error> {
-    // Maximum Volume name length is 32 characters which is equivalent to 64 unicode bytes
-    let mut volume_name_buf: Vec<u16> = Vec::with_capacity(64);
-    volume_name_buf.resize(64, 0);
+    lprootpathname: impl AsRef<OsStr>
+) -> Result<(String, String, VolumeSerial, u32, FileSystemFlags), WinPartitionError> {
+    let lprootpathname = lprootpathname.as_ref();
+    let display_name = lprootpathname.to_string_lossy();
+    let mut root_path_buf = to_wide_null(lprootpathname)
+        .map_err(|_| WinPartitionError::from_code("GetVolumeInformationW", Some(&display_name), ERROR_INVALID_NAME))?;
+    // The documented buffer size is MAX_PATH + 1, drivers of exotic file systems may need more
+    let mut buffer_length = MAX_PATH + 1;
+    loop {
+        let mut volume_name_buf: Vec<u16> = vec![0; buffer_length];
+        let mut file_system_name_buf: Vec<u16> = vec![0; buffer_length];
+        let mut lpvolumeserialnumber: u32 = 0;
+        let mut lpmaximumcomponentlength: u32 = 0;
+        let mut lpfilesystemflags: u32 = 0;
+        let result = unsafe {
+            GetVolumeInformationW(
+                PWSTR(root_path_buf.as_mut_ptr()),
+                PWSTR(volume_name_buf.as_mut_ptr()),
+                volume_name_buf.len() as u32,
+                &mut lpvolumeserialnumber,
+                &mut lpmaximumcomponentlength,
+                &mut lpfilesystemflags,
+                PWSTR(file_system_name_buf.as_mut_ptr()),
+                file_system_name_buf.len() as u32).as_bool()
+        };
 
-    let mut file_system_name_buf: Vec<u16> = Vec::with_capacity(255);
-    file_system_name_buf.resize(255, 0);
+        let too_small = if result {
+            // Some drivers fill the buffer without the null terminator instead of failing
+            is_unterminated(&volume_name_buf) || is_unterminated(&file_system_name_buf)
+        } else {
+            let err = WinPartitionError::last_os_error("GetVolumeInformationW", Some(&display_name));
+            if !is_buffer_too_small(err.raw_os_error()) || buffer_length >= MAX_VOLUME_INFORMATION_LENGTH {
+                return Err(err);
+            }
+            true
+        };
+        if too_small && buffer_length < MAX_VOLUME_INFORMATION_LENGTH {
+            buffer_length = (buffer_length * 2).min(MAX_VOLUME_INFORMATION_LENGTH);
+            continue;
+        }
+
+        return Ok((
+            vec_u16_to_string(&volume_name_buf),
+            vec_u16_to_string(&file_system_name_buf),
+            VolumeSerial(lpvolumeserialnumber),
+            lpmaximumcomponentlength,
+            FileSystemFlags::from_bits_retain(lpfilesystemflags)));
+    }
+}
 
-    let pwstr_volume_name: PWSTR = PWSTR(volume_name_buf.as_mut_ptr());
-    let pwstr_file_system_name: PWSTR = PWSTR(file_system_name_buf.as_mut_ptr());
+/// Whether a failed call reported a buffer too small for its result. APIs without a length output report it
+/// with differing codes
+fn is_buffer_too_small(code: i32) -> bool {
+    matches!(code, ERROR_BAD_LENGTH | ERROR_INSUFFICIENT_BUFFER | ERROR_FILENAME_EXCED_RANGE | ERROR_MORE_DATA)
+}
 
-    let mut lpvolumeserialnumber: u32 = 0;
-    let mut lpmaximumcomponentlength: u32 = 0;
-    let mut lpfilesystemflags: u32 = 0;
+/// Whether an API filled the whole buffer without a null terminator, i.e. the string may be truncated
+fn is_unterminated(buffer: &[u16]) -> bool {
+    !buffer.contains(&0)
+}
+
+/// Calls [SetVolumeLabelW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-setvolumelabelw)
+/// Windows API and sets the name of the volume mounted at `letter`. An empty `name` deletes the label.
+/// Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn set_volume_label(letter: char, name: String) -> Result<(), WinPartitionError> {
+    let lprootpathname = format!("{}:\\", letter);
     let result = unsafe {
-        GetVolumeInformationW(
-            lprootpathname,
-            pwstr_volume_name,
-            volume_name_buf.capacity() as u32,
-            &mut lpvolumeserialnumber,
-            &mut lpmaximumcomponentlength,
-            &mut lpfilesystemflags,
-            pwstr_file_system_name,
-            file_system_name_buf.capacity() as u32).as_bool()
+        if name.is_empty() {
+            SetVolumeLabelW(lprootpathname.as_str(), PWSTR(null_mut())).as_bool()
+        } else {
+            SetVolumeLabelW(lprootpathname.as_str(), name.as_str()).as_bool()
+        }
     };
 
     if result {
-        let result_volume_name = vec_u16_to_string(&volume_name_buf);
-        let result_volume_system_name = vec_u16_to_string(&file_system_name_buf);
-        Ok((result_volume_name, result_volume_system_name, lpvolumeserialnumber, lpmaximumcomponentlength, lpfilesystemflags))
+        Ok(())
     } else {
-        Err(Error::last_os_error())
+        Err(WinPartitionError::last_os_error("SetVolumeLabelW", Some(&lprootpathname)))
     }
 }
 
 /// Get drive type by calling [GetDriveTypeW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdrivetypew)
-/// API function.
+/// API function. Codes unknown to this crate are reported as [DriveType::DriveUnknown].
 ///
 /// Minimum OS: Windows XP/Windows Server 2003
 pub fn get_drive_type(
-    lprootpathname: String,
+    lprootpathname: impl AsRef<OsStr>,
 ) -> DriveType {
+    let mut root_path_buf = match to_wide_null(lprootpathname) {
+        Ok(root_path_buf) => root_path_buf,
+        Err(_) => return DriveType::DriveNoRootDir,
+    };
     let result = unsafe {
         GetDriveTypeW(
-            lprootpathname
+            PWSTR(root_path_buf.as_mut_ptr())
         )
     };
 
-    DriveType::from(result)
+    DriveType::try_from(result).unwrap_or(DriveType::DriveUnknown)
+}
+
+/// Calls [WNetGetConnectionW](https://docs.microsoft.com/en-us/windows/win32/api/winnetwk/nf-winnetwk-wnetgetconnectionw)
+/// Windows API function and returns the remote UNC path (e.g. `\\server\share`) a network drive letter is mapped to
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_network_connection(letter: char) -> Result<String, WinPartitionError> {
+    let lplocalname = format!("{}:", letter);
+    let mut lpnlength: u32 = 261;
+    loop {
+        let mut remote_name_buf: Vec<u16> = vec![0; lpnlength as usize];
+        let result = unsafe {
+            WNetGetConnectionW(
+                lplocalname.as_str(),
+                PWSTR(remote_name_buf.as_mut_ptr()),
+                &mut lpnlength)
+        };
+
+        match result as i32 {
+            0 => return Ok(vec_u16_to_string(&remote_name_buf)),
+            ERROR_MORE_DATA => continue,
+            code => return Err(WinPartitionError::from_code("WNetGetConnectionW", Some(&lplocalname), code)),
+        }
+    }
 }
 
 /// Calls [GetDiskFreeSpaceW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdiskfreespacew)
 /// Windows API and returns tuple of (free bytes available to caller, total number of bytes, total number of free bytes)
 ///
+/// `lpdirectoryname` is any directory of the volume, such as `C:\` or a UNC path such as `\\server\share\`,
+/// given as `&str`, `&Path`, `OsString` or the like.
+/// The trailing backslash the API requires for UNC paths is added if missing.
+///
 /// Minimum OS: Windows XP/Windows Server 2003
 pub fn get_disk_free_space(
-    lpdirectoryname: String
-) -> Result<(u64, u64, u64), Error> {
+    lpdirectoryname: impl AsRef<OsStr>
+) -> Result<(u64, u64, u64), WinPartitionError> {
+    let lpdirectoryname = lpdirectoryname.as_ref();
+    let display_name = lpdirectoryname.to_string_lossy();
+    let mut directory_name_buf = to_wide_null(lpdirectoryname)
+        .map_err(|_| WinPartitionError::from_code("GetDiskFreeSpaceExW", Some(&display_name), ERROR_INVALID_NAME))?;
+    if unc_share_root(&display_name).is_some() && !display_name.ends_with('\\') {
+        directory_name_buf.insert(directory_name_buf.len() - 1, '\\' as u16);
+    }
     let mut lpfreebytesavailabletocaller: u64 = 0;
     let mut lptotalnumberofbytes: u64 = 0;
     let mut lptotalnumberoffreebytes: u64 = 0;
     let result =
         unsafe {
             GetDiskFreeSpaceExW(
-                lpdirectoryname,
+                PWSTR(directory_name_buf.as_mut_ptr()),
                 &mut lpfreebytesavailabletocaller,
                 &mut lptotalnumberofbytes,
                 &mut lptotalnumberoffreebytes).as_bool()
@@ -135,28 +786,510 @@ pub fn get_disk_free_space(
     if result {
         Ok((lpfreebytesavailabletocaller, lptotalnumberofbytes, lptotalnumberoffreebytes))
     } else {
-        Err(Error::last_os_error())
+        Err(WinPartitionError::last_os_error("GetDiskFreeSpaceExW", Some(&display_name)))
+    }
+}
+
+/// Calls [GetDiskFreeSpaceW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdiskfreespacew)
+/// Windows API and returns tuple of (sectors per cluster, bytes per sector, number of free clusters, total number of clusters).
+///
+/// Cluster counts are limited to 32 bits, so use [get_disk_free_space] for sizes of large volumes.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_disk_free_space_basic(
+    lprootpathname: String
+) -> Result<(u32, u32, u32, u32), WinPartitionError> {
+    let mut lpsectorspercluster: u32 = 0;
+    let mut lpbytespersector: u32 = 0;
+    let mut lpnumberoffreeclusters: u32 = 0;
+    let mut lptotalnumberofclusters: u32 = 0;
+    let result =
+        unsafe {
+            GetDiskFreeSpaceW(
+                lprootpathname.as_str(),
+                &mut lpsectorspercluster,
+                &mut lpbytespersector,
+                &mut lpnumberoffreeclusters,
+                &mut lptotalnumberofclusters).as_bool()
+        };
+
+    if result {
+        Ok((lpsectorspercluster, lpbytespersector, lpnumberoffreeclusters, lptotalnumberofclusters))
+    } else {
+        Err(WinPartitionError::last_os_error("GetDiskFreeSpaceW", Some(&lprootpathname)))
     }
 }
 
 /// Calls [GetLogicalDrives](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getlogicaldrives) Windows API function
-/// and returns Vector of drive letters
-pub fn get_logical_drive() -> Result<Vec<char>, Error> {
+/// and returns Vector of drive letters. See [get_logical_drive_strings] for their root paths
+pub fn get_logical_drive() -> Result<Vec<char>, WinPartitionError> {
     let bitmask = unsafe { GetLogicalDrives() };
     if bitmask == 0 {
-        Err(Error::last_os_error())
+        Err(WinPartitionError::last_os_error("GetLogicalDrives", None))
     } else {
-        let mut mask = 1;
-        let mut result: Vec<char> = vec![];
+        Ok(drive_mask_to_letters(bitmask))
+    }
+}
+
+/// Calls [GetLogicalDriveStringsW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getlogicaldrivestringsw)
+/// Windows API function and returns Vector of the root paths of the drives (e.g. `C:\`), in the form the
+/// other functions of this module take
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_logical_drive_strings() -> Result<Vec<String>, WinPartitionError> {
+    // Room for all 26 drives of `X:\` and the list terminator
+    let mut buffer_length: u32 = 26 * 4 + 1;
+    loop {
+        let mut drive_strings_buf: Vec<u16> = vec![0; buffer_length as usize];
+        let result = unsafe {
+            GetLogicalDriveStringsW(drive_strings_buf.len() as u32, PWSTR(drive_strings_buf.as_mut_ptr()))
+        };
+
+        match result {
+            0 => return Err(WinPartitionError::last_os_error("GetLogicalDriveStringsW", None)),
+            // Too small buffers get the length needed, without the terminating null
+            length if length as usize >= drive_strings_buf.len() => buffer_length = length + 1,
+            _ => return Ok(multi_sz_to_vec(&drive_strings_buf)),
+        }
+    }
+}
+
+/// Converts a bitmask of drives, where bit 0 is drive A, to drive letters
+pub(crate) fn drive_mask_to_letters(bitmask: u32) -> Vec<char> {
+    let mut mask = 1;
+    let mut result: Vec<char> = vec![];
+
+    for index in 1..=26 {
+        if mask & bitmask == mask {
+            let char = std::char::from_u32(index + 64);
+            result.push(char.unwrap());
+        }
+        mask <<= 1;
+    }
 
-        for index in 1..=26 {
-            if mask & bitmask == mask {
-                let char = std::char::from_u32(index + 64);
-                result.push(char.unwrap());
+    result
+}
+
+/// Calls [FindFirstVolumeW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findfirstvolumew)
+/// and [FindNextVolumeW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findnextvolumew) Windows API functions
+/// and returns Vector of volume GUID paths (e.g. `\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\`).
+///
+/// Unlike [get_logical_drive], this also returns volumes without a drive letter such as
+/// EFI, recovery or folder-mounted volumes.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn enumerate_volumes() -> Result<Vec<String>, WinPartitionError> {
+    // Volume GUID paths are 49 characters long, MAX_PATH is plenty
+    let mut volume_name_buf: Vec<u16> = vec![0; 261];
+    let handle = unsafe {
+        FindFirstVolumeW(
+            PWSTR(volume_name_buf.as_mut_ptr()),
+            volume_name_buf.len() as u32)
+    };
+    if handle.0 == -1 {
+        return Err(WinPartitionError::last_os_error("FindFirstVolumeW", None));
+    }
+
+    let mut result: Vec<String> = vec![vec_u16_to_string(&volume_name_buf)];
+    loop {
+        let found = unsafe {
+            FindNextVolumeW(
+                handle,
+                PWSTR(volume_name_buf.as_mut_ptr()),
+                volume_name_buf.len() as u32).as_bool()
+        };
+        if !found {
+            let err = WinPartitionError::last_os_error("FindNextVolumeW", None);
+            unsafe { FindVolumeClose(handle) };
+            if err.raw_os_error() == ERROR_NO_MORE_FILES {
+                break;
             }
-            mask = mask << 1;
+            return Err(err);
         }
+        result.push(vec_u16_to_string(&volume_name_buf));
+    }
 
+    Ok(result)
+}
+
+/// Calls [GetVolumePathNamesForVolumeNameW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumepathnamesforvolumenamew)
+/// Windows API function and returns Vector of drive letters and mounted folders of a volume (e.g. `C:\` or `C:\Mount\Data\`)
+///
+/// `lpszvolumename` is a volume GUID path as returned by [enumerate_volumes].
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_volume_path_names(
+    lpszvolumename: String
+) -> Result<Vec<String>, WinPartitionError> {
+    let mut lpcchreturnlength: u32 = 261;
+    loop {
+        let mut path_names_buf: Vec<u16> = vec![0; lpcchreturnlength as usize];
+        let result = unsafe {
+            GetVolumePathNamesForVolumeNameW(
+                lpszvolumename.as_str(),
+                PWSTR(path_names_buf.as_mut_ptr()),
+                path_names_buf.len() as u32,
+                &mut lpcchreturnlength).as_bool()
+        };
+
+        if result {
+            return Ok(multi_sz_to_vec(&path_names_buf));
+        }
+        let err = WinPartitionError::last_os_error("GetVolumePathNamesForVolumeNameW", Some(&lpszvolumename));
+        if err.raw_os_error() != ERROR_MORE_DATA {
+            return Err(err);
+        }
+    }
+}
+
+/// Calls [GetVolumeNameForVolumeMountPointW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumenameforvolumemountpointw)
+/// Windows API function and returns volume GUID path of the volume mounted at `lpszvolumemountpoint`
+/// (e.g. `C:\` or `C:\Mount\Data\`)
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_volume_name_for_mount_point(
+    lpszvolumemountpoint: String
+) -> Result<String, WinPartitionError> {
+    // Volume GUID paths are 49 characters long, 50 is the documented buffer size
+    let mut volume_name_buf: Vec<u16> = vec![0; 50];
+    let result = unsafe {
+        GetVolumeNameForVolumeMountPointW(
+            lpszvolumemountpoint.as_str(),
+            PWSTR(volume_name_buf.as_mut_ptr()),
+            volume_name_buf.len() as u32).as_bool()
+    };
+
+    if result {
+        Ok(vec_u16_to_string(&volume_name_buf))
+    } else {
+        Err(WinPartitionError::last_os_error("GetVolumeNameForVolumeMountPointW", Some(&lpszvolumemountpoint)))
+    }
+}
+
+/// Calls [GetVolumePathNameW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumepathnamew)
+/// Windows API function and returns the mount point of the volume containing `lpszfilename`, e.g. `C:\`
+/// for `C:\Windows\notepad.exe` or `C:\Mount\Data\` for a file inside a folder mounted volume.
+/// The file doesn't need to exist.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_volume_path_name(
+    lpszfilename: String
+) -> Result<String, WinPartitionError> {
+    let mut volume_path_name_buf: Vec<u16> = vec![0; 261];
+    let result = unsafe {
+        GetVolumePathNameW(
+            lpszfilename.as_str(),
+            PWSTR(volume_path_name_buf.as_mut_ptr()),
+            volume_path_name_buf.len() as u32).as_bool()
+    };
+
+    if result {
+        Ok(vec_u16_to_string(&volume_path_name_buf))
+    } else {
+        Err(WinPartitionError::last_os_error("GetVolumePathNameW", Some(&lpszfilename)))
+    }
+}
+
+/// Win32 value returned by `GetFileAttributesW` on failure
+const INVALID_FILE_ATTRIBUTES: u32 = u32::MAX;
+
+/// Calls [GetFileAttributesW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getfileattributesw)
+/// Windows API function and returns the raw `FILE_ATTRIBUTE_*` flags of a file or directory, e.g.
+/// `FILE_ATTRIBUTE_COMPRESSED` (0x800)
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_file_attributes(lpfilename: String) -> Result<u32, WinPartitionError> {
+    let result = unsafe { GetFileAttributesW(lpfilename.as_str()) };
+
+    if result == INVALID_FILE_ATTRIBUTES {
+        Err(WinPartitionError::last_os_error("GetFileAttributesW", Some(&lpfilename)))
+    } else {
         Ok(result)
     }
 }
+
+/// Calls [GetSystemWindowsDirectoryW](https://docs.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-getsystemwindowsdirectoryw)
+/// Windows API function and returns the Windows directory of the running system, e.g. `C:\Windows`
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_system_windows_directory() -> Result<String, WinPartitionError> {
+    let mut buffer_buf: Vec<u16> = vec![0; 261];
+    let result = unsafe {
+        GetSystemWindowsDirectoryW(
+            PWSTR(buffer_buf.as_mut_ptr()),
+            buffer_buf.len() as u32)
+    };
+
+    if result == 0 {
+        Err(WinPartitionError::last_os_error("GetSystemWindowsDirectoryW", None))
+    } else {
+        Ok(vec_u16_to_string(&buffer_buf))
+    }
+}
+
+/// Reads a `REG_SZ` or `REG_MULTI_SZ` value of a key under `HKEY_LOCAL_MACHINE` by calling
+/// [RegGetValueW](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew),
+/// returning the raw null terminated UTF-16 data
+pub(crate) fn get_registry_value(lpsubkey: &str, lpvalue: &str) -> Result<Vec<u16>, WinPartitionError> {
+    let mut pcbdata: u32 = 522;
+    loop {
+        let mut data_buf: Vec<u16> = vec![0; pcbdata as usize / 2 + 1];
+        let result = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                lpsubkey,
+                lpvalue,
+                RRF_RT_REG_SZ | RRF_RT_REG_MULTI_SZ,
+                null_mut(),
+                data_buf.as_mut_ptr() as *mut c_void,
+                &mut pcbdata)
+        };
+
+        match result.0 {
+            0 => return Ok(data_buf),
+            ERROR_MORE_DATA => continue,
+            code => return Err(WinPartitionError::from_code("RegGetValueW", Some(lpsubkey), code)),
+        }
+    }
+}
+
+/// Reads a `REG_DWORD` value of a key under `HKEY_LOCAL_MACHINE` by calling
+/// [RegGetValueW](https://docs.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew)
+pub(crate) fn get_registry_dword(lpsubkey: &str, lpvalue: &str) -> Result<u32, WinPartitionError> {
+    let mut data: u32 = 0;
+    let mut pcbdata = size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            lpsubkey,
+            lpvalue,
+            RRF_RT_REG_DWORD,
+            null_mut(),
+            &mut data as *mut u32 as *mut c_void,
+            &mut pcbdata)
+    };
+
+    match result.0 {
+        0 => Ok(data),
+        code => Err(WinPartitionError::from_code("RegGetValueW", Some(lpsubkey), code)),
+    }
+}
+
+/// Assigns drive letter `letter` to a volume by calling
+/// [SetVolumeMountPointW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-setvolumemountpointw).
+/// The letter must not be in use.
+///
+/// `lpszvolumename` is a volume GUID path as returned by [enumerate_volumes]. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn assign_drive_letter(letter: char, lpszvolumename: String) -> Result<(), WinPartitionError> {
+    let lpszvolumemountpoint = format!("{}:\\", letter);
+    let result = unsafe {
+        SetVolumeMountPointW(
+            lpszvolumemountpoint.as_str(),
+            lpszvolumename.as_str()).as_bool()
+    };
+
+    if result {
+        Ok(())
+    } else {
+        Err(WinPartitionError::last_os_error("SetVolumeMountPointW", Some(&lpszvolumemountpoint)))
+    }
+}
+
+/// Removes drive letter `letter` from its volume by calling
+/// [DeleteVolumeMountPointW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-deletevolumemountpointw).
+/// The volume stays reachable through its volume GUID path. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn remove_drive_letter(letter: char) -> Result<(), WinPartitionError> {
+    let lpszvolumemountpoint = format!("{}:\\", letter);
+    let result = unsafe { DeleteVolumeMountPointW(lpszvolumemountpoint.as_str()).as_bool() };
+
+    if result {
+        Ok(())
+    } else {
+        Err(WinPartitionError::last_os_error("DeleteVolumeMountPointW", Some(&lpszvolumemountpoint)))
+    }
+}
+
+/// Moves the volume mounted at drive letter `from` to drive letter `to`. If assigning the new letter
+/// fails, the old letter is restored. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn change_drive_letter(from: char, to: char) -> Result<(), WinPartitionError> {
+    let volume_name = get_volume_name_for_mount_point(format!("{}:\\", from))?;
+    remove_drive_letter(from)?;
+    if let Err(err) = assign_drive_letter(to, volume_name.to_string()) {
+        let _ = assign_drive_letter(from, volume_name);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Calls [QueryDosDeviceW](https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-querydosdevicew)
+/// Windows API function and returns the targets of an MS-DOS device name such as `C:`,
+/// e.g. `\Device\HarddiskVolume3`. The first target is the current one
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn query_dos_device(
+    lpdevicename: String
+) -> Result<Vec<String>, WinPartitionError> {
+    let mut target_path_buf: Vec<u16> = vec![0; 261];
+    loop {
+        let result = unsafe {
+            QueryDosDeviceW(
+                lpdevicename.as_str(),
+                PWSTR(target_path_buf.as_mut_ptr()),
+                target_path_buf.len() as u32)
+        };
+
+        if result != 0 {
+            return Ok(multi_sz_to_vec(&target_path_buf[..result as usize]));
+        }
+        let err = WinPartitionError::last_os_error("QueryDosDeviceW", Some(&lpdevicename));
+        if err.raw_os_error() != ERROR_INSUFFICIENT_BUFFER {
+            return Err(err);
+        }
+        let len = target_path_buf.len() * 2;
+        target_path_buf.resize(len, 0);
+    }
+}
+
+/// Part of a volume stored on a physical disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskExtent {
+    /// Number of the physical disk, as in `\\.\PhysicalDriveN`
+    pub disk_number: u32,
+    /// Offset of the extent from the start of the disk in bytes
+    pub starting_offset: u64,
+    /// Length of the extent in bytes
+    pub extent_length: u64,
+}
+
+/// Calls [IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_volume_get_volume_disk_extents)
+/// and returns Vector of physical disk extents the volume is stored on. Spanned, striped and
+/// mirrored volumes have more than one extent.
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn get_volume_disk_extents(
+    lprootpathname: String
+) -> Result<Vec<DiskExtent>, WinPartitionError> {
+    let device = open_device(&volume_device_path(&lprootpathname), FILE_ACCESS_FLAGS(0))?;
+    let buffer = unsafe {
+        device.query_variable(
+            "IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS",
+            IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS,
+            null_mut(),
+            0)?
+    };
+
+    let extents = unsafe {
+        let header = &*(buffer.as_ptr() as *const VOLUME_DISK_EXTENTS);
+        std::slice::from_raw_parts(header.Extents.as_ptr(), header.NumberOfDiskExtents as usize)
+    };
+    Ok(extents.iter()
+        .map(|extent| DiskExtent {
+            disk_number: extent.DiskNumber,
+            starting_offset: extent.StartingOffset as u64,
+            extent_length: extent.ExtentLength as u64,
+        })
+        .collect())
+}
+
+/// Calls [FSCTL_IS_VOLUME_DIRTY](https://docs.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-fsctl_is_volume_dirty)
+/// and checks whether the volume is flagged to be checked by chkdsk at next boot.
+///
+/// `lprootpathname` is a root path such as `C:\` or a volume GUID path. Requires administrator rights.
+///
+/// Minimum OS: Windows XP/Windows Server 2003
+pub fn is_volume_dirty(lprootpathname: String) -> Result<bool, WinPartitionError> {
+    let device = open_device(&volume_device_path(&lprootpathname), FILE_GENERIC_READ)?;
+    let flags: u32 = device.query("FSCTL_IS_VOLUME_DIRTY", FSCTL_IS_VOLUME_DIRTY)?;
+
+    Ok(flags & VOLUME_IS_DIRTY != 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn drive_type_try_from_test() {
+        assert!(matches!(DriveType::try_from(3), Ok(DriveType::DriveFixed)));
+        assert_eq!(DriveType::try_from(7).unwrap_err(), InvalidDriveType(7));
+    }
+
+    #[test]
+    fn file_system_flags_test() {
+        // Typical NTFS volume
+        let flags = FileSystemFlags::from_bits_retain(0x03e7_00ff);
+        assert!(flags.supports_compression());
+        assert!(flags.supports_usn_journal());
+        assert!(!flags.is_read_only());
+        assert_eq!(flags.bits(), 0x03e7_00ff);
+    }
+
+    #[test]
+    fn drive_mask_to_letters_test() {
+        assert_eq!(drive_mask_to_letters(1 << 25 | 0b101), vec!['A', 'C', 'Z']);
+        assert!(drive_mask_to_letters(0).is_empty());
+    }
+
+    #[test]
+    fn drive_type_name_test() {
+        assert_eq!(DriveType::DriveCDRom.to_string(), "CD-ROM");
+        assert_eq!(format!("{:<8}|", DriveType::DriveRemote), "Network |");
+        assert_eq!("cdrom".parse::<DriveType>(), Ok(DriveType::DriveCDRom));
+        assert_eq!("RAM_DISK".parse::<DriveType>(), Ok(DriveType::DriveRamDisk));
+        assert_eq!("no root".parse::<DriveType>(), Ok(DriveType::DriveNoRootDir));
+        assert!("floppy".parse::<DriveType>().unwrap_err().to_string().contains("no root"));
+        for index in 0..=6 {
+            let drive_type = DriveType::try_from(index).unwrap();
+            assert_eq!(drive_type.to_string().parse::<DriveType>(), Ok(drive_type));
+        }
+    }
+
+    #[test]
+    fn volume_serial_test() {
+        assert_eq!(VolumeSerial(0x1a2b_03c4).to_string(), "1A2B-03C4");
+        assert_eq!("1a2b-03C4".parse::<VolumeSerial>(), Ok(VolumeSerial(0x1a2b_03c4)));
+        assert!("1A2B03C4".parse::<VolumeSerial>().is_err());
+        assert!("1A2B-3C4".parse::<VolumeSerial>().is_err());
+        assert!("+A2B-03C4".parse::<VolumeSerial>().is_err());
+    }
+
+    #[test]
+    fn volume_device_path_test() {
+        assert_eq!(volume_device_path("C:\\"), "\\\\.\\C:");
+        assert_eq!(volume_device_path("\\\\?\\Volume{1}\\"), "\\\\?\\Volume{1}");
+    }
+
+    #[test]
+    fn unc_share_root_test() {
+        assert_eq!(unc_share_root("\\\\fs01\\projects\\2024\\plan.docx").as_deref(), Some("\\\\fs01\\projects"));
+        assert_eq!(unc_share_root("\\\\fs01\\projects\\").as_deref(), Some("\\\\fs01\\projects"));
+        assert_eq!(unc_share_root("\\\\fs01"), None);
+        assert_eq!(unc_share_root("\\\\?\\Volume{1}\\"), None);
+        assert_eq!(unc_share_root("C:\\Users"), None);
+    }
+
+    #[test]
+    fn volume_information_buffer_test() {
+        assert!(is_buffer_too_small(ERROR_BAD_LENGTH) && is_buffer_too_small(ERROR_MORE_DATA));
+        assert!(!is_buffer_too_small(ERROR_INVALID_NAME));
+        assert!(is_unterminated(&[0x4e, 0x54, 0x46, 0x53]));
+        assert!(!is_unterminated(&[0x4e, 0x54, 0x46, 0x53, 0]));
+    }
+
+    #[test]
+    fn multi_sz_to_vec_test() {
+        let buf: Vec<u16> = "C:\\\0C:\\Mount\\\0\0\0".encode_utf16().collect();
+        assert_eq!(multi_sz_to_vec(&buf), vec!["C:\\", "C:\\Mount\\"]);
+        assert!(multi_sz_to_vec(&[0, 0]).is_empty());
+    }
+}
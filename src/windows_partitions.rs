@@ -1,81 +1,1074 @@
-use std::io::Error;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
+use crate::backend::{SystemBackend, WinApiBackend};
+use crate::byte_size::ByteSize;
+use crate::device_info::get_disk_device;
+use crate::dfs::{get_dfs_referral, DfsReferral};
+use crate::dynamic_disk::{get_volume_layout, VolumeLayout};
+use crate::error::WinPartitionError;
+use crate::file_system::FileSystem;
+use crate::offline_files::{get_offline_state, OfflineState};
+use crate::storage::{get_removable_media_kind, is_ssd, supports_trim, RemovableMediaKind, StorageBusType};
+use crate::storage_spaces::{get_storage_space, StorageSpace};
+use crate::system_volume::{get_boot_volume_letter, get_page_files, get_system_partition_device, is_efs_disabled};
 use crate::win_api::*;
 
+/// Win32 error code for "The system cannot find the path specified"
+const ERROR_PATH_NOT_FOUND: i32 = 3;
+/// Win32 error code for "The specified path is invalid"
+const ERROR_BAD_PATHNAME: i32 = 161;
+/// `FILE_ATTRIBUTE_COMPRESSED` flag of [get_file_attributes]
+const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+
 /// Provides information about a partition
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowsPartition {
     /// Drive letter assigned to partition
     pub letter: char,
     /// Indicate if partition is ready.
     /// For a CD-Rom drive this property indicates if CD was inserted
     pub ready: bool,
+    /// Why the partition isn't ready, [ReadyState::Ready] when `ready` is `true`
+    pub ready_state: ReadyState,
     /// Partition name
     pub name: String,
     /// Total size of partition in bytes
     pub size: u64,
     /// Free space in bytes
     pub free_space: u64,
+    /// Cluster size in bytes, 0 if it couldn't be read
+    pub allocation_unit_size: u32,
     /// Partition format name
     pub file_system_name: String,
+    /// Serial number assigned when the volume was formatted, `None` if it couldn't be read. Cheap and stable
+    /// enough to recognize a removable drive when it is inserted again, possibly with another drive letter
+    pub serial: Option<VolumeSerial>,
+    /// Longest file name component the file system supports, e.g. 255 for NTFS, 0 if it couldn't be read
+    pub max_component_length: u32,
+    /// Flags of the file system, such as its support for compression, case-sensitive names or quotas
+    pub file_system_flags: FileSystemFlags,
     /// Partition type
     pub drive_type: DriveType,
+    /// Physical disk extents the partition is stored on.
+    /// Empty for drives that aren't backed by a local disk, like network or CD-Rom drives
+    pub disk_extents: Vec<DiskExtent>,
+    /// Bus of the storage device the partition is stored on, if it is a local device
+    pub bus_type: Option<StorageBusType>,
+    /// Remote UNC path a network drive is mapped to, e.g. `\\server\share`
+    pub remote_path: Option<String>,
+    /// Native NT device path the drive letter links to, e.g. `\Device\HarddiskVolume3`.
+    /// For substituted drives this is the target directory, e.g. `\??\C:\Projects`
+    pub device_path: Option<String>,
+    /// Directory a drive created with `SUBST` or `DefineDosDevice` points to, e.g. `C:\Projects`
+    pub subst_target: Option<PathBuf>,
+    /// Whether the file system supports per-file compression, e.g. NTFS
+    pub supports_compression: bool,
+    /// Whether NTFS compression is enabled on the root directory, so new files are compressed by default
+    pub compressed: bool,
+    /// Drive letter and mounted folders of the volume, e.g. `C:\` and `C:\Mount\Data\`.
+    /// Empty for drives that aren't local volumes, like network or `subst` drives
+    pub mount_points: Vec<PathBuf>,
 }
 
-/// Gets list of system partitions or operating system error
-pub fn get_partitions() -> Result<Vec<WindowsPartition>, Error> {
-    let drives = get_logical_drive()?;
-    let mut result: Vec<WindowsPartition> = vec![];
-    for letter in drives {
-        let path = format!("{}:\\", letter);
-        let drive_type = get_drive_type(path.to_string());
-        let mut ready = true;
-        let mut name = "".to_string();
-        let mut total_size = 0;
-        let mut free_space = 0;
-        let mut file_system_name = "".to_string();
-        match get_disk_free_space(path.to_string()) {
+impl WindowsPartition {
+    /// Queries the partition mounted at drive letter `letter`, without enumerating the other drives.
+    ///
+    /// A drive without media is returned with `ready` set to `false`. Any other failure returns the first error met
+    pub fn from_letter(letter: char) -> Result<WindowsPartition, WinPartitionError> {
+        WindowsPartition::from_letter_with(&SystemBackend, letter)
+    }
+
+    /// Like [from_letter](WindowsPartition::from_letter), making the Windows API calls through `backend`
+    pub fn from_letter_with(backend: &dyn WinApiBackend, letter: char) -> Result<WindowsPartition, WinPartitionError> {
+        let letter = letter.to_ascii_uppercase();
+        let mut errors: Vec<WinPartitionError> = vec![];
+        let partition = query_partition(backend, letter, &mut errors);
+        if let DriveType::DriveNoRootDir = partition.drive_type {
+            let path = format!("{}:\\", letter);
+            return Err(WinPartitionError::from_code("GetDriveTypeW", Some(&path), ERROR_PATH_NOT_FOUND));
+        }
+
+        if errors.is_empty() {
+            Ok(partition)
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+
+    /// Re-queries readiness, free space and volume information of the partition in place,
+    /// e.g. to track a CD being inserted or removed. Returns the first error met, if any
+    pub fn refresh(&mut self) -> Result<(), WinPartitionError> {
+        let mut errors: Vec<WinPartitionError> = vec![];
+        self.query_state(&SystemBackend, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+
+    /// Reads the parts of the partition that change with media and usage,
+    /// pushing any error other than "not ready" to `errors`
+    fn query_state(&mut self, backend: &dyn WinApiBackend, errors: &mut Vec<WinPartitionError>) {
+        self.set_ready();
+        self.query_free_space(backend, errors);
+        self.query_volume_information(backend, errors);
+    }
+
+    /// Reads size, free space and cluster size, clearing `ready` on failure
+    fn query_free_space(&mut self, backend: &dyn WinApiBackend, errors: &mut Vec<WinPartitionError>) {
+        let path = format!("{}:\\", self.letter);
+        self.size = 0;
+        self.free_space = 0;
+        match backend.disk_free_space(&path) {
             Ok(value) => {
-                total_size = value.1;
-                free_space = value.2;
-            }
-            Err(_err) => {
-                ready = false;
-                // if err.raw_os_error().is_some() &&
-                //     err.raw_os_error().unwrap() == 21 {
-                //     ready = false;
-                // } else {
-                //     return Err(err);
-                // }
+                self.size = value.1;
+                self.free_space = value.2;
             }
+            Err(err) => self.record_failure(err, errors),
         };
-        match get_volume_information(path.to_string()) {
+        self.allocation_unit_size = backend.disk_free_space_basic(&path)
+            .map(|value| value.0 * value.1)
+            .unwrap_or(0);
+    }
+
+    /// Reads volume name, serial, file system name, flags and compression state, clearing `ready` on failure
+    fn query_volume_information(&mut self, backend: &dyn WinApiBackend, errors: &mut Vec<WinPartitionError>) {
+        let path = format!("{}:\\", self.letter);
+        self.name = "".to_string();
+        self.file_system_name = "".to_string();
+        self.serial = None;
+        self.max_component_length = 0;
+        self.file_system_flags = FileSystemFlags::empty();
+        self.supports_compression = false;
+        self.compressed = false;
+        match backend.volume_information(&path) {
             Ok(value) => {
-                name = value.0;
-                file_system_name = value.1;
+                self.name = value.0;
+                self.file_system_name = value.1;
+                self.serial = value.2;
+                self.max_component_length = value.3;
+                self.file_system_flags = value.4;
+                self.supports_compression = value.4.supports_compression();
+                self.compressed = self.supports_compression && backend.file_attributes(&path)
+                    .is_ok_and(|attributes| attributes & FILE_ATTRIBUTE_COMPRESSED != 0);
             }
-            Err(_err) => {
-                ready = false;
-                // if err.raw_os_error().is_some() &&
-                //     err.raw_os_error().unwrap() == 21 {
-                //     ready = false;
-                // } else {
-                //     return Err(err);
-                // }
+            Err(err) => self.record_failure(err, errors),
+        }
+    }
+
+    /// Probes readiness with the free space call, without populating the size fields
+    fn query_ready(&mut self, backend: &dyn WinApiBackend, errors: &mut Vec<WinPartitionError>) {
+        if let Err(err) = backend.disk_free_space(&format!("{}:\\", self.letter)) {
+            self.record_failure(err, errors);
+        }
+    }
+
+    fn set_ready(&mut self) {
+        self.ready = true;
+        self.ready_state = ReadyState::Ready;
+    }
+
+    /// Clears `ready`, keeping the reason of the first failure, and pushes any error other than "not ready"
+    /// to `errors`
+    fn record_failure(&mut self, err: WinPartitionError, errors: &mut Vec<WinPartitionError>) {
+        if self.ready {
+            self.ready = false;
+            self.ready_state = ReadyState::from(&err);
+        }
+        if !matches!(err, WinPartitionError::NotReady { .. }) {
+            errors.push(err);
+        }
+    }
+
+    /// File system of the partition, parsed from `file_system_name`
+    pub fn file_system(&self) -> FileSystem {
+        FileSystem::from(self.file_system_name.as_str())
+    }
+
+    /// Whether the drive was created with `SUBST` or `DefineDosDevice` and points to a directory of another drive
+    pub fn is_subst(&self) -> bool {
+        self.subst_target.is_some()
+    }
+
+    /// Reads the NT device path and, for substituted drives, the directory the drive points to
+    fn query_dos_device(&mut self, backend: &dyn WinApiBackend) {
+        let target = backend.dos_device(&format!("{}:", self.letter))
+            .ok()
+            .and_then(|targets| targets.into_iter().next());
+        self.subst_target = target.as_deref().and_then(parse_subst_target);
+        self.device_path = target;
+    }
+
+    /// Reads the mount points of the volume, which a drive letter of a network or `subst` drive isn't
+    fn query_mount_points(&mut self, backend: &dyn WinApiBackend) {
+        self.mount_points = match self.drive_type {
+            DriveType::DriveRemote => vec![],
+            _ if self.subst_target.is_some() => vec![],
+            _ => backend.volume_path_names(&format!("{}:\\", self.letter))
+                .map(|names| names.into_iter().map(PathBuf::from).collect())
+                .unwrap_or_default(),
+        };
+    }
+
+    /// Used space in bytes
+    pub fn used_space(&self) -> u64 {
+        self.size.saturating_sub(self.free_space)
+    }
+
+    /// Free space as a percentage of the size, from 0 to 100. 0 for a drive without media
+    pub fn percent_free(&self) -> f64 {
+        if self.size == 0 {
+            0.0
+        } else {
+            self.free_space as f64 * 100.0 / self.size as f64
+        }
+    }
+
+    /// Used space as a percentage of the size, from 0 to 100. 0 for a drive without media
+    pub fn percent_used(&self) -> f64 {
+        if self.size == 0 {
+            0.0
+        } else {
+            self.used_space() as f64 * 100.0 / self.size as f64
+        }
+    }
+
+    /// Root directory of the partition, e.g. `C:\`
+    pub fn root_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}:\\", self.letter))
+    }
+
+    /// Checks whether the partition is stored on a solid state drive, see [is_ssd](crate::storage::is_ssd)
+    pub fn is_ssd(&self) -> Result<bool, WinPartitionError> {
+        is_ssd(format!("{}:\\", self.letter))
+    }
+
+    /// Tells apart SD cards, CompactFlash cards, USB sticks and floppies on a removable drive, `None` for other
+    /// drive types, see [get_removable_media_kind](crate::storage::get_removable_media_kind)
+    pub fn removable_media_kind(&self) -> Result<Option<RemovableMediaKind>, WinPartitionError> {
+        get_removable_media_kind(self.letter)
+    }
+
+    /// Returns the human-readable name of the disk the partition is stored on, e.g. `Samsung SSD 990 PRO 2TB`,
+    /// as shown by Device Manager, see [DiskDevice::friendly_name](crate::device_info::DiskDevice::friendly_name).
+    /// For a volume spanning several disks the name of the first disk is returned
+    pub fn disk_friendly_name(&self) -> Result<Option<String>, WinPartitionError> {
+        let extents = if self.disk_extents.is_empty() {
+            get_volume_disk_extents(format!("{}:\\", self.letter))?
+        } else {
+            self.disk_extents.clone()
+        };
+        match extents.first() {
+            Some(extent) => Ok(get_disk_device(extent.disk_number)?.friendly_name),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks whether the partition's device supports TRIM, see [supports_trim](crate::storage::supports_trim)
+    pub fn supports_trim(&self) -> Result<bool, WinPartitionError> {
+        supports_trim(format!("{}:\\", self.letter))
+    }
+
+    /// Checks whether the partition is flagged for chkdsk at next boot, see [is_volume_dirty]
+    pub fn is_dirty(&self) -> Result<bool, WinPartitionError> {
+        is_volume_dirty(format!("{}:\\", self.letter))
+    }
+
+    /// Checks whether Windows is installed on the partition, see [get_boot_volume_letter]
+    pub fn is_boot_volume(&self) -> Result<bool, WinPartitionError> {
+        Ok(get_boot_volume_letter()? == self.letter)
+    }
+
+    /// Checks whether the partition holds the boot loader, see [get_system_partition_device].
+    /// Destructive operations on the boot or system volume make the system unbootable
+    pub fn is_system_volume(&self) -> Result<bool, WinPartitionError> {
+        let device = get_system_partition_device()?;
+        Ok(self.device_path.as_deref().is_some_and(|path| path.eq_ignore_ascii_case(&device)))
+    }
+
+    /// Checks whether the system currently uses a page file on the partition, see [get_page_files]
+    pub fn has_page_file(&self) -> Result<bool, WinPartitionError> {
+        Ok(get_page_files()?
+            .iter()
+            .any(|path| drive_letter_of_path(path) == Some(self.letter)))
+    }
+
+    /// Checks whether files on the partition can be encrypted with EFS: the file system must support
+    /// encryption, see [FileSystemFlags::supports_encryption], and EFS must not be turned off, see [is_efs_disabled]
+    pub fn supports_efs(&self) -> Result<bool, WinPartitionError> {
+        let flags = get_volume_information(format!("{}:\\", self.letter))?.4;
+        Ok(flags.supports_encryption() && !is_efs_disabled()?)
+    }
+
+    /// Returns the DFS referral of a network drive mapped to a DFS namespace, `None` for other drives,
+    /// see [get_dfs_referral]. The active target is the file server the drive currently uses
+    pub fn dfs_referral(&self) -> Result<Option<DfsReferral>, WinPartitionError> {
+        match &self.remote_path {
+            Some(remote_path) => get_dfs_referral(remote_path.clone()),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns whether a network drive is online, on a slow link or working offline through Offline Files,
+    /// `None` for other drives and shares not cached by Offline Files, see [get_offline_state]
+    pub fn offline_state(&self) -> Result<Option<OfflineState>, WinPartitionError> {
+        match &self.remote_path {
+            Some(remote_path) => get_offline_state(remote_path.clone()),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns whether the partition is a simple, spanned, striped, mirrored or RAID-5 volume,
+    /// see [get_volume_layout]
+    pub fn volume_layout(&self) -> Result<VolumeLayout, WinPartitionError> {
+        get_volume_layout(format!("{}:\\", self.letter))
+    }
+
+    /// Returns the Storage Spaces virtual disk the partition is stored on, or `None` for a plain
+    /// physical disk, see [get_storage_space]. The free space of a thin-provisioned space may exceed
+    /// the free capacity of its pool
+    pub fn storage_space(&self) -> Result<Option<StorageSpace>, WinPartitionError> {
+        if self.bus_type.is_some_and(|bus_type| bus_type != StorageBusType::Spaces) {
+            return Ok(None);
+        }
+        let extents = if self.disk_extents.is_empty() {
+            get_volume_disk_extents(format!("{}:\\", self.letter))?
+        } else {
+            self.disk_extents.clone()
+        };
+        match extents.first() {
+            Some(extent) => get_storage_space(extent.disk_number),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Formats as `C: "System" NTFS 512 GB (123 GB free)`, or e.g. `E: not ready` for a drive without media
+impl fmt::Display for WindowsPartition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.ready {
+            return match self.ready_state {
+                ReadyState::AccessDenied | ReadyState::DeviceError(_) => {
+                    write!(f, "{}: {}", self.letter, self.ready_state)
+                }
+                ReadyState::Unknown => write!(f, "{}: readiness not queried", self.letter),
+                _ => write!(f, "{}: not ready", self.letter),
+            };
+        }
+        write!(
+            f,
+            "{}: \"{}\" {} {} ({} free)",
+            self.letter,
+            self.name,
+            self.file_system_name,
+            ByteSize(self.size),
+            ByteSize(self.free_space))
+    }
+}
+
+/// Whether a partition could be queried, and why not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReadyState {
+    /// Size and volume information were read
+    Ready,
+    /// Readiness wasn't probed, e.g. by a [PartitionQuery] skipping the free space and volume information
+    #[default]
+    Unknown,
+    /// The drive has no media, e.g. a CD-Rom drive without a CD or a card reader without a card (`ERROR_NOT_READY`)
+    NoMedia,
+    /// The calling user may not read the volume, e.g. a drive locked by BitLocker (`ERROR_ACCESS_DENIED`)
+    AccessDenied,
+    /// Any other failure, with its Win32 error code
+    DeviceError(i32),
+}
+
+impl ReadyState {
+    /// Whether the partition is ready
+    pub fn is_ready(&self) -> bool {
+        matches!(self, ReadyState::Ready)
+    }
+}
+
+impl From<&WinPartitionError> for ReadyState {
+    fn from(err: &WinPartitionError) -> ReadyState {
+        match err {
+            WinPartitionError::NotReady { .. } => ReadyState::NoMedia,
+            WinPartitionError::AccessDenied { .. } => ReadyState::AccessDenied,
+            err => ReadyState::DeviceError(err.raw_os_error()),
+        }
+    }
+}
+
+/// Formats as `ready`, `unknown`, `not ready`, `access denied` or `error 1117`
+impl fmt::Display for ReadyState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadyState::Ready => write!(f, "ready"),
+            ReadyState::Unknown => write!(f, "unknown"),
+            ReadyState::NoMedia => write!(f, "not ready"),
+            ReadyState::AccessDenied => write!(f, "access denied"),
+            ReadyState::DeviceError(code) => write!(f, "error {}", code),
+        }
+    }
+}
+
+/// Provides information about a volume, including volumes without a drive letter
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowsVolume {
+    /// Volume GUID path, e.g. `\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\`
+    pub guid_path: String,
+    /// Drive letters and mounted folders of the volume, e.g. `C:\` or `C:\Mount\Data\`.
+    /// Empty for volumes that are not mounted anywhere
+    pub mount_points: Vec<PathBuf>,
+}
+
+/// Provides size and file system information about a volume without a drive letter, such as an EFI, recovery
+/// or folder-mounted volume, queried through its volume GUID path
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnletteredVolume {
+    /// Volume GUID path, e.g. `\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\`
+    pub guid_path: String,
+    /// Mounted folders of the volume, e.g. `C:\Mount\Data\`. Empty for volumes that are not mounted anywhere
+    pub mount_points: Vec<PathBuf>,
+    /// `false` if the volume couldn't be queried, e.g. a card reader without media or a volume the calling
+    /// user may not read. The other fields are then empty
+    pub ready: bool,
+    /// Volume label
+    pub name: String,
+    /// Partition size in bytes
+    pub size: u64,
+    /// Free space in bytes
+    pub free_space: u64,
+    /// File system name, e.g. `NTFS` or `FAT32`
+    pub file_system_name: String,
+}
+
+impl UnletteredVolume {
+    /// Used space in bytes
+    pub fn used_space(&self) -> u64 {
+        self.size.saturating_sub(self.free_space)
+    }
+}
+
+/// Partitions with a drive letter and volumes without one, see [get_all_volumes]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllVolumes {
+    /// Partitions as returned by [get_partitions]
+    pub partitions: Vec<WindowsPartition>,
+    /// Volumes without a drive letter
+    pub unlettered: Vec<UnletteredVolume>,
+}
+
+impl AllVolumes {
+    /// Total size in bytes of the local volumes, leaving out network drives and `subst` drives which would
+    /// count the space of another volume again
+    pub fn local_size(&self) -> u64 {
+        self.local_partitions().map(|partition| partition.size).sum::<u64>()
+            + self.unlettered.iter().map(|volume| volume.size).sum::<u64>()
+    }
+
+    /// Total free space in bytes of the local volumes, see [AllVolumes::local_size]
+    pub fn local_free_space(&self) -> u64 {
+        self.local_partitions().map(|partition| partition.free_space).sum::<u64>()
+            + self.unlettered.iter().map(|volume| volume.free_space).sum::<u64>()
+    }
+
+    fn local_partitions(&self) -> impl Iterator<Item = &WindowsPartition> {
+        self.partitions
+            .iter()
+            .filter(|partition| partition.drive_type != DriveType::DriveRemote && !partition.is_subst())
+    }
+}
+
+/// Provides information about a network share, which doesn't need to be mapped to a drive letter
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkShare {
+    /// UNC root path of the share, e.g. `\\server\share\`
+    pub path: String,
+    /// Label of the volume the share is stored on
+    pub name: String,
+    /// File system of the volume the share is stored on, as reported by the server
+    pub file_system_name: String,
+    /// Total size in bytes, limited to the quota of the user if the server enforces one
+    pub size: u64,
+    /// Free space in bytes
+    pub free_space: u64,
+    /// Free space in bytes the calling user may use, less than `free_space` with quotas
+    pub available_space: u64,
+}
+
+/// Queries the network share holding the UNC path `path`, such as `\\server\share\` or
+/// `\\server\share\backups\2024`. Fails with `ERROR_BAD_PATHNAME` if `path` isn't a UNC path
+pub fn get_network_share(path: String) -> Result<NetworkShare, WinPartitionError> {
+    let root = match unc_share_root(&path) {
+        Some(share) => format!("{}\\", share),
+        None => return Err(WinPartitionError::from_code("GetDiskFreeSpaceExW", Some(&path), ERROR_BAD_PATHNAME)),
+    };
+    let (available_space, size, free_space) = get_disk_free_space(&root)?;
+    let (name, file_system_name, ..) = get_volume_information(&root)?;
+
+    Ok(NetworkShare { path: root, name, file_system_name, size, free_space, available_space })
+}
+
+/// Gets list of system partitions or operating system error
+pub fn get_partitions() -> Result<Vec<WindowsPartition>, WinPartitionError> {
+    get_partitions_lossy().map(|(partitions, _errors)| partitions)
+}
+
+/// Gets list of system partitions, tolerating per-drive failures.
+///
+/// Returns every partition alongside the errors met while querying them. A partition whose
+/// query failed is still listed with `ready` set to `false` and whatever information could be
+/// read. Drives without media (e.g. an empty CD-Rom drive) are not reported as errors.
+/// Only a failure to list the drives themselves is returned as `Err`.
+pub fn get_partitions_lossy() -> Result<(Vec<WindowsPartition>, Vec<WinPartitionError>), WinPartitionError> {
+    get_partitions_lossy_with(&SystemBackend)
+}
+
+/// Like [get_partitions], making the Windows API calls through `backend`
+pub fn get_partitions_with(backend: &dyn WinApiBackend) -> Result<Vec<WindowsPartition>, WinPartitionError> {
+    get_partitions_lossy_with(backend).map(|(partitions, _errors)| partitions)
+}
+
+/// Like [get_partitions_lossy], making the Windows API calls through `backend`
+pub fn get_partitions_lossy_with(
+    backend: &dyn WinApiBackend
+) -> Result<(Vec<WindowsPartition>, Vec<WinPartitionError>), WinPartitionError> {
+    let mut iter = PartitionIter::with_backend(backend)?;
+    let result: Vec<WindowsPartition> = iter.by_ref().collect();
+
+    Ok((result, iter.errors))
+}
+
+/// Builder for partition queries that only makes the Windows API calls needed for the requested drives and fields.
+///
+/// By default every drive is listed with every field populated, like [get_partitions]. Skipped fields keep
+/// their empty value (`0`, `""`, `None`). Filters are checked by drive type first, so skipped drives cost no
+/// further calls.
+///
+/// `ready` and `ready_state` come from the free space and volume information calls. With both skipped they
+/// stay `false` and [ReadyState::Unknown], unless [PartitionQuery::only_ready] is set, which then probes
+/// readiness with the free space call without populating `size`, `free_space` and `allocation_unit_size`.
+///
+/// ```no_run
+/// use win_partitions::windows_partitions::PartitionQuery;
+///
+/// let partitions = PartitionQuery::new()
+///     .only_fixed()
+///     .disk_extents(false)
+///     .bus_type(false)
+///     .run()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct PartitionQuery {
+    only_ready: bool,
+    only_fixed: bool,
+    include_network: bool,
+    free_space: bool,
+    volume_information: bool,
+    disk_extents: bool,
+    bus_type: bool,
+}
+
+impl Default for PartitionQuery {
+    fn default() -> Self {
+        PartitionQuery {
+            only_ready: false,
+            only_fixed: false,
+            include_network: true,
+            free_space: true,
+            volume_information: true,
+            disk_extents: true,
+            bus_type: true,
+        }
+    }
+}
+
+impl PartitionQuery {
+    /// Creates a query listing every drive with every field populated
+    pub fn new() -> PartitionQuery {
+        PartitionQuery::default()
+    }
+
+    /// Only lists drives that are ready, e.g. skips CD-Rom drives without a CD
+    pub fn only_ready(mut self) -> PartitionQuery {
+        self.only_ready = true;
+        self
+    }
+
+    /// Only lists drives of type [DriveType::DriveFixed]
+    pub fn only_fixed(mut self) -> PartitionQuery {
+        self.only_fixed = true;
+        self
+    }
+
+    /// Whether to list network drives, defaults to `true`
+    pub fn include_network(mut self, include: bool) -> PartitionQuery {
+        self.include_network = include;
+        self
+    }
+
+    /// Whether to populate `size`, `free_space` and `allocation_unit_size`, defaults to `true`
+    pub fn free_space(mut self, populate: bool) -> PartitionQuery {
+        self.free_space = populate;
+        self
+    }
+
+    /// Whether to populate `name` and `file_system_name`, defaults to `true`
+    pub fn volume_information(mut self, populate: bool) -> PartitionQuery {
+        self.volume_information = populate;
+        self
+    }
+
+    /// Whether to populate `disk_extents`, defaults to `true`
+    pub fn disk_extents(mut self, populate: bool) -> PartitionQuery {
+        self.disk_extents = populate;
+        self
+    }
+
+    /// Whether to populate `bus_type`, defaults to `true`
+    pub fn bus_type(mut self, populate: bool) -> PartitionQuery {
+        self.bus_type = populate;
+        self
+    }
+
+    /// Runs the query, discarding per-drive errors like [get_partitions]
+    pub fn run(&self) -> Result<Vec<WindowsPartition>, WinPartitionError> {
+        self.run_lossy().map(|(partitions, _errors)| partitions)
+    }
+
+    /// Runs the query, returning per-drive errors like [get_partitions_lossy]
+    pub fn run_lossy(&self) -> Result<(Vec<WindowsPartition>, Vec<WinPartitionError>), WinPartitionError> {
+        self.run_lossy_with(&SystemBackend)
+    }
+
+    /// Like [run](PartitionQuery::run), making the Windows API calls through `backend`
+    pub fn run_with(&self, backend: &dyn WinApiBackend) -> Result<Vec<WindowsPartition>, WinPartitionError> {
+        self.run_lossy_with(backend).map(|(partitions, _errors)| partitions)
+    }
+
+    /// Like [run_lossy](PartitionQuery::run_lossy), making the Windows API calls through `backend`
+    pub fn run_lossy_with(
+        &self,
+        backend: &dyn WinApiBackend
+    ) -> Result<(Vec<WindowsPartition>, Vec<WinPartitionError>), WinPartitionError> {
+        let mut result: Vec<WindowsPartition> = vec![];
+        let mut errors: Vec<WinPartitionError> = vec![];
+        for letter in backend.logical_drives()? {
+            if let Some(partition) = self.query(backend, letter, &mut errors) {
+                result.push(partition);
+            }
+        }
+
+        Ok((result, errors))
+    }
+
+    fn query(
+        &self,
+        backend: &dyn WinApiBackend,
+        letter: char,
+        errors: &mut Vec<WinPartitionError>,
+    ) -> Option<WindowsPartition> {
+        let path = format!("{}:\\", letter);
+        let drive_type = backend.drive_type(&path);
+        if self.only_fixed && !matches!(drive_type, DriveType::DriveFixed)
+            || !self.include_network && matches!(drive_type, DriveType::DriveRemote) {
+            return None;
+        }
+
+        let mut partition = not_ready_partition(letter, drive_type);
+        // Readiness is only known from the free space or volume information calls
+        if self.free_space || self.volume_information || self.only_ready {
+            partition.set_ready();
+        }
+        if self.free_space {
+            partition.query_free_space(backend, errors);
+        } else if self.only_ready && !self.volume_information {
+            partition.query_ready(backend, errors);
+        }
+        if self.volume_information {
+            partition.query_volume_information(backend, errors);
+        }
+        if self.only_ready && !partition.ready {
+            return None;
+        }
+        if self.disk_extents {
+            partition.disk_extents = backend.volume_disk_extents(&path).unwrap_or_default();
+        }
+        if self.bus_type {
+            partition.bus_type = backend.storage_bus_type(&path).ok();
+        }
+        partition.remote_path = query_remote_path(backend, &partition);
+        partition.query_dos_device(backend);
+        partition.query_mount_points(backend);
+
+        Some(partition)
+    }
+}
+
+/// Iterator querying partitions one at a time, so the first drives can be shown while slower ones
+/// (e.g. network drives) are still to be probed. Tolerates per-drive failures like [get_partitions_lossy]
+pub struct PartitionIter<'a> {
+    backend: &'a dyn WinApiBackend,
+    drives: std::vec::IntoIter<char>,
+    errors: Vec<WinPartitionError>,
+}
+
+impl PartitionIter<'static> {
+    /// Lists the drives to query. Drives are only queried when the iterator is advanced
+    pub fn new() -> Result<PartitionIter<'static>, WinPartitionError> {
+        PartitionIter::with_backend(&SystemBackend)
+    }
+}
+
+impl<'a> PartitionIter<'a> {
+    /// Like [new](PartitionIter::new), making the Windows API calls through `backend`
+    pub fn with_backend(backend: &'a dyn WinApiBackend) -> Result<PartitionIter<'a>, WinPartitionError> {
+        let drives = backend.logical_drives()?;
+        Ok(PartitionIter { backend, drives: drives.into_iter(), errors: vec![] })
+    }
+
+    /// Errors met while querying the partitions yielded so far
+    pub fn errors(&self) -> &[WinPartitionError] {
+        &self.errors
+    }
+}
+
+impl Iterator for PartitionIter<'_> {
+    type Item = WindowsPartition;
+
+    fn next(&mut self) -> Option<WindowsPartition> {
+        let letter = self.drives.next()?;
+        Some(query_partition(self.backend, letter, &mut self.errors))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drives.size_hint()
+    }
+}
+
+impl ExactSizeIterator for PartitionIter<'_> {}
+
+/// Finds the partition containing a file or directory, e.g. to check whether there is enough free space to
+/// save a file there. The path doesn't need to exist.
+///
+/// Paths inside a folder mounted volume resolve to that volume rather than the drive hosting the folder.
+/// Returns `None` if the volume containing the path has no drive letter.
+pub fn partition_for_path(path: String) -> Result<Option<WindowsPartition>, WinPartitionError> {
+    let mount_point = get_volume_path_name(path)?;
+    let volume_name = get_volume_name_for_mount_point(mount_point)?;
+    let letter = get_volume_path_names(volume_name)?
+        .iter()
+        .find_map(|path_name| drive_letter_of_root(path_name));
+
+    Ok(letter.map(|letter| query_partition(&SystemBackend, letter, &mut vec![])))
+}
+
+/// Returns the drive letter of a root path such as `D:\`, `None` for mounted folders
+fn drive_letter_of_root(path: &str) -> Option<char> {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some(letter), Some(':'), Some('\\'), None) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Returns the drive letter a path such as `C:\pagefile.sys` starts with
+fn drive_letter_of_path(path: &Path) -> Option<char> {
+    let mut chars = path.to_str()?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Gets list of system partitions like [get_partitions_lossy], giving up on drives that don't answer within `timeout`.
+///
+/// Drives are queried concurrently, one thread per drive. A drive that times out, such as a disconnected mapped
+/// network drive, is listed with `ready` set to `false` and a [WinPartitionError::TimedOut] error naming the
+/// call that hung. Its query keeps running in the background until Windows gives up on it, and later calls
+/// wait for that query instead of starting another one, so there is at most one thread per drive letter.
+/// Concurrent calls run one after the other.
+pub fn get_partitions_with_timeout(
+    timeout: Duration
+) -> Result<(Vec<WindowsPartition>, Vec<WinPartitionError>), WinPartitionError> {
+    let deadline = Instant::now() + timeout;
+    let mut pending = PENDING_QUERIES.lock().unwrap_or_else(|err| err.into_inner());
+    let queries: Vec<(char, DriveQuery)> = get_logical_drive()?
+        .into_iter()
+        .map(|letter| (letter, pending.remove(&letter).unwrap_or_else(|| DriveQuery::start(letter))))
+        .collect();
+
+    let mut result: Vec<WindowsPartition> = vec![];
+    let mut errors: Vec<WinPartitionError> = vec![];
+    for (letter, mut query) in queries {
+        match query.wait(deadline) {
+            Some((partition, partition_errors)) => {
+                result.push(partition);
+                errors.extend(partition_errors);
+            }
+            None => {
+                let err = WinPartitionError::TimedOut { api: query.api, path: Some(format!("{}:\\", letter)) };
+                let mut partition = not_ready_partition(letter, query.drive_type);
+                partition.ready_state = ReadyState::from(&err);
+                result.push(partition);
+                errors.push(err);
+                pending.insert(letter, query);
             }
         }
-        result.push(WindowsPartition {
-            letter,
-            ready,
-            name,
-            size: total_size,
-            free_space,
-            file_system_name,
-            drive_type,
-        })
     }
 
-    Ok(result)
+    Ok((result, errors))
+}
+
+/// Queries of [get_partitions_with_timeout] that timed out and are still running, by drive letter
+static PENDING_QUERIES: Mutex<BTreeMap<char, DriveQuery>> = Mutex::new(BTreeMap::new());
+
+/// Progress of a drive queried in a background thread
+enum QueryProgress {
+    /// The thread is about to call the Windows API function
+    Calling(&'static str),
+    DriveType(DriveType),
+    Done(Box<WindowsPartition>, Vec<WinPartitionError>),
+}
+
+/// Drive queried in a background thread, with the last progress it reported
+struct DriveQuery {
+    letter: char,
+    receiver: Receiver<QueryProgress>,
+    /// Windows API function the thread is in
+    api: &'static str,
+    /// Drive type, [DriveType::DriveUnknown] until the thread read it
+    drive_type: DriveType,
+}
+
+impl DriveQuery {
+    fn start(letter: char) -> DriveQuery {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let backend = ReportingBackend { sender: sender.clone() };
+            let mut errors: Vec<WinPartitionError> = vec![];
+            let partition = query_partition(&backend, letter, &mut errors);
+            let _ = sender.send(QueryProgress::Done(Box::new(partition), errors));
+        });
+        DriveQuery { letter, receiver, api: "GetDriveTypeW", drive_type: DriveType::DriveUnknown }
+    }
+
+    /// Waits for the result until `deadline`, `None` if the query is still running
+    fn wait(&mut self, deadline: Instant) -> Option<(WindowsPartition, Vec<WinPartitionError>)> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(remaining) {
+                Ok(QueryProgress::Calling(api)) => self.api = api,
+                Ok(QueryProgress::DriveType(drive_type)) => self.drive_type = drive_type,
+                Ok(QueryProgress::Done(partition, errors)) => return Some((*partition, errors)),
+                Err(RecvTimeoutError::Timeout) => return None,
+                // The thread panicked, report the drive as not ready like a query that never ends
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Some((not_ready_partition(self.letter, self.drive_type), vec![]))
+                }
+            }
+        }
+    }
+}
+
+/// [SystemBackend] reporting each call before making it, so a hung query can tell where it hangs
+struct ReportingBackend {
+    sender: Sender<QueryProgress>,
+}
+
+impl ReportingBackend {
+    fn calling(&self, api: &'static str) {
+        let _ = self.sender.send(QueryProgress::Calling(api));
+    }
+}
+
+impl WinApiBackend for ReportingBackend {
+    fn logical_drives(&self) -> Result<Vec<char>, WinPartitionError> {
+        self.calling("GetLogicalDrives");
+        SystemBackend.logical_drives()
+    }
+
+    fn drive_type(&self, root_path: &str) -> DriveType {
+        self.calling("GetDriveTypeW");
+        let drive_type = SystemBackend.drive_type(root_path);
+        let _ = self.sender.send(QueryProgress::DriveType(drive_type));
+        drive_type
+    }
+
+    fn disk_free_space(&self, root_path: &str) -> Result<(u64, u64, u64), WinPartitionError> {
+        self.calling("GetDiskFreeSpaceExW");
+        SystemBackend.disk_free_space(root_path)
+    }
+
+    fn volume_information(
+        &self,
+        root_path: &str,
+    ) -> Result<(String, String, Option<VolumeSerial>, u32, FileSystemFlags), WinPartitionError> {
+        self.calling("GetVolumeInformationW");
+        SystemBackend.volume_information(root_path)
+    }
+
+    fn disk_free_space_basic(&self, root_path: &str) -> Result<(u32, u32, u32, u32), WinPartitionError> {
+        self.calling("GetDiskFreeSpaceW");
+        SystemBackend.disk_free_space_basic(root_path)
+    }
+
+    fn volume_disk_extents(&self, root_path: &str) -> Result<Vec<DiskExtent>, WinPartitionError> {
+        self.calling("IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS");
+        SystemBackend.volume_disk_extents(root_path)
+    }
+
+    fn storage_bus_type(&self, root_path: &str) -> Result<StorageBusType, WinPartitionError> {
+        self.calling("IOCTL_STORAGE_QUERY_PROPERTY");
+        SystemBackend.storage_bus_type(root_path)
+    }
+
+    fn network_connection(&self, letter: char) -> Result<String, WinPartitionError> {
+        self.calling("WNetGetConnectionW");
+        SystemBackend.network_connection(letter)
+    }
+
+    fn dos_device(&self, device_name: &str) -> Result<Vec<String>, WinPartitionError> {
+        self.calling("QueryDosDeviceW");
+        SystemBackend.dos_device(device_name)
+    }
+
+    fn file_attributes(&self, path: &str) -> Result<u32, WinPartitionError> {
+        self.calling("GetFileAttributesW");
+        SystemBackend.file_attributes(path)
+    }
+
+    fn volume_path_names(&self, root_path: &str) -> Result<Vec<String>, WinPartitionError> {
+        self.calling("GetVolumePathNamesForVolumeNameW");
+        SystemBackend.volume_path_names(root_path)
+    }
+}
+
+/// Partition entry for a drive that couldn't be queried
+fn not_ready_partition(letter: char, drive_type: DriveType) -> WindowsPartition {
+    WindowsPartition { letter, drive_type, ..Default::default() }
+}
+
+/// Queries a single drive, pushing any error other than "not ready" to `errors`
+pub(crate) fn query_partition(
+    backend: &dyn WinApiBackend,
+    letter: char,
+    errors: &mut Vec<WinPartitionError>,
+) -> WindowsPartition {
+    let path = format!("{}:\\", letter);
+    let mut partition = not_ready_partition(letter, backend.drive_type(&path));
+    partition.query_state(backend, errors);
+    // Network, CD-Rom and RAM drives don't support the extents query, so it's not an error
+    partition.disk_extents = backend.volume_disk_extents(&path).unwrap_or_default();
+    partition.bus_type = backend.storage_bus_type(&path).ok();
+    partition.remote_path = query_remote_path(backend, &partition);
+    partition.query_dos_device(backend);
+    partition.query_mount_points(backend);
+    partition
+}
+
+/// Returns the UNC path of a network drive, `None` for local drives
+fn query_remote_path(backend: &dyn WinApiBackend, partition: &WindowsPartition) -> Option<String> {
+    match partition.drive_type {
+        DriveType::DriveRemote => backend.network_connection(partition.letter).ok(),
+        _ => None,
+    }
+}
+
+/// Gets list of all volumes with their mount points, including volumes without a drive letter.
+/// A volume whose mount points can't be read, e.g. one removed during the enumeration, is listed without any
+pub fn get_volumes() -> Result<Vec<WindowsVolume>, WinPartitionError> {
+    get_volumes_lossy().map(|(volumes, _errors)| volumes)
+}
+
+/// Gets list of all volumes like [get_volumes], together with the errors met reading their mount points
+pub fn get_volumes_lossy() -> Result<(Vec<WindowsVolume>, Vec<WinPartitionError>), WinPartitionError> {
+    let mut result: Vec<WindowsVolume> = vec![];
+    let mut errors: Vec<WinPartitionError> = vec![];
+    for guid_path in enumerate_volumes()? {
+        let mount_points = match get_volume_path_names(guid_path.to_string()) {
+            Ok(names) => names.into_iter().map(PathBuf::from).collect(),
+            Err(err) => {
+                errors.push(err);
+                vec![]
+            }
+        };
+        result.push(WindowsVolume {
+            guid_path,
+            mount_points,
+        });
+    }
+
+    Ok((result, errors))
+}
+
+/// Gets the partitions of [get_partitions] merged with the volumes that have no drive letter, so the space of
+/// EFI, recovery and folder-mounted volumes can be accounted for too. The volumes are found with
+/// [enumerate_volumes] and queried through their volume GUID path. Volumes whose mount points can't be read
+/// are left out
+pub fn get_all_volumes() -> Result<AllVolumes, WinPartitionError> {
+    let partitions = get_partitions()?;
+    let mut unlettered: Vec<UnletteredVolume> = vec![];
+    let (volumes, errors) = get_volumes_lossy()?;
+    for volume in volumes {
+        // A volume whose mount points couldn't be read may have a drive letter, so it could be counted twice
+        if errors.iter().any(|err| err.path() == Some(volume.guid_path.as_str())) {
+            continue;
+        }
+        let has_letter = volume.mount_points
+            .iter()
+            .any(|mount_point| drive_letter_of_root(&mount_point.to_string_lossy()).is_some());
+        if has_letter {
+            continue;
+        }
+        unlettered.push(query_unlettered_volume(volume));
+    }
+
+    Ok(AllVolumes { partitions, unlettered })
+}
+
+fn query_unlettered_volume(volume: WindowsVolume) -> UnletteredVolume {
+    let mut result = UnletteredVolume {
+        guid_path: volume.guid_path,
+        mount_points: volume.mount_points,
+        ..Default::default()
+    };
+    let space = get_disk_free_space(&result.guid_path);
+    let information = get_volume_information(&result.guid_path);
+    if let (Ok((_, size, free_space)), Ok((name, file_system_name, ..))) = (space, information) {
+        result.ready = true;
+        result.size = size;
+        result.free_space = free_space;
+        result.name = name;
+        result.file_system_name = file_system_name;
+    }
+
+    result
+}
+
+/// Parses a DOS device target such as `\??\C:\Projects` or `\??\UNC\server\share`.
+/// Real drives point to a device such as `\Device\HarddiskVolume3` instead
+fn parse_subst_target(target: &str) -> Option<PathBuf> {
+    let path = target.strip_prefix("\\??\\")?;
+    match path.strip_prefix("UNC\\") {
+        Some(share) => Some(PathBuf::from(format!("\\\\{}", share))),
+        None => Some(PathBuf::from(path)),
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +1082,141 @@ mod test {
             println!("{:?}", item)
         }
     }
+
+    #[test]
+    fn display_test() {
+        let mut partition = not_ready_partition('E', DriveType::DriveCDRom);
+        assert_eq!(partition.to_string(), "E: readiness not queried");
+        partition.ready_state = ReadyState::NoMedia;
+        assert_eq!(partition.to_string(), "E: not ready");
+
+        partition.ready = true;
+        partition.name = "System".to_string();
+        partition.file_system_name = "NTFS".to_string();
+        partition.size = 512 * 1024 * 1024 * 1024;
+        partition.free_space = 123 * 1024 * 1024 * 1024;
+        assert_eq!(partition.to_string(), "E: \"System\" NTFS 512 GB (123 GB free)");
+    }
+
+    #[test]
+    fn drive_letter_of_root_test() {
+        assert_eq!(drive_letter_of_root("d:\\"), Some('D'));
+        assert_eq!(drive_letter_of_root("C:\\Mount\\Data\\"), None);
+        assert_eq!(drive_letter_of_root("\\\\?\\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\\"), None);
+        assert_eq!(drive_letter_of_path(Path::new("c:\\pagefile.sys")), Some('C'));
+    }
+
+    #[test]
+    fn parse_subst_target_test() {
+        assert_eq!(parse_subst_target("\\??\\C:\\Projects"), Some(PathBuf::from("C:\\Projects")));
+        assert_eq!(parse_subst_target("\\??\\UNC\\server\\share"), Some(PathBuf::from("\\\\server\\share")));
+        assert_eq!(parse_subst_target("\\Device\\HarddiskVolume3"), None);
+    }
+
+    /// Backend with a ready `C:` drive and an empty `E:` CD-Rom drive
+    struct FakeBackend;
+
+    impl WinApiBackend for FakeBackend {
+        fn logical_drives(&self) -> Result<Vec<char>, WinPartitionError> {
+            Ok(vec!['C', 'E'])
+        }
+
+        fn drive_type(&self, root_path: &str) -> DriveType {
+            match root_path {
+                "C:\\" => DriveType::DriveFixed,
+                "E:\\" => DriveType::DriveCDRom,
+                _ => DriveType::DriveNoRootDir,
+            }
+        }
+
+        fn disk_free_space(&self, root_path: &str) -> Result<(u64, u64, u64), WinPartitionError> {
+            match root_path {
+                "C:\\" => Ok((100, 400, 100)),
+                _ => Err(WinPartitionError::NotReady { api: "GetDiskFreeSpaceExW", path: Some(root_path.to_string()) }),
+            }
+        }
+
+        fn volume_information(
+            &self,
+            root_path: &str,
+        ) -> Result<(String, String, Option<VolumeSerial>, u32, FileSystemFlags), WinPartitionError> {
+            match root_path {
+                "C:\\" => Ok((
+                    "System".to_string(),
+                    "NTFS".to_string(),
+                    Some(VolumeSerial(0x1a2b_03c4)),
+                    255,
+                    FileSystemFlags::FILE_FILE_COMPRESSION)),
+                _ => Err(WinPartitionError::NotReady { api: "GetVolumeInformationW", path: Some(root_path.to_string()) }),
+            }
+        }
+
+        fn file_attributes(&self, _path: &str) -> Result<u32, WinPartitionError> {
+            Ok(0x10 | FILE_ATTRIBUTE_COMPRESSED)
+        }
+
+        fn volume_path_names(&self, root_path: &str) -> Result<Vec<String>, WinPartitionError> {
+            Ok(vec![root_path.to_string(), format!("{}Mount\\Data\\", root_path)])
+        }
+    }
+
+    #[test]
+    fn fake_backend_test() {
+        let (partitions, errors) = get_partitions_lossy_with(&FakeBackend).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].to_string(), "C: \"System\" NTFS 400 B (100 B free)");
+        assert_eq!(partitions[0].drive_type, DriveType::DriveFixed);
+        assert!(partitions[0].supports_compression && partitions[0].compressed);
+        assert_eq!(partitions[0].serial, Some(VolumeSerial(0x1a2b_03c4)));
+        assert_eq!(partitions[0].max_component_length, 255);
+        assert_eq!(partitions[0].file_system_flags, FileSystemFlags::FILE_FILE_COMPRESSION);
+        assert_eq!(partitions[0].mount_points, vec![PathBuf::from("C:\\"), PathBuf::from("C:\\Mount\\Data\\")]);
+        assert_eq!(partitions[1].to_string(), "E: not ready");
+        assert_eq!(partitions[1].serial, None);
+        assert_eq!(partitions[1].ready_state, ReadyState::NoMedia);
+        assert!(partitions[0].ready_state.is_ready());
+        assert!(!partitions[1].compressed);
+
+        assert_eq!(WindowsPartition::from_letter_with(&FakeBackend, 'c').unwrap(), partitions[0]);
+        assert!(!WindowsPartition::from_letter_with(&FakeBackend, 'E').unwrap().ready);
+        let missing = WindowsPartition::from_letter_with(&FakeBackend, 'F').unwrap_err();
+        assert_eq!(missing.raw_os_error(), ERROR_PATH_NOT_FOUND);
+
+        let fixed = PartitionQuery::new().only_fixed().run_with(&FakeBackend).unwrap();
+        assert_eq!(fixed.len(), 1);
+
+        let unprobed = PartitionQuery::new().free_space(false).volume_information(false);
+        let partitions = unprobed.run_with(&FakeBackend).unwrap();
+        assert!(partitions.iter().all(|partition| !partition.ready && partition.ready_state == ReadyState::Unknown));
+        assert_eq!(partitions[1].to_string(), "E: readiness not queried");
+        let ready = unprobed.only_ready().run_with(&FakeBackend).unwrap();
+        assert_eq!(ready.len(), 1);
+        assert!(ready[0].ready && ready[0].size == 0);
+    }
+
+    #[test]
+    fn ready_state_test() {
+        let err = |code| WinPartitionError::from_code("GetVolumeInformationW", Some("E:\\"), code);
+        assert_eq!(ReadyState::from(&err(21)), ReadyState::NoMedia);
+        assert_eq!(ReadyState::from(&err(5)), ReadyState::AccessDenied);
+        assert_eq!(ReadyState::from(&err(1117)), ReadyState::DeviceError(1117));
+
+        let mut partition = not_ready_partition('E', DriveType::DriveFixed);
+        partition.ready_state = ReadyState::AccessDenied;
+        assert_eq!(partition.to_string(), "E: access denied");
+    }
+
+    #[test]
+    fn computed_fields_test() {
+        let mut partition = not_ready_partition('D', DriveType::DriveFixed);
+        assert_eq!(partition.percent_free(), 0.0);
+        assert_eq!(partition.root_path(), PathBuf::from("D:\\"));
+
+        partition.size = 400;
+        partition.free_space = 100;
+        assert_eq!(partition.used_space(), 300);
+        assert_eq!(partition.percent_free(), 25.0);
+        assert_eq!(partition.percent_used(), 75.0);
+    }
 }
@@ -0,0 +1,230 @@
+use std::convert::TryFrom;
+use std::ptr::null_mut;
+
+use crate::bindings::{
+    Windows::Win32::Foundation::BSTR,
+    Windows::Win32::Foundation::PWSTR,
+    Windows::Win32::System::Com::CoCreateInstance,
+    Windows::Win32::System::Com::CoSetProxyBlanket,
+    Windows::Win32::System::Com::CLSCTX_INPROC_SERVER,
+    Windows::Win32::System::Com::EOAC_NONE,
+    Windows::Win32::System::Com::RPC_C_AUTHN_LEVEL_CALL,
+    Windows::Win32::System::Com::RPC_C_IMP_LEVEL_IMPERSONATE,
+    Windows::Win32::System::OleAutomation::VariantClear,
+    Windows::Win32::System::OleAutomation::VARIANT,
+    Windows::Win32::System::OleAutomation::VT_BOOL,
+    Windows::Win32::System::OleAutomation::VT_BSTR,
+    Windows::Win32::System::OleAutomation::VT_I2,
+    Windows::Win32::System::OleAutomation::VT_I4,
+    Windows::Win32::System::OleAutomation::VT_I8,
+    Windows::Win32::System::OleAutomation::VT_UI1,
+    Windows::Win32::System::OleAutomation::VT_UI2,
+    Windows::Win32::System::OleAutomation::VT_UI4,
+    Windows::Win32::System::OleAutomation::VT_UI8,
+    Windows::Win32::System::Rpc::RPC_C_AUTHN_WINNT,
+    Windows::Win32::System::Rpc::RPC_C_AUTHZ_NONE,
+    Windows::Win32::System::Wmi::IEnumWbemClassObject,
+    Windows::Win32::System::Wmi::IWbemClassObject,
+    Windows::Win32::System::Wmi::IWbemLocator,
+    Windows::Win32::System::Wmi::IWbemServices,
+    Windows::Win32::System::Wmi::WbemLocator,
+    Windows::Win32::System::Wmi::WBEM_FLAG_FORWARD_ONLY,
+    Windows::Win32::System::Wmi::WBEM_FLAG_RETURN_IMMEDIATELY,
+    Windows::Win32::System::Wmi::WBEM_INFINITE,
+};
+use crate::com::{com_error, ComGuard};
+use crate::error::WinPartitionError;
+
+/// WMI namespace of the Win32 classes such as `Win32_LogicalDisk`
+pub(crate) const CIMV2_NAMESPACE: &str = "ROOT\\CIMV2";
+/// WMI namespace of the Windows Storage Management API
+pub(crate) const STORAGE_NAMESPACE: &str = "ROOT\\Microsoft\\Windows\\Storage";
+/// WMI namespace of the Failover Cluster provider
+pub(crate) const CLUSTER_NAMESPACE: &str = "ROOT\\MSCluster";
+
+/// Value of a WMI property. `uint64` properties are passed as strings by WMI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum WmiValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Unsigned(u64),
+    String(String),
+}
+
+impl WmiValue {
+    pub(crate) fn into_string(self) -> Option<String> {
+        match self {
+            WmiValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            WmiValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            WmiValue::Integer(value) => u64::try_from(*value).ok(),
+            WmiValue::Unsigned(value) => Some(*value),
+            WmiValue::String(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Connection to a local WMI namespace, keeping COM initialized while it's open
+pub(crate) struct WmiConnection {
+    services: IWbemServices,
+    _com: ComGuard,
+}
+
+impl WmiConnection {
+    /// Connects to `namespace`, such as `ROOT\CIMV2`, with the credentials of the calling process
+    pub(crate) fn connect(namespace: &str) -> Result<WmiConnection, WinPartitionError> {
+        let com = ComGuard::new()?;
+        let locator: IWbemLocator = unsafe { CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER) }
+            .map_err(com_error("CoCreateInstance", Some(namespace)))?;
+        let services = unsafe {
+            locator.ConnectServer(BSTR::from(namespace), BSTR::new(), BSTR::new(), BSTR::new(), 0, BSTR::new(), None)
+        }
+            .map_err(com_error("IWbemLocator::ConnectServer", Some(namespace)))?;
+        unsafe {
+            CoSetProxyBlanket(
+                &services,
+                RPC_C_AUTHN_WINNT,
+                RPC_C_AUTHZ_NONE,
+                PWSTR::NULL,
+                RPC_C_AUTHN_LEVEL_CALL,
+                RPC_C_IMP_LEVEL_IMPERSONATE,
+                null_mut(),
+                EOAC_NONE)
+        }
+            .map_err(com_error("CoSetProxyBlanket", Some(namespace)))?;
+
+        Ok(WmiConnection { services, _com: com })
+    }
+
+    /// Runs a WQL query and returns the matching objects
+    pub(crate) fn query(&self, wql: &str) -> Result<Vec<WmiObject>, WinPartitionError> {
+        let objects: IEnumWbemClassObject = unsafe {
+            self.services.ExecQuery(
+                BSTR::from("WQL"),
+                BSTR::from(wql),
+                WBEM_FLAG_FORWARD_ONLY.0 | WBEM_FLAG_RETURN_IMMEDIATELY.0,
+                None)
+        }
+            .map_err(com_error("IWbemServices::ExecQuery", Some(wql)))?;
+
+        let mut result: Vec<WmiObject> = vec![];
+        loop {
+            let mut object: Option<IWbemClassObject> = None;
+            let mut returned: u32 = 0;
+            unsafe { objects.Next(WBEM_INFINITE.0, 1, &mut object, &mut returned) }
+                .map_err(com_error("IEnumWbemClassObject::Next", Some(wql)))?;
+            match object {
+                Some(object) if returned == 1 => result.push(WmiObject(object)),
+                _ => break,
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Object returned by a WMI query
+pub(crate) struct WmiObject(IWbemClassObject);
+
+impl WmiObject {
+    /// Reads the property `name`
+    pub(crate) fn get(&self, name: &str) -> Result<WmiValue, WinPartitionError> {
+        let mut variant: VARIANT = unsafe { std::mem::zeroed() };
+        unsafe { self.0.Get(name, 0, &mut variant, null_mut(), null_mut()) }
+            .map_err(com_error("IWbemClassObject::Get", Some(name)))?;
+        let value = unsafe { variant_to_value(&variant) };
+        let _ = unsafe { VariantClear(&mut variant) };
+        Ok(value)
+    }
+
+    pub(crate) fn get_string(&self, name: &str) -> Result<Option<String>, WinPartitionError> {
+        Ok(self.get(name)?.into_string())
+    }
+
+    pub(crate) fn get_u64(&self, name: &str) -> Result<Option<u64>, WinPartitionError> {
+        Ok(self.get(name)?.as_u64())
+    }
+
+    pub(crate) fn get_bool(&self, name: &str) -> Result<Option<bool>, WinPartitionError> {
+        Ok(self.get(name)?.as_bool())
+    }
+}
+
+/// Builds a query for the objects of `class` associated with `object`
+pub(crate) fn associators_query(object: &WmiObject, class: &str) -> Result<String, WinPartitionError> {
+    let path = object.get_string("__PATH")?.unwrap_or_default();
+    Ok(format_associators_query(&path, class))
+}
+
+fn format_associators_query(path: &str, class: &str) -> String {
+    format!("ASSOCIATORS OF {{{}}} WHERE ResultClass = {}", path, class)
+}
+
+/// Copies the value of a VARIANT holding a string, an integer or a boolean
+unsafe fn variant_to_value(variant: &VARIANT) -> WmiValue {
+    let data = &variant.Anonymous.Anonymous;
+    let vt = data.vt as i32;
+    let value = &data.Anonymous;
+    if vt == VT_BSTR.0 {
+        let string = value.bstrVal;
+        if string.is_null() {
+            return WmiValue::String(String::new());
+        }
+        let length = (0..).take_while(|&index| *string.add(index) != 0).count();
+        WmiValue::String(String::from_utf16_lossy(std::slice::from_raw_parts(string, length)))
+    } else if vt == VT_BOOL.0 {
+        WmiValue::Bool(value.boolVal != 0)
+    } else if vt == VT_I2.0 {
+        WmiValue::Integer(value.iVal as i64)
+    } else if vt == VT_I4.0 {
+        WmiValue::Integer(value.lVal as i64)
+    } else if vt == VT_I8.0 {
+        WmiValue::Integer(value.llVal)
+    } else if vt == VT_UI1.0 {
+        WmiValue::Integer(value.bVal as i64)
+    } else if vt == VT_UI2.0 {
+        WmiValue::Integer(value.uiVal as i64)
+    } else if vt == VT_UI4.0 {
+        WmiValue::Integer(value.ulVal as i64)
+    } else if vt == VT_UI8.0 {
+        WmiValue::Unsigned(value.ullVal)
+    } else {
+        WmiValue::Null
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wmi_value_test() {
+        assert_eq!(WmiValue::String("1099511627776".to_string()).as_u64(), Some(1_099_511_627_776));
+        assert_eq!(WmiValue::Integer(2).as_u64(), Some(2));
+        assert_eq!(WmiValue::Integer(-1).as_u64(), None);
+        assert_eq!(WmiValue::Null.into_string(), None);
+        assert_eq!(WmiValue::Bool(true).as_bool(), Some(true));
+        assert_eq!(WmiValue::Integer(1).as_bool(), None);
+    }
+
+    #[test]
+    fn format_associators_query_test() {
+        assert_eq!(
+            format_associators_query("\\\\PC\\ROOT\\Microsoft\\Windows\\Storage:MSFT_Disk.ObjectId=\"x\"", "MSFT_VirtualDisk"),
+            "ASSOCIATORS OF {\\\\PC\\ROOT\\Microsoft\\Windows\\Storage:MSFT_Disk.ObjectId=\"x\"} \
+            WHERE ResultClass = MSFT_VirtualDisk");
+    }
+}
@@ -0,0 +1,61 @@
+use std::ffi::OsStr;
+use std::fmt;
+
+/// Error returned when a string passed to the Windows API holds a NUL character, which would end it early
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteriorNulError {
+    /// Index of the first NUL in UTF-16 code units
+    pub position: usize,
+}
+
+impl fmt::Display for InteriorNulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NUL character at UTF-16 position {}", self.position)
+    }
+}
+
+impl std::error::Error for InteriorNulError {}
+
+/// Creates a Rust String from a UTF-16 buffer filled by a Windows API function, up to the first NUL or the
+/// end of the buffer. Surrogate pairs are decoded to characters outside the Basic Multilingual Plane, unpaired
+/// surrogates become `U+FFFD`
+pub fn vec_u16_to_string(vec: &[u16]) -> String {
+    let length = vec.iter().position(|unit| *unit == 0).unwrap_or(vec.len());
+    String::from_utf16_lossy(&vec[..length])
+}
+
+/// Encodes `value` as a null-terminated UTF-16 string for the `W` functions of the Windows API, such as a
+/// `&str`, `&Path` or `OsString`. On Windows this is lossless, as `OsStr` holds the UTF-16 of the string as is,
+/// including unpaired surrogates. Fails if `value` holds a NUL, so the API never sees a truncated path
+pub fn to_wide_null(value: impl AsRef<OsStr>) -> Result<Vec<u16>, InteriorNulError> {
+    #[cfg(windows)]
+    let mut wide: Vec<u16> = std::os::windows::ffi::OsStrExt::encode_wide(value.as_ref()).collect();
+    #[cfg(not(windows))]
+    let mut wide: Vec<u16> = value.as_ref().to_string_lossy().encode_utf16().collect();
+    if let Some(position) = wide.iter().position(|unit| *unit == 0) {
+        return Err(InteriorNulError { position });
+    }
+    wide.push(0);
+    Ok(wide)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn vec_u16_to_string_test() {
+        assert_eq!(vec_u16_to_string(&[0x43, 0x3a, 0, 0x44]), "C:");
+        assert_eq!(vec_u16_to_string(&[0x43, 0x3a]), "C:");
+        assert_eq!(vec_u16_to_string(&[0xd83d, 0xdcbe, 0]), "\u{1f4be}");
+        assert_eq!(vec_u16_to_string(&[0xd83d, 0x41]), "\u{fffd}A");
+    }
+
+    #[test]
+    fn to_wide_null_test() {
+        assert_eq!(to_wide_null(std::path::Path::new("C:\\")).unwrap(), vec![0x43, 0x3a, 0x5c, 0]);
+        assert_eq!(to_wide_null("").unwrap(), vec![0]);
+        assert_eq!(to_wide_null("\u{1f4be}").unwrap(), vec![0xd83d, 0xdcbe, 0]);
+        assert_eq!(to_wide_null("\u{1f4be}\0C:\\"), Err(InteriorNulError { position: 2 }));
+    }
+}